@@ -1,44 +1,30 @@
-mod background;
 mod tests;
 
-use macroquad::experimental::animation::{AnimatedSprite, Animation};
+use macroquad::experimental::animation::Animation;
 use macroquad::prelude::*;
 use shared_v2::*;
 
 const ORIGINAL_SPRITE_SIZE: f32 = 48.0;
+const TILE: u32 = ORIGINAL_SPRITE_SIZE as u32;
 const GAME_SPRITE_SIZE: f32 = 48.0 * 2.0;
 const GROUND: f32 = 40.0;
+const ASSETS_FOLDER: &str = "./assets";
+
+const PLAYER_LAYER: u32 = 1 << 0;
+const ENEMY_LAYER: u32 = 1 << 1;
 
 pub const VIRTUAL_WIDTH: f32 = 800.0;
 pub const VIRTUAL_HEIGHT: f32 = 600.0;
 
-fn normalise_camera(screen_w: f32, screen_h: f32) {
-    let scale = (screen_w / VIRTUAL_WIDTH).min(0.1).floor();
-
-    let viewport_w = VIRTUAL_WIDTH * scale;
-    let viewport_h = VIRTUAL_HEIGHT * scale;
-
-    let offset_x = (screen_w - viewport_w) * 0.5;
-    let offset_y = (screen_h - viewport_h) * 0.5;
-
-    let mut camera = Camera2D::default();
-
-    camera.zoom = vec2(2.0 / viewport_w, -2.0 / viewport_h);
-    camera.target = vec2(viewport_w * 0.5, viewport_h * 0.5);
-    camera.offset = vec2(offset_x / scale, offset_y / scale);
-
-    set_camera(&camera);
-}
-
 fn gravity_engine(world: &mut World, _state: &mut GameState, input: &Input) {
-    for e in &mut world.entities {
+    for e in world.entities_mut() {
         let Some(ref mut physics) = e.physics else {
             continue;
         };
         const GRAVITY: f32 = 800.0;
         const JUMP_STRENGTH: f32 = 450.0;
 
-        if input.spacebar && physics.is_grounded {
+        if input.jump && physics.is_grounded {
             physics.velocity.y += JUMP_STRENGTH;
             physics.is_grounded = false;
         }
@@ -54,21 +40,22 @@ fn gravity_engine(world: &mut World, _state: &mut GameState, input: &Input) {
     }
 }
 
-fn render_sprites(world: &World, _state: &GameState) {
-    for entity in &world.entities {
+fn render_sprites(world: &World, state: &GameState, renderer: &mut dyn Renderer) {
+    for entity in world.entities() {
         let Some(ref player) = entity.sprite else {
             continue;
         };
         let frame = player.sprite.frame();
+        let transform = entity.interpolated(state.alpha);
         let direction = if entity.tag.is_some() && entity.tag.unwrap() == Tag::Enemy {
             -1.0
         } else {
             1.0
         };
-        draw_texture_ex(
-            &player.texture,
-            entity.transform.x + ORIGINAL_SPRITE_SIZE * direction,
-            entity.transform.y,
+        renderer.draw_texture_ex(
+            state.assets.get(player.texture),
+            transform.x + ORIGINAL_SPRITE_SIZE * direction,
+            transform.y,
             WHITE,
             DrawTextureParams {
                 dest_size: Some(frame.dest_size * 3.0),
@@ -81,146 +68,233 @@ fn render_sprites(world: &World, _state: &GameState) {
     }
 }
 
-fn update_sprites(world: &mut World) {
-    for entity in &mut world.entities {
-        let Some(ref mut player) = entity.sprite else {
-            continue;
-        };
-        let Some(ref mut physics) = entity.physics else {
-            player.sprite.update();
-            continue;
-        };
-        if physics.is_grounded {
-            player.sprite.update();
-        }
-    }
-}
-
+// Layers only let the player and enemies touch, so any contact is fatal.
 fn collision_system(world: &mut World, state: &mut GameState, _input: &Input) {
-    let len = world.entities.len();
-    for i in 0..len {
-        for j in i + 1..len {
-            let (a, b) = {
-                let (left, right) = world.entities.split_at_mut(j);
-                (&mut left[i], &mut right[0])
-            };
-
-            if a.tag.is_some()
-                && a.tag.unwrap() == Tag::Player
-                && a.transform.overlaps(&b.transform)
-            {
-                state.game_over = true;
+    if !world.overlapping_pairs().is_empty() {
+        state.camera.add_trauma(0.8);
+        for player in world.with_tag_mut(Tag::Player) {
+            if let Some(sprite) = &mut player.sprite {
+                sprite.play(DEATH);
             }
         }
+        state.push_scene(GAME_OVER);
     }
 }
 
-fn ui_system(_world: &World, state: &GameState) {
-    set_default_camera();
-
-    if state.game_over {
-        let text = "GAME OVER!";
-        let text_dimensions = measure_text(text, None, 50, 1.0);
-        let pos = Transform {
-            x: screen_width() / 2.0 - text_dimensions.width / 2.0,
-            y: screen_height() / 2.0 - text_dimensions.height / 2.0,
-        };
-        draw_text(text, pos.x, pos.y, 60.0, RED);
-    }
+fn replay_hint(_world: &World, state: &GameState, renderer: &mut dyn Renderer) {
+    let canvas = state.canvas.viewport();
+    renderer.draw_text(
+        "Press R to watch the last run",
+        canvas.x + 20.0,
+        canvas.bottom() - 20.0,
+        30.0,
+        WHITE,
+    );
 }
 
-fn move_enemy_system(world: &mut World, _state: &mut GameState, input: &Input) {
+fn move_enemy_system(world: &mut World, state: &mut GameState, input: &Input) {
     for e in world.with_tag_mut(Tag::Enemy) {
         e.transform.x -= 300.0 * input.dt;
         if e.transform.x < -GAME_SPRITE_SIZE {
-            e.transform.x += GAME_SPRITE_SIZE + VIRTUAL_WIDTH * rand::gen_range(1.0, 3.0);
+            let x =
+                e.transform.x + GAME_SPRITE_SIZE + VIRTUAL_WIDTH * state.rng.gen_range(1.0, 3.0);
+            e.teleport(x, e.transform.y);
         }
     }
 }
 
-fn load_player_sprite() -> AnimatedSprite {
-    AnimatedSprite::new(
-        ORIGINAL_SPRITE_SIZE as u32,
-        ORIGINAL_SPRITE_SIZE as u32,
-        &[Animation {
-            name: "step_3".to_string(),
-            row: 0,
-            frames: 6,
-            fps: 12,
-        }],
-        true,
-    )
+// Airborne, the boy holds his first stride rather than running on air.
+fn player_animator(walk: Handle<Texture>, death: Handle<Texture>) -> Animator {
+    let clip = |name: AnimationState, frames, fps| Animation {
+        name: name.to_string(),
+        row: 0,
+        frames,
+        fps,
+    };
+    Animator::new(RUN)
+        .with_clip(walk, clip(RUN, 6, 12), ClipEnd::Loop)
+        .with_clip(walk, clip(JUMP, 1, 1), ClipEnd::Loop)
+        .with_clip(death, clip(DEATH, 4, 8), ClipEnd::Hold)
 }
 
-fn load_enemy_sprite() -> AnimatedSprite {
-    AnimatedSprite::new(
-        ORIGINAL_SPRITE_SIZE as u32,
-        ORIGINAL_SPRITE_SIZE as u32,
-        &[Animation {
-            name: "imma_snake".to_string(),
+fn enemy_animator(walk: Handle<Texture>) -> Animator {
+    Animator::new(RUN).with_clip(
+        walk,
+        Animation {
+            name: RUN.to_string(),
             row: 0,
             frames: 4,
             fps: 12,
-        }],
-        true,
+        },
+        ClipEnd::Loop,
     )
 }
 
-async fn load_player(file_name: &str) -> Texture2D {
-    let texture: Texture2D = load_texture(file_name).await.expect("Couldn't load file");
-    texture.set_filter(FilterMode::Linear);
-    texture
+const BACKGROUND_LAYERS: [&str; 5] = ["1.png", "2.png", "3.png", "4.png", "5.png"];
+
+fn manifest() -> AssetManifest {
+    let manifest = AssetManifest::new()
+        .with_texture("boy_walk.png", FilterMode::Linear)
+        .with_texture("Boy_death.png", FilterMode::Linear)
+        .with_texture("snake_walk.png", FilterMode::Linear);
+    BACKGROUND_LAYERS.iter().fold(manifest, |manifest, path| {
+        manifest.with_texture(path, FilterMode::Nearest)
+    })
+}
+
+// Back to front, with each layer three times faster than the one behind.
+// The sky's edges don't meet cleanly, so its tiles overlap a little.
+fn background(assets: &AssetServer) -> Entity {
+    let parallax = BACKGROUND_LAYERS
+        .iter()
+        .zip([3.0, 9.0, 27.0, 81.0, 243.0])
+        .zip([4.0, 0.0, 0.0, 0.0, 0.0])
+        .fold(
+            Parallax::new().flipped(),
+            |parallax, ((path, speed), overlap)| {
+                let texture = texture(assets, path);
+                parallax.with_layer(
+                    ParallaxLayer::new(texture)
+                        .with_speed(speed, 0.0)
+                        .with_overlap(overlap),
+                )
+            },
+        );
+    Entity::new(Rect::new(0.0, 0.0, VIRTUAL_WIDTH, VIRTUAL_HEIGHT)).with_parallax(parallax)
 }
 
-#[macroquad::main("Death avoider")]
-async fn main() {
-    set_pc_assets_folder("./assets");
-    let mut para = background::load_background_assets().await;
-    let texture = load_player("boy_walk.png").await;
-    let sprite = load_player_sprite();
-    let enemy_texture = load_player("snake_walk.png").await;
-    let enemy_sprite = load_enemy_sprite();
-    build_textures_atlas();
-    let screen_w = screen_width();
-    let screen_h = screen_height();
+fn texture(assets: &AssetServer, path: &str) -> Handle<Texture> {
+    assets
+        .handle(path)
+        .unwrap_or_else(|| panic!("{path} isn't in the manifest"))
+}
 
-    let entity = Entity::new(Rect {
+fn player() -> Entity {
+    Entity::new(Rect {
         x: GAME_SPRITE_SIZE,
         y: GROUND,
         w: GAME_SPRITE_SIZE,
         h: GAME_SPRITE_SIZE,
     })
     .with_tag(Tag::Player)
+    .with_collider(CollisionLayers::new(PLAYER_LAYER, ENEMY_LAYER))
     .with_render(BLACK)
     .with_physics(Physics::new())
-    .with_sprite(texture, sprite);
+}
 
-    let enemy = Entity::new(Rect {
+fn enemy() -> Entity {
+    Entity::new(Rect {
         x: VIRTUAL_WIDTH * 2.0,
         y: GROUND,
         w: ORIGINAL_SPRITE_SIZE / 2.0,
         h: ORIGINAL_SPRITE_SIZE / 2.0,
     })
-    .with_sprite(enemy_texture, enemy_sprite)
-    .with_tag(Tag::Enemy);
+    .with_tag(Tag::Enemy)
+    .with_collider(CollisionLayers::new(ENEMY_LAYER, PLAYER_LAYER))
+}
 
-    let world = World::new().spawn(entity).spawn(enemy);
-    let mut game = Game::new(world)
-        .with_update_systems(vec![gravity_engine, move_enemy_system, collision_system])
-        .with_render_systems(vec![render_sprites, ui_system]);
+// Every run starts from a fresh world.
+fn start_run(world: &mut World, state: &mut GameState) {
+    let assets = &state.assets;
+    *world = World::new()
+        .with_entity(background(assets))
+        .with_entity(player().with_animator(
+            player_animator(
+                texture(assets, "boy_walk.png"),
+                texture(assets, "Boy_death.png"),
+            ),
+            TILE,
+            TILE,
+        ))
+        .with_entity(enemy().with_animator(
+            enemy_animator(texture(assets, "snake_walk.png")),
+            TILE,
+            TILE,
+        ));
+}
+
+fn build_game(assets: AssetServer, seed: u64) -> Game {
+    let playing = Scene::new()
+        .with_on_enter(start_run)
+        .with_update_systems(vec![
+            scroll_parallax,
+            gravity_engine,
+            move_enemy_system,
+            collision_system,
+        ])
+        .with_render_systems(vec![render_parallax, render_sprites]);
+    Game::new(World::new())
+        .with_canvas(
+            VirtualCanvas::new(VIRTUAL_WIDTH, VIRTUAL_HEIGHT).with_scaling(Scaling::Integer),
+        )
+        .with_assets(assets)
+        .with_seed(seed)
+        .with_update_system(camera_system)
+        .with_standard_flow(playing)
+        .with_scene(GAME_OVER, game_over_scene().with_render_system(replay_hint))
+}
+
+#[macroquad::main("Death avoider")]
+async fn main() {
+    set_pc_assets_folder(ASSETS_FOLDER);
+    let assets = load_assets(&manifest()).await;
+    let mut watcher = AssetWatcher::new(ASSETS_FOLDER, &manifest());
+    build_textures_atlas();
+
+    let mut game = build_game(assets, Rng::seed_from_clock()).with_recording();
+    let mut last_run: Option<Recording> = None;
+    let mut playback: Option<Playback> = None;
+    let mut renderer = MacroquadRenderer;
+    let mut focus = FocusWatcher::new(STALL_SECONDS);
+    let actions = ActionMap::new().with_storage(Box::new(LocalStorage::new("avoider")));
+    let mut pads = Gamepads::new(default_gamepads());
 
     loop {
+        watcher.reload_changed(&mut game.state.assets);
+        // Pulling out a pad mid-run pauses, the same as looking away.
+        let pad_lost = pads
+            .update()
+            .iter()
+            .any(|event| matches!(event, PadEvent::Disconnected(_)));
         let input = Input {
-            dt: get_frame_time(),
-            spacebar: is_key_pressed(KeyCode::Space),
+            focus_lost: focus.poll(get_frame_time()) || pad_lost,
+            ..actions.input(get_frame_time(), &(MacroquadInput, &pads))
         };
-        normalise_camera(screen_w, screen_h);
-        background::render_paralax_background(&mut para, game.state.game_over);
-        if !game.state.game_over {
-            game.update(&input);
-            update_sprites(&mut game.world);
+        let scene = game.scene();
+        match playback {
+            Some(ref mut run) if !run.is_finished() => run.advance(&mut game, get_frame_time()),
+            Some(_) => {
+                // Back to live play, recording again for the next retry.
+                playback = None;
+                game = game.with_recording();
+            }
+            None => game.fixed_update(get_frame_time(), &input),
+        }
+        // Still animating under game over, so the boy's death plays out.
+        if matches!(game.scene(), Some(PLAYING | GAME_OVER)) {
+            animate_sprites(&mut game.world, get_frame_time());
+        }
+        if game.scene() == Some(GAME_OVER) {
+            if scene != Some(GAME_OVER) && playback.is_none() {
+                last_run = game.recording.clone();
+            }
+            if let Some(ref recording) = last_run
+                && is_key_pressed(KeyCode::R)
+            {
+                let assets = std::mem::take(&mut game.state.assets);
+                game = build_game(assets, recording.seed);
+                playback = Some(Playback::new(recording, &mut game));
+            }
+        }
+        game.state
+            .canvas
+            .resize(vec2(screen_width(), screen_height()));
+        renderer.set_camera(&game.state.camera.view(&game.state.canvas));
+        game.render(&mut renderer);
+        if playback.is_some() {
+            let canvas = game.state.canvas.viewport();
+            renderer.draw_text("REPLAY", canvas.x + 20.0, canvas.y + 40.0, 40.0, WHITE);
         }
-        game.render();
         next_frame().await;
     }
 }
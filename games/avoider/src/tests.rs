@@ -1,5 +1,7 @@
 #[cfg(test)]
+#[allow(clippy::module_inception)]
 mod tests {
+    use macroquad::camera::Camera as _;
     use macroquad::math::Rect;
     use shared_v2::*;
 
@@ -8,38 +10,33 @@ mod tests {
     const EPS: f32 = 0.001;
 
     fn player_entity(y: f32, grounded: bool) -> Entity {
-        Entity {
-            transform: Rect {
-                x: 0.0,
-                y,
-                w: 10.0,
-                h: 10.0,
-            },
-            tag: Some(Tag::Player),
-            render: None,
-            sprite: None,
-            physics: Some(Physics {
-                is_grounded: grounded,
-                velocity: Velocity { x: 0.0, y: 0.0 },
-            }),
-        }
+        Entity::new(Rect {
+            x: 0.0,
+            y,
+            w: 10.0,
+            h: 10.0,
+        })
+        .with_tag(Tag::Player)
+        .with_physics(Physics {
+            is_grounded: grounded,
+            velocity: Velocity { x: 0.0, y: 0.0 },
+        })
     }
 
     #[test]
     fn gravity_applies_when_airborne() {
-        let mut world = World {
-            entities: vec![player_entity(GROUND + 100.0, false)],
-        };
+        let mut world = World::new().with_entity(player_entity(GROUND + 100.0, false));
 
         let mut state = GameState::new();
         let input = Input {
             dt: 0.016,
-            spacebar: false,
+            jump: false,
+            ..Default::default()
         };
 
         gravity_engine(&mut world, &mut state, &input);
 
-        let e = &world.entities[0];
+        let e = &world.entities()[0];
         let physics = e.physics.as_ref().unwrap();
 
         assert!(physics.velocity.y < 0.0);
@@ -48,19 +45,18 @@ mod tests {
 
     #[test]
     fn jump_sets_upward_velocity_when_grounded() {
-        let mut world = World {
-            entities: vec![player_entity(GROUND, true)],
-        };
+        let mut world = World::new().with_entity(player_entity(GROUND, true));
 
         let mut state = GameState::new();
         let input = Input {
             dt: 0.016,
-            spacebar: true,
+            jump: true,
+            ..Default::default()
         };
 
         gravity_engine(&mut world, &mut state, &input);
 
-        let physics = world.entities[0].physics.as_ref().unwrap();
+        let physics = world.entities()[0].physics.as_ref().unwrap();
 
         assert!(!physics.is_grounded);
         assert!(physics.velocity.y > 0.0);
@@ -68,19 +64,18 @@ mod tests {
 
     #[test]
     fn jump_does_not_trigger_midair() {
-        let mut world = World {
-            entities: vec![player_entity(GROUND + 50.0, false)],
-        };
+        let mut world = World::new().with_entity(player_entity(GROUND + 50.0, false));
 
         let mut state = GameState::new();
         let input = Input {
             dt: 0.016,
-            spacebar: true,
+            jump: true,
+            ..Default::default()
         };
 
         gravity_engine(&mut world, &mut state, &input);
 
-        let physics = world.entities[0].physics.as_ref().unwrap();
+        let physics = world.entities()[0].physics.as_ref().unwrap();
 
         // gravity applied, but no jump impulse
         assert!(physics.velocity.y < 0.0);
@@ -88,21 +83,268 @@ mod tests {
 
     #[test]
     fn entity_lands_and_resets_velocity() {
-        let mut world = World {
-            entities: vec![player_entity(GROUND - 1.0, false)],
-        };
+        let mut world = World::new().with_entity(player_entity(GROUND - 1.0, false));
 
         let mut state = GameState::new();
         let input = Input {
             dt: 0.016,
-            spacebar: false,
+            jump: false,
+            ..Default::default()
         };
 
         gravity_engine(&mut world, &mut state, &input);
 
-        let physics = world.entities[0].physics.as_ref().unwrap();
+        let physics = world.entities()[0].physics.as_ref().unwrap();
 
         assert!(physics.is_grounded);
         assert!((physics.velocity.y).abs() < EPS);
     }
+
+    fn enemy_entity(x: f32) -> Entity {
+        Entity::new(Rect {
+            x,
+            y: GROUND,
+            w: 10.0,
+            h: 10.0,
+        })
+        .with_tag(Tag::Enemy)
+    }
+
+    #[test]
+    fn enemy_respawn_is_reproducible_from_seed() {
+        fn respawn_positions(seed: u64) -> Vec<f32> {
+            let mut game = Game::new(World::new().with_entity(enemy_entity(-GAME_SPRITE_SIZE)))
+                .with_seed(seed)
+                .with_update_system(move_enemy_system);
+            let input = Input {
+                dt: 0.016,
+                jump: false,
+                ..Default::default()
+            };
+
+            (0..3)
+                .map(|_| {
+                    game.world.entities_mut()[0].teleport(-GAME_SPRITE_SIZE, GROUND);
+                    game.update(&input);
+                    game.world.entities()[0].transform.x
+                })
+                .collect()
+        }
+
+        assert_eq!(respawn_positions(42), respawn_positions(42));
+        assert_ne!(respawn_positions(42), respawn_positions(43));
+    }
+
+    fn headless_game(seed: u64) -> Game {
+        let world = World::new()
+            .with_entity(
+                player_entity(GROUND, true)
+                    .with_collider(CollisionLayers::new(PLAYER_LAYER, ENEMY_LAYER)),
+            )
+            .with_entity(
+                enemy_entity(VIRTUAL_WIDTH)
+                    .with_collider(CollisionLayers::new(ENEMY_LAYER, PLAYER_LAYER)),
+            );
+        Game::new(world).with_seed(seed).with_update_systems(vec![
+            gravity_engine,
+            move_enemy_system,
+            collision_system,
+        ])
+    }
+
+    #[test]
+    fn recorded_session_replays_to_same_outcome() {
+        let mut game = headless_game(1234).with_recording();
+        for tick in 0..600 {
+            if game.scene() == Some(GAME_OVER) {
+                break;
+            }
+            let input = Input {
+                dt: DEFAULT_STEP,
+                jump: tick % 45 == 0,
+                ..Default::default()
+            };
+            game.update(&input);
+        }
+        let recording = Recording::from_bytes(&game.recording.take().unwrap().to_bytes()).unwrap();
+
+        let mut replayed = headless_game(0);
+        recording.replay(&mut replayed);
+
+        assert_eq!(replayed.scene(), game.scene());
+        for (a, b) in replayed.world.entities().iter().zip(game.world.entities()) {
+            assert_eq!(a.transform, b.transform);
+        }
+    }
+
+    #[test]
+    fn boy_dies_when_caught() {
+        let texture = AssetServer::new().add("boy", placeholder_texture(1));
+        let mut world = World::new()
+            .with_entity(
+                player_entity(GROUND, true)
+                    .with_collider(CollisionLayers::new(PLAYER_LAYER, ENEMY_LAYER))
+                    .with_animator(player_animator(texture, texture), TILE, TILE),
+            )
+            .with_entity(
+                enemy_entity(0.0).with_collider(CollisionLayers::new(ENEMY_LAYER, PLAYER_LAYER)),
+            );
+        let mut state = GameState::new();
+
+        collision_system(&mut world, &mut state, &Input::default());
+        animate_sprites(&mut world, 10.0);
+
+        let animator = world.entities()[0]
+            .sprite
+            .as_ref()
+            .unwrap()
+            .animator
+            .as_ref();
+        assert_eq!(animator.unwrap().state(), DEATH);
+        assert!(animator.unwrap().is_finished());
+    }
+
+    #[test]
+    fn render_sprites_flips_and_offsets_by_direction() {
+        let mut state = GameState::new();
+        let player_texture = state.assets.add("player", placeholder_texture(1));
+        let enemy_texture = state.assets.add("enemy", placeholder_texture(2));
+        let world = World::new()
+            .with_entity(player_entity(GROUND, true).with_animator(
+                player_animator(player_texture, player_texture),
+                TILE,
+                TILE,
+            ))
+            .with_entity(enemy_entity(300.0).with_animator(
+                enemy_animator(enemy_texture),
+                TILE,
+                TILE,
+            ));
+        let mut renderer = RecordingRenderer::new();
+
+        render_sprites(&world, &state, &mut renderer);
+
+        let draws: Vec<_> = renderer.textures().collect();
+        assert_eq!(draws.len(), 2);
+
+        let DrawCall::Texture {
+            texture,
+            x,
+            y,
+            dest_size,
+            source,
+            flip_x,
+            flip_y,
+            ..
+        } = draws[0]
+        else {
+            unreachable!();
+        };
+        assert_eq!(texture, state.assets.get(player_texture));
+        assert_eq!((*x, *y), (ORIGINAL_SPRITE_SIZE, GROUND));
+        assert_eq!(
+            *dest_size,
+            Some(vec2(ORIGINAL_SPRITE_SIZE, ORIGINAL_SPRITE_SIZE) * 3.0)
+        );
+        assert_eq!(
+            *source,
+            Some(Rect::new(
+                0.0,
+                0.0,
+                ORIGINAL_SPRITE_SIZE,
+                ORIGINAL_SPRITE_SIZE
+            ))
+        );
+        assert!(!*flip_x);
+        assert!(*flip_y);
+
+        // Enemies face the other way, so they're shifted back rather than forward.
+        let DrawCall::Texture { texture, x, .. } = draws[1] else {
+            unreachable!();
+        };
+        assert_eq!(texture, state.assets.get(enemy_texture));
+        assert_eq!(*x, 300.0 - ORIGINAL_SPRITE_SIZE);
+    }
+
+    // What the game's canvas should produce for an 800x600 window: the whole
+    // virtual area at scale 1, y up.
+    fn virtual_camera() -> Camera2D {
+        Camera2D {
+            zoom: vec2(2.0 / VIRTUAL_WIDTH, -2.0 / VIRTUAL_HEIGHT),
+            target: vec2(VIRTUAL_WIDTH * 0.5, VIRTUAL_HEIGHT * 0.5),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn canvas_letterboxes_the_virtual_area_at_whole_scales() {
+        let mut canvas = build_game(AssetServer::new(), 1).state.canvas;
+
+        canvas.resize(vec2(VIRTUAL_WIDTH, VIRTUAL_HEIGHT));
+        assert_eq!(canvas.camera().matrix(), virtual_camera().matrix());
+
+        // Big enough for 2.4x across but only 1.8x down.
+        canvas.resize(vec2(1920.0, 1080.0));
+        assert_eq!(canvas.scale(), 1.0);
+        assert_eq!(canvas.viewport(), Rect::new(560.0, 240.0, 800.0, 600.0));
+
+        canvas.resize(vec2(1700.0, 1300.0));
+        assert_eq!(canvas.scale(), 2.0);
+        assert_eq!(
+            canvas.world_to_screen(vec2(0.0, GROUND)),
+            vec2(50.0, 1170.0)
+        );
+    }
+
+    /// Builds the real game with placeholder textures, runs `ticks` fixed
+    /// updates, jumping on the ticks in `jumps`, and renders the frame at a
+    /// quarter of the virtual resolution.
+    fn capture_frame(ticks: usize, jumps: &[usize]) -> Image {
+        let mut assets = AssetServer::new();
+        let mut renderer = ImageRenderer::new(VIRTUAL_WIDTH as u16 / 4, VIRTUAL_HEIGHT as u16 / 4);
+        for (id, entry) in manifest().entries.iter().enumerate() {
+            let texture = placeholder_texture(id as u32 + 1);
+            let png = format!("{}/assets/{}", env!("CARGO_MANIFEST_DIR"), entry.path);
+            renderer = renderer.with_texture(texture.clone(), load_png(png).unwrap());
+            assets.add(&entry.path, texture);
+        }
+
+        let mut game = build_game(assets, 7);
+        let script = Recording {
+            seed: 7,
+            inputs: (0..ticks)
+                .map(|tick| Input {
+                    dt: DEFAULT_STEP,
+                    jump: jumps.contains(&tick),
+                    ..Default::default()
+                })
+                .collect(),
+        };
+        script.replay(&mut game);
+
+        renderer.set_camera(&virtual_camera());
+        game.render(&mut renderer);
+        renderer.image().clone()
+    }
+
+    fn golden(name: &str) -> String {
+        format!("{}/golden/{name}.png", env!("CARGO_MANIFEST_DIR"))
+    }
+
+    const TOLERANCE: Tolerance = Tolerance {
+        channel: 2,
+        pixels: 16,
+    };
+
+    #[test]
+    fn first_frame_matches_golden() {
+        assert_golden(&capture_frame(0, &[]), golden("first_frame"), TOLERANCE);
+    }
+
+    // Covers the scrolled parallax, the jumping boy and the snake facing him,
+    // both drawn flipped and offset by their direction.
+    #[test]
+    fn mid_run_frame_matches_golden() {
+        assert_golden(&capture_frame(200, &[185]), golden("mid_run"), TOLERANCE);
+    }
 }
@@ -1,7 +1,15 @@
 use macroquad::prelude::*;
+use shared::{FixedStep, PauseOverlay, PauseRequest, Rng, StepInput, VirtualCanvas};
+use shared_v2::{
+    Action, ActionMap, AssetServer, FocusWatcher, Gamepads, LocalStorage, MacroquadInput,
+    MacroquadRenderer, PadButton, STALL_SECONDS, SceneFlow, TouchControls, default_gamepads,
+};
 
 const MOVEMENT_SPEED: f32 = 200.0;
 const RADIUS: f32 = 16.0;
+// The play area, whatever the window's size.
+const WIDTH: f32 = 800.0;
+const HEIGHT: f32 = 600.0;
 
 struct Shape {
     size: f32,
@@ -26,81 +34,138 @@ impl Shape {
     }
 }
 
+/// The player's input for one fixed update.
+#[derive(Debug, Clone, Copy)]
+struct Controls {
+    dt: f32,
+    movement: Vec2,
+    fire: bool,
+}
+
+impl StepInput for Controls {
+    fn with_dt(&self, dt: f32) -> Self {
+        Controls { dt, ..*self }
+    }
+
+    fn merge(&mut self, later: &Self) {
+        self.movement = later.movement;
+        self.fire |= later.fire;
+    }
+
+    fn consume_presses(&mut self) {
+        self.fire = false;
+    }
+}
+
 #[macroquad::main("My game")]
 async fn main() {
-    rand::srand(miniquad::date::now() as u64);
-    let mut gameover = false;
-    let screen_boundary_x = screen_width() - RADIUS;
-    let screen_boundary_y = screen_height() - RADIUS;
+    let mut rng = Rng::new(Rng::seed_from_clock());
+    let mut flow = SceneFlow::new();
+    let mut canvas = VirtualCanvas::new(WIDTH, HEIGHT).with_y_down();
+    let screen_boundary_x = WIDTH - RADIUS;
+    let screen_boundary_y = HEIGHT - RADIUS;
     let mut squares: Vec<Shape> = vec![];
     let mut bullets: Vec<Shape> = vec![];
     let mut circle = Shape {
         size: 32.0,
         speed: MOVEMENT_SPEED,
-        x: screen_width() / 2.0,
-        y: screen_height() / 2.0,
+        x: WIDTH / 2.0,
+        y: HEIGHT / 2.0,
         hit: false,
     };
 
+    let actions = ActionMap::new().with_storage(Box::new(LocalStorage::new("dodger")));
+    let mut pads = Gamepads::new(default_gamepads());
+    let mut touch = TouchControls::new().with_button(PadButton::West, "FIRE");
+    let mut clock = FixedStep::default();
+    let pause = PauseOverlay::new();
+    let mut focus = FocusWatcher::new(STALL_SECONDS);
+    // Text is drawn in the default font, so there's nothing to load.
+    let assets = AssetServer::new();
+
     loop {
         clear_background(DARKPURPLE);
+        canvas.resize(vec2(screen_width(), screen_height()));
+        set_camera(&canvas.camera());
+        pads.update();
+        touch.update(&touches(), vec2(screen_width(), screen_height()));
+        let devices = (MacroquadInput, (&pads, &touch));
+
+        let request = PauseRequest {
+            toggle: actions.is_pressed(Action::Pause, &devices),
+            resume: actions.is_pressed(Action::Confirm, &devices),
+            focus_lost: focus.poll(get_frame_time()),
+        };
+        let running = pause.update(&mut clock, request, flow.is_playing());
 
-        let delta_time = get_frame_time();
-        if !gameover {
-            if is_key_down(KeyCode::Right) {
-                circle.x += MOVEMENT_SPEED * delta_time;
-            }
-            if is_key_down(KeyCode::Left) {
-                circle.x -= MOVEMENT_SPEED * delta_time;
-            }
-            if is_key_down(KeyCode::Down) {
-                circle.y += MOVEMENT_SPEED * delta_time;
-            }
-            if is_key_down(KeyCode::Up) {
-                circle.y -= MOVEMENT_SPEED * delta_time;
-            }
-            if is_key_pressed(KeyCode::Space) {
-                bullets.push(Shape {
-                    x: circle.x,
-                    y: circle.y,
-                    speed: circle.speed * 2.0,
-                    size: 5.0,
-                    hit: false,
-                })
-            }
-            circle.x = clamp(circle.x, circle.size / 2.0, screen_boundary_x);
-            circle.y = clamp(circle.y, circle.size / 2.0, screen_boundary_y);
-
-            if rand::gen_range(0, 99) >= 95 {
-                let size = rand::gen_range(16.0, 64.0);
-                squares.push(Shape {
-                    size,
-                    speed: rand::gen_range(50.0, 150.0),
-                    x: rand::gen_range(size / 2.0, screen_width() - size / 2.0),
-                    y: -size,
-                    hit: false,
-                });
-            }
-            for square in &mut squares {
-                square.y += square.speed * delta_time;
-            }
-            for bullet in &mut bullets {
-                bullet.y -= bullet.speed * delta_time;
-            }
-            if squares.iter().any(|square| circle.collides_with(square)) {
-                gameover = true;
-            }
-            for bullet in bullets.iter_mut() {
-                for square in squares.iter_mut() {
-                    if bullet.collides_with(square) {
-                        bullet.hit = true;
-                        square.hit = true;
+        // The canvas's y grows downwards, the opposite of MoveY.
+        let controls = Controls {
+            dt: get_frame_time(),
+            movement: vec2(
+                actions.value(Action::MoveX, &devices),
+                -actions.value(Action::MoveY, &devices),
+            ),
+            fire: actions.is_pressed(Action::Fire, &devices),
+        };
+        if flow.is_playing() && running {
+            clock.advance(get_frame_time(), &controls, |controls| {
+                if !flow.is_playing() {
+                    return;
+                }
+                let delta_time = controls.dt;
+                let movement = controls.movement;
+                circle.x += movement.x * MOVEMENT_SPEED * delta_time;
+                circle.y += movement.y * MOVEMENT_SPEED * delta_time;
+                if controls.fire {
+                    bullets.push(Shape {
+                        x: circle.x,
+                        y: circle.y,
+                        speed: circle.speed * 2.0,
+                        size: 5.0,
+                        hit: false,
+                    })
+                }
+                circle.x = clamp(circle.x, circle.size / 2.0, screen_boundary_x);
+                circle.y = clamp(circle.y, circle.size / 2.0, screen_boundary_y);
+
+                if rng.gen_range(0, 99) >= 95 {
+                    let size = rng.gen_range(16.0, 64.0);
+                    squares.push(Shape {
+                        size,
+                        speed: rng.gen_range(50.0, 150.0),
+                        x: rng.gen_range(size / 2.0, WIDTH - size / 2.0),
+                        y: -size,
+                        hit: false,
+                    });
+                }
+                for square in &mut squares {
+                    square.y += square.speed * delta_time;
+                }
+                for bullet in &mut bullets {
+                    bullet.y -= bullet.speed * delta_time;
+                }
+                if squares.iter().any(|square| circle.collides_with(square)) {
+                    flow.game_over();
+                }
+                for bullet in bullets.iter_mut() {
+                    for square in squares.iter_mut() {
+                        if bullet.collides_with(square) {
+                            bullet.hit = true;
+                            square.hit = true;
+                        }
                     }
                 }
-            }
+            });
         }
 
-        squares.retain(|square| square.y < screen_height() + square.size);
+        if flow.update(actions.is_pressed(Action::Confirm, &devices)) {
+            squares.clear();
+            bullets.clear();
+            circle.x = WIDTH / 2.0;
+            circle.y = HEIGHT / 2.0;
+        }
+
+        squares.retain(|square| square.y < HEIGHT + square.size);
         bullets.retain(|bullet| bullet.y > 0.0);
 
         squares.retain(|square| !square.hit);
@@ -119,25 +184,12 @@ async fn main() {
             draw_circle(bullet.x, bullet.y, bullet.size, BLUE)
         }
         draw_circle(circle.x, circle.y, circle.size / 2.0, YELLOW);
+        canvas.render_letterbox(&mut MacroquadRenderer, BLACK);
+        touch.render(&mut MacroquadRenderer);
 
-        if gameover {
-            let text = "GAME OVER!";
-            let text_dimensions = measure_text(text, None, 50, 1.0);
-            draw_text(
-                text,
-                screen_width() / 2.0 - text_dimensions.width / 2.0,
-                screen_height() / 2.0 - text_dimensions.height / 2.0,
-                50.0,
-                RED,
-            );
-            if is_key_pressed(KeyCode::Space) {
-                squares.clear();
-                bullets.clear();
-                circle.x = screen_width() / 2.0;
-                circle.y = screen_height() / 2.0;
-                gameover = false;
-            }
-        }
+        pause.render(&clock, &canvas, &mut MacroquadRenderer);
+
+        flow.render(&canvas, &assets, &mut MacroquadRenderer);
 
         next_frame().await
     }
@@ -1,103 +1,150 @@
 use macroquad::prelude::*;
-use shared::{Entity, Input, Transform, World, render_text};
+use shared::{
+    Entity, FixedStep, Input, MacroquadRenderer, PauseOverlay, PauseRequest, Renderer, Rng,
+    VirtualCanvas, World,
+};
+use shared_v2::{
+    Action, ActionMap, AssetServer, FocusWatcher, Gamepads, HAlign, LocalStorage, MacroquadInput,
+    Parallax, ParallaxLayer, STALL_SECONDS, SceneFlow, TextStyle, VAlign, default_gamepads,
+    render_text,
+};
 
 const DEFAULT_SIZE: f32 = 64.0;
+// The play area, whatever the window's size.
+const WIDTH: f32 = 800.0;
+const HEIGHT: f32 = 600.0;
+// The strip the player runs along, below the entities.
+const GROUND: Rect = Rect {
+    x: -WIDTH / 2.0,
+    y: -100.0,
+    w: WIDTH,
+    h: 100.0,
+};
+// How fast the enemy runs at the player, and the ground scrolls by.
+const RUN_SPEED: f32 = WIDTH / 1.25;
+
+// Stripes of two blues, so the scrolling ground shows the player moving.
+fn ground_texture() -> Texture2D {
+    const STRIPES: u16 = 16;
+    let mut image = Image::gen_image_color(STRIPES, 1, BLUE);
+    for x in (1..STRIPES as u32).step_by(2) {
+        image.set_pixel(x, 0, DARKBLUE);
+    }
+    let texture = Texture2D::from_image(&image);
+    texture.set_filter(FilterMode::Nearest);
+    texture
+}
 
 #[macroquad::main("My game")]
 async fn main() {
-    let mut gameover = false;
-    let player_id = 1;
-    let enemy_id = 2;
-    let edge = screen_width() / 2.0;
+    let mut flow = SceneFlow::new();
+    let edge = WIDTH / 2.0;
 
-    let player_ent = Entity::new(player_id, -screen_width() / 4.0, 0.0);
+    let player_ent = Entity::new(-WIDTH / 4.0, 0.0);
     let enemy_ent = Entity::new(
-        enemy_id,
         // 3.0 is to add some padding so the user has time
         // to react at the start of the game
-        screen_width(),
-        0.0,
+        WIDTH, 0.0,
     );
 
     let mut world = World::new();
+    world.rng.reseed(Rng::seed_from_clock());
 
+    world.canvas = VirtualCanvas::new(WIDTH, HEIGHT);
     world.set_origin(0.0, 200.0);
-    world.spawn(player_ent.with_jump(350.0, 0.0).with_collide().with_render(
+    let player_id = world.spawn(player_ent.with_jump(350.0, 0.0).with_collide().with_render(
         DEFAULT_SIZE,
         DEFAULT_SIZE,
         YELLOW,
     ));
-    world.spawn(
+    let enemy_id = world.spawn(
         enemy_ent
             .with_collide()
-            // By making the speed a factor of screen width, the speed is proportional to the size
-            // of the screen
-            .with_move(-screen_width() / 1.25, 0.0)
+            // By making the speed a factor of the play area's width, the speed is proportional
+            // to its size
+            .with_move(-RUN_SPEED, 0.0)
             .with_render(DEFAULT_SIZE, DEFAULT_SIZE, RED),
     );
 
-    let time = get_frame_time();
+    let mut clock = FixedStep::default();
+    let pause = PauseOverlay::new();
+    let mut renderer = MacroquadRenderer;
     let mut score = 0.0;
+    // Text is drawn in the default font; the ground is built here.
+    let mut assets = AssetServer::new();
+    let mut ground = Parallax::new().with_layer(
+        ParallaxLayer::new(assets.add("ground", ground_texture())).with_speed(RUN_SPEED, 0.0),
+    );
+
+    let actions = ActionMap::new().with_storage(Box::new(LocalStorage::new("jumper")));
+    let mut pads = Gamepads::new(default_gamepads());
+    let mut focus = FocusWatcher::new(STALL_SECONDS);
 
     loop {
-        clear_background(DARKGREEN);
+        renderer.clear_background(DARKGREEN);
+        world.canvas.resize(vec2(screen_width(), screen_height()));
+        world.set_default_origin();
+        pads.update();
+        let devices = (MacroquadInput, &pads);
+
+        let request = PauseRequest {
+            toggle: actions.is_pressed(Action::Pause, &devices),
+            resume: actions.is_pressed(Action::Confirm, &devices),
+            focus_lost: focus.poll(get_frame_time()),
+        };
+        let running = pause.update(&mut clock, request, flow.is_playing());
 
-        if !gameover {
+        if flow.is_playing() && running {
             score += get_frame_time() * 100.0;
+            ground.scroll(get_frame_time());
+            ground.wrap(vec2(GROUND.w, GROUND.h));
             let input = Input {
-                dt: time,
-                is_jump: !gameover && is_key_pressed(KeyCode::Space),
+                dt: get_frame_time(),
+                is_jump: actions.is_pressed(Action::Jump, &devices),
             };
             let Some(ref c) = world.find(player_id).unwrap().collide else {
                 continue;
             };
 
             if c.is_collided {
-                gameover = true;
+                flow.game_over();
                 continue;
             }
 
-            world.update(&input);
+            world.fixed_update(&mut clock, get_frame_time(), &input);
 
             if world.find(enemy_id).unwrap().transform.x < -edge - DEFAULT_SIZE {
-                world.find_mut(enemy_id).unwrap().set_position(
-                    screen_width() + DEFAULT_SIZE * rand::gen_range(2, 10) as f32,
-                    0.0 + (DEFAULT_SIZE * 2.0) * rand::gen_range(0, 2) as f32,
-                );
+                let x = WIDTH + DEFAULT_SIZE * world.rng.gen_range(2, 10) as f32;
+                let y = 0.0 + (DEFAULT_SIZE * 2.0) * world.rng.gen_range(0, 2) as f32;
+                world.find_mut(enemy_id).unwrap().set_position(x, y);
             }
         }
 
-        world.render();
-        draw_rectangle(-screen_width() / 2.0, -100.0, screen_width(), 100.0, BLUE);
+        if flow.update(actions.is_pressed(Action::Confirm, &devices)) {
+            score = 0.0;
+            world.find_mut(player_id).unwrap().set_position(-100.0, 0.0);
+            world
+                .find_mut(enemy_id)
+                .unwrap()
+                .set_position(WIDTH + DEFAULT_SIZE, 0.0);
+            world.reset();
+        }
+
+        world.render_interpolated(clock.alpha(), &mut renderer);
+        ground.render(&assets, GROUND, &mut renderer);
+        world.canvas.render_letterbox(&mut renderer, BLACK);
 
-        let text = format!("{:.0}", score);
-        let text_dimensions = measure_text(&text, None, 50, 1.0);
-        let score_pos = Transform {
-            x: 10.0,
-            y: 10.0 + text_dimensions.height,
-        };
-        render_text(&mut world, &text, 40.0, &score_pos, WHITE);
-
-        if gameover {
-            let text = "GAME OVER!";
-            let text_dimensions = measure_text(text, None, 50, 1.0);
-            let pos = Transform {
-                x: screen_width() / 2.0 - text_dimensions.width / 2.0,
-                y: screen_height() / 2.0 - text_dimensions.height / 2.0,
-            };
+        render_text(
+            &format!("{score:.0}"),
+            vec2(10.0, 30.0),
+            &TextStyle::new(40.0).aligned(HAlign::Left, VAlign::Middle),
+            &assets,
+            &mut renderer,
+        );
 
-            render_text(&mut world, text, 50.0, &pos, RED);
-            if is_key_pressed(KeyCode::Space) {
-                score = 0.0;
-                gameover = false;
-                world.find_mut(player_id).unwrap().set_position(-100.0, 0.0);
-                world
-                    .find_mut(enemy_id)
-                    .unwrap()
-                    .set_position(screen_width() + DEFAULT_SIZE, 0.0);
-                world.reset();
-            }
-        }
+        pause.render(&clock, &world.canvas, &mut renderer);
+
+        flow.render(&world.canvas, &assets, &mut renderer);
 
         next_frame().await
     }
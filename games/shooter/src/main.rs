@@ -1,58 +1,228 @@
 use macroquad::experimental::animation::{AnimatedSprite, Animation};
 use macroquad::prelude::*;
-use shared::{Entity, GameState, Input, Transform, World, render_text};
+use shared::{
+    CollisionEvent, CollisionLayers, Entity, EntityId, FixedStep, Input, MacroquadRenderer,
+    Renderer, Rng, VirtualCanvas, World,
+};
+use shared_v2::{
+    Action, ActionMap, AnimationState, Animator, AssetManifest, AssetServer, AssetWatcher, Audio,
+    AudioBackend, Binding, Camera, ClipEnd, FocusWatcher, Gamepads, HAlign, Handle, IDLE,
+    LocalStorage, MacroquadInput, NineSlice, PadButton, PadEvent, STALL_SECONDS, SceneFlow, Shader,
+    Sound, TextStyle, Texture, TouchControls, Ui, UiInput, UiSkin, VAlign, default_gamepads,
+    load_assets, render_text,
+};
 
+const ASSETS_FOLDER: &str = "./assets";
 const MOVEMENT_SPEED: f32 = 100.0;
+const BULLET_SPEED: f32 = 300.0;
+// The ship is drawn mirrored, so the sheet's left bank turns right.
+const BANK_LEFT: AnimationState = "left";
+const BANK_RIGHT: AnimationState = "right";
+// The play area, centred on the origin, whatever the window's size.
+const WIDTH: f32 = 800.0;
+const HEIGHT: f32 = 600.0;
+
+const PLAYER_LAYER: u32 = 1 << 0;
+const ENEMY_LAYER: u32 = 1 << 1;
+const BULLET_LAYER: u32 = 1 << 2;
+
+fn manifest() -> AssetManifest {
+    AssetManifest::new()
+        .with_texture("ship.png", FilterMode::Linear)
+        .with_texture("enemy-small.png", FilterMode::Nearest)
+        .with_texture("window_background.png", FilterMode::Nearest)
+        .with_texture("button_background.png", FilterMode::Nearest)
+        .with_texture("button_clicked_background.png", FilterMode::Nearest)
+        .with_sound("8bit-spaceshooter.ogg")
+        .with_sound("laser.wav")
+        .with_sound("explosion.wav")
+        .with_font("atari_games.ttf")
+        .with_shader("starfield-shader.glsl")
+}
 
 fn create_user() -> Entity {
-    let mut entity = Entity::new(1, 0.0, 0.0).with_collide();
+    let mut entity =
+        Entity::new(0.0, 0.0).with_collide_layers(CollisionLayers::new(PLAYER_LAYER, ENEMY_LAYER));
     entity.set_dimensions(32.0, 48.0);
     entity
 }
 
+fn create_bullet(ship: &Rect) -> Entity {
+    let mut entity = Entity::new(ship.x + ship.w / 2.0 - 2.0, ship.y + ship.h)
+        .with_move(0.0, BULLET_SPEED)
+        .with_collide_layers(CollisionLayers::new(BULLET_LAYER, ENEMY_LAYER));
+    entity.set_dimensions(4.0, 12.0);
+    entity
+}
+
+fn is_layer(entity: &Entity, layer: u32) -> bool {
+    entity
+        .collide
+        .as_ref()
+        .is_some_and(|c| c.layers.layer == layer)
+}
+
+#[cfg(feature = "audio")]
+async fn audio_backend(assets: &AssetServer) -> Box<dyn AudioBackend> {
+    Box::new(shared_v2::MacroquadAudio::new(assets).await)
+}
+
+#[cfg(not(feature = "audio"))]
+async fn audio_backend(_assets: &AssetServer) -> Box<dyn AudioBackend> {
+    Box::new(shared_v2::NullAudio)
+}
+
+/// What the menu's callbacks can change.
+struct Session {
+    audio: Audio,
+    playing: bool,
+    /// Waiting for a key to bind to this action.
+    rebinding: Option<Action>,
+}
+
+fn menu_skin(assets: &AssetServer) -> UiSkin {
+    let slice = |path: &str, size: Vec2, border: RectOffset| {
+        NineSlice::new(assets.handle(path).unwrap(), size, border)
+    };
+    let button_border = RectOffset::new(10.0, 10.0, 10.0, 10.0);
+    UiSkin {
+        panel: Some(slice(
+            "window_background.png",
+            vec2(376.0, 312.0),
+            RectOffset::new(28.0, 28.0, 44.0, 28.0),
+        )),
+        button: Some(slice(
+            "button_background.png",
+            vec2(36.0, 36.0),
+            button_border,
+        )),
+        button_pressed: Some(slice(
+            "button_clicked_background.png",
+            vec2(36.0, 36.0),
+            button_border,
+        )),
+        font: assets.handle("atari_games.ttf"),
+        text_size: 16.0,
+        ..Default::default()
+    }
+}
+
+fn key_label(actions: &ActionMap, action: Action) -> String {
+    let key = actions
+        .bindings(action)
+        .iter()
+        .find(|(binding, _)| matches!(binding, Binding::Key(_)));
+    match key {
+        Some((binding, _)) => binding.to_string().to_uppercase(),
+        None => "NONE".to_string(),
+    }
+}
+
+/// The start and pause menu, centred on the screen.
+fn build_menu(assets: &AssetServer, session: &Session, actions: &ActionMap) -> Ui<Session> {
+    let window = Rect::new(
+        screen_width() / 2.0 - 188.0,
+        screen_height() / 2.0 - 186.0,
+        376.0,
+        372.0,
+    );
+    let row = |y: f32, h: f32| Rect::new(window.x + 40.0, window.y + y, window.w - 80.0, h);
+    let volumes = session.audio.volumes();
+    let fire = match session.rebinding {
+        Some(Action::Fire) => "FIRE: PRESS A KEY".to_string(),
+        _ => format!("FIRE: {}", key_label(actions, Action::Fire)),
+    };
+    Ui::<Session>::new(menu_skin(assets))
+        .with_panel(window)
+        .with_label(row(6.0, 32.0), "SHOOTER")
+        .with_button(row(60.0, 48.0), "PLAY", |s| s.playing = true)
+        .with_slider(row(124.0, 32.0), "MUSIC", volumes.music, |s, v| {
+            s.audio.set_music_volume(v)
+        })
+        .with_slider(row(172.0, 32.0), "EFFECTS", volumes.sfx, |s, v| {
+            s.audio.set_sfx_volume(v)
+        })
+        .with_toggle(row(220.0, 48.0), "MUTE", volumes.muted, |s, on| {
+            s.audio.set_muted(on)
+        })
+        .with_button(row(284.0, 48.0), &fire, |s| {
+            s.rebinding = Some(Action::Fire)
+        })
+}
+
+// Keeps the keyboard focus where it was, so rebinding doesn't lose the
+// player's place.
+fn rebuild_menu(
+    menu: &Ui<Session>,
+    assets: &AssetServer,
+    session: &Session,
+    actions: &ActionMap,
+) -> Ui<Session> {
+    let mut rebuilt = build_menu(assets, session, actions);
+    rebuilt.focus = menu.focus;
+    rebuilt
+}
+
+// Fills the window with drifting stars, behind everything else.
+fn draw_starfield(starfield: &Shader, renderer: &mut dyn Renderer) {
+    let material = &starfield.material;
+    material.set_uniform("iResolution", vec2(screen_width(), screen_height()));
+    material.set_uniform("direction_modifier", 0.0f32);
+    renderer.set_default_camera();
+    gl_use_material(material);
+    renderer.draw_rectangle(0.0, 0.0, screen_width(), screen_height(), WHITE);
+    gl_use_default_material();
+}
+
 #[macroquad::main("Shooter")]
 async fn main() {
     let mut world = World::new();
-    world.spawn(create_user());
-    set_pc_assets_folder("./assets");
+    world.canvas = VirtualCanvas::new(WIDTH, HEIGHT).with_target(0.0, 0.0);
+    let mut camera = Camera::new(Vec2::ZERO);
+    world.rng.reseed(Rng::seed_from_clock());
+    let mut player: EntityId = world.spawn(create_user());
+    let mut clock = FixedStep::default();
+    let mut renderer = MacroquadRenderer;
+    set_pc_assets_folder(ASSETS_FOLDER);
+    let mut assets = load_assets(&manifest()).await;
+    let mut watcher = AssetWatcher::new(ASSETS_FOLDER, &manifest());
     // Texture2D stores image data in GPU (Image uses the CPU)
-    let ship_texture: Texture2D = load_texture("ship.png").await.expect("Couldn't load file");
-    ship_texture.set_filter(FilterMode::Linear);
-    // let bullet_texture: Texture2D = load_texture("laser-bolts.png")
-    //     .await
-    //     .expect("Couldn't load file");
-    // bullet_texture.set_filter(FilterMode::Linear);
-    let enemy_small_texture: Texture2D = load_texture("enemy-small.png")
-        .await
-        .expect("Couldn't load file");
-    enemy_small_texture.set_filter(FilterMode::Nearest);
+    let ship_texture: Handle<Texture> = assets.handle("ship.png").unwrap();
+    let enemy_small_texture: Handle<Texture> = assets.handle("enemy-small.png").unwrap();
+    let music: Handle<Sound> = assets.handle("8bit-spaceshooter.ogg").unwrap();
+    let laser: Handle<Sound> = assets.handle("laser.wav").unwrap();
+    let explosion: Handle<Sound> = assets.handle("explosion.wav").unwrap();
+    let hud_font: Handle<Font> = assets.handle("atari_games.ttf").unwrap();
+    let starfield: Handle<Shader> = assets.handle("starfield-shader.glsl").unwrap();
+    let mut score: u32 = 0;
+    let mut session = Session {
+        audio: Audio::new(audio_backend(&assets).await)
+            .with_storage(Box::new(LocalStorage::new("shooter"))),
+        playing: false,
+        rebinding: None,
+    };
+    let mut actions = ActionMap::new().with_storage(Box::new(LocalStorage::new("shooter")));
+    let mut pads = Gamepads::new(default_gamepads());
+    let mut touch = TouchControls::new().with_button(PadButton::West, "FIRE");
+    session.audio.play_music(music);
+    let mut menu = build_menu(&assets, &session, &actions);
+    let mut focus = FocusWatcher::new(STALL_SECONDS);
+    // The menu is the title screen, so the flow goes straight into play.
+    let mut flow = SceneFlow::new();
+    flow.start();
     build_textures_atlas();
 
-    let mut ship_sprite = AnimatedSprite::new(
-        16,
-        24,
-        &[
-            Animation {
-                name: "idle".to_string(),
-                row: 0,
-                frames: 2,
-                fps: 12,
-            },
-            Animation {
-                name: "left".to_string(),
-                row: 2,
-                frames: 2,
-                fps: 12,
-            },
-            Animation {
-                name: "right".to_string(),
-                row: 4,
-                frames: 2,
-                fps: 12,
-            },
-        ],
-        true,
-    );
+    let clip = |name: AnimationState, row| Animation {
+        name: name.to_string(),
+        row,
+        frames: 2,
+        fps: 12,
+    };
+    let mut ship = Animator::new(IDLE)
+        .with_clip(ship_texture, clip(IDLE, 0), ClipEnd::Loop)
+        .with_clip(ship_texture, clip(BANK_LEFT, 2), ClipEnd::Loop)
+        .with_clip(ship_texture, clip(BANK_RIGHT, 4), ClipEnd::Loop);
+    let mut ship_sprite = ship.sprite(16, 24);
     let mut enemy_small_sprite = AnimatedSprite::new(
         17,
         16,
@@ -65,60 +235,125 @@ async fn main() {
         true,
     );
     loop {
-        clear_background(BLACK);
-        world.set_default_origin();
+        watcher.reload_changed(&mut assets);
+        renderer.clear_background(BLACK);
+        world.canvas.resize(vec2(screen_width(), screen_height()));
+        draw_starfield(assets.get(starfield), &mut renderer);
+        camera.update(get_frame_time(), None, &world.canvas);
+        renderer.set_camera(&camera.view(&world.canvas));
 
-        let delta_time = get_frame_time();
-        if world.state == GameState::Running {
+        // The menu has its own mute toggle, which this would leave stale.
+        if session.playing && is_key_pressed(KeyCode::M) {
+            session.audio.toggle_mute();
+        }
+        let pad_lost = pads
+            .update()
+            .iter()
+            .any(|event| matches!(event, PadEvent::Disconnected(_)));
+        touch.update(&touches(), vec2(screen_width(), screen_height()));
+        let devices = (MacroquadInput, (&pads, &touch));
+        let focus_lost = focus.poll(get_frame_time()) || pad_lost;
+        let pause = actions.is_pressed(Action::Pause, &devices);
+        if session.playing && (pause || focus_lost) {
+            session.playing = false;
+            menu = build_menu(&assets, &session, &actions);
+        }
+
+        if session.playing && flow.is_playing() {
             let input = Input {
-                dt: delta_time,
+                dt: get_frame_time(),
                 is_jump: false,
             };
-            world.new_update(&input);
-            ship_sprite.set_animation(0);
-            if is_key_down(KeyCode::Right) {
-                // direction_modifier += 0.05 * delta_time;
-                ship_sprite.set_animation(1);
-                world.find_mut(1).unwrap().transform.x += MOVEMENT_SPEED * delta_time;
-            }
-            if is_key_down(KeyCode::Left) {
-                ship_sprite.set_animation(2);
-                // direction_modifier -= 0.05 * delta_time;
-                world.find_mut(1).unwrap().transform.x -= MOVEMENT_SPEED * delta_time;
-            }
-            if is_key_down(KeyCode::Down) {
-                world.find_mut(1).unwrap().transform.y -= MOVEMENT_SPEED * delta_time;
-            }
-            if is_key_down(KeyCode::Up) {
-                world.find_mut(1).unwrap().transform.y += MOVEMENT_SPEED * delta_time;
-            }
-            if rand::gen_range(0, 99) >= 95 {
-                let mut entity = Entity::new(
-                    world.entities.len() as i32,
-                    rand::gen_range(-screen_width() / 2.0 + 32.0, screen_width() / 2.0 - 32.0),
-                    screen_width() / 2.0,
-                )
-                .with_move(0.0, -100.0)
-                .with_collide();
-                entity.set_dimensions(32.0, 32.0);
-                world.spawn(entity);
+            let move_x = actions.value(Action::MoveX, &devices);
+            let move_y = actions.value(Action::MoveY, &devices);
+
+            ship.play(if move_x > 0.0 {
+                BANK_LEFT
+            } else if move_x < 0.0 {
+                BANK_RIGHT
+            } else {
+                IDLE
+            });
+            if actions.is_pressed(Action::Fire, &devices) {
+                let bullet = create_bullet(&world.find(player).unwrap().transform);
+                world.spawn(bullet);
+                session.audio.play_sfx(laser);
             }
 
-            for i in 0..world.entities.len() - 1 {
-                if world.entities[i].id != 1 {
-                    if world.entities[i].transform.y < -screen_width() {
-                        world.despawn(world.entities[i].id);
+            clock.advance(get_frame_time(), &input, |input| {
+                if !flow.is_playing() {
+                    return;
+                }
+                world.new_update(input);
+                let started: Vec<CollisionEvent> = world
+                    .drain_collision_events()
+                    .filter(|e| matches!(e, CollisionEvent::Started(..)))
+                    .collect();
+                for event in started {
+                    if event.involves(player) {
+                        flow.game_over();
+                        session.audio.stop_music();
+                        session.audio.play_sfx(explosion);
+                        camera.add_trauma(1.0);
+                        return;
                     }
+                    // Only bullets and enemies are left to collide.
+                    let (a, b) = event.entities();
+                    world.despawn(a);
+                    world.despawn(b);
+                    session.audio.play_sfx(explosion);
+                    camera.add_trauma(0.3);
+                    score += 1;
                 }
-            }
+
+                let ship = world.find_mut(player).unwrap();
+                ship.transform.x += move_x * MOVEMENT_SPEED * input.dt;
+                ship.transform.y += move_y * MOVEMENT_SPEED * input.dt;
+                if world.rng.gen_range(0, 99) >= 95 {
+                    let mut entity = Entity::new(
+                        world.rng.gen_range(-WIDTH / 2.0 + 32.0, WIDTH / 2.0 - 32.0),
+                        WIDTH / 2.0,
+                    )
+                    .with_move(0.0, -100.0)
+                    .with_collide_layers(CollisionLayers::new(
+                        ENEMY_LAYER,
+                        PLAYER_LAYER | BULLET_LAYER,
+                    ));
+                    entity.set_dimensions(32.0, 32.0);
+                    world.commands.spawn(entity);
+                }
+
+                // Queued rather than despawned in place so removals can't shift
+                // entities under the loop; applied by the next `new_update`.
+                let (entities, commands) = world.entities_and_commands();
+                for enemy in entities.iter() {
+                    if enemy.id() != player && enemy.transform.y < -WIDTH {
+                        commands.despawn(enemy.id());
+                    }
+                }
+                for bullet in entities.iter().filter(|e| is_layer(e, BULLET_LAYER)) {
+                    if bullet.transform.y > WIDTH {
+                        commands.despawn(bullet.id());
+                    }
+                }
+            });
+        }
+
+        for bullet in world
+            .entities()
+            .iter()
+            .filter(|e| is_layer(e, BULLET_LAYER))
+        {
+            let rect = bullet.transform;
+            renderer.draw_rectangle(rect.x, rect.y, rect.w, rect.h, YELLOW);
         }
 
         let enemy_frame = enemy_small_sprite.frame();
-        for i in 1..world.entities.len() - 1 {
-            draw_texture_ex(
-                &enemy_small_texture,
-                world.entities[i].transform.x,
-                world.entities[i].transform.y + 32.0,
+        for enemy in world.entities().iter().filter(|e| is_layer(e, ENEMY_LAYER)) {
+            renderer.draw_texture_ex(
+                assets.get(enemy_small_texture),
+                enemy.transform.x,
+                enemy.transform.y + 32.0,
                 WHITE,
                 DrawTextureParams {
                     dest_size: Some(vec2(32.0, -32.0)),
@@ -128,29 +363,19 @@ async fn main() {
             )
         }
 
-        if world.state == GameState::GameOver {
-            let text = "GAME OVER!";
-            let text_dimensions = measure_text(text, None, 50, 1.0);
-            let pos = Transform {
-                x: screen_width() / 2.0 - text_dimensions.width / 2.0,
-                y: screen_height() / 2.0 - text_dimensions.height / 2.0,
-            };
-
-            render_text(&mut world, text, 50.0, &pos, RED);
-
-            if is_key_pressed(KeyCode::Space) {
-                world.entities = vec![];
-                world.spawn(create_user());
-                world.state = GameState::Running;
-                world.reset();
-            }
+        if flow.update(session.playing && actions.is_pressed(Action::Confirm, &devices)) {
+            score = 0;
+            world.clear();
+            player = world.spawn(create_user());
+            world.reset();
+            session.audio.play_music(music);
         }
 
         let ship_frame = ship_sprite.frame();
-        draw_texture_ex(
-            &ship_texture,
-            world.find_mut(1).unwrap().transform.x + ship_frame.dest_size.x * 2.0,
-            world.find_mut(1).unwrap().transform.y + ship_frame.dest_size.y * 2.0,
+        renderer.draw_texture_ex(
+            assets.get(ship_texture),
+            world.find_mut(player).unwrap().transform.x + ship_frame.dest_size.x * 2.0,
+            world.find_mut(player).unwrap().transform.y + ship_frame.dest_size.y * 2.0,
             WHITE,
             DrawTextureParams {
                 dest_size: Some(ship_frame.dest_size * -2.0),
@@ -159,8 +384,51 @@ async fn main() {
             },
         );
 
-        ship_sprite.update();
-        enemy_small_sprite.update();
+        world.canvas.render_letterbox(&mut renderer, BLACK);
+        let hud = TextStyle::new(24.0).with_font(hud_font);
+        render_text(
+            &format!("SCORE {score}"),
+            vec2(16.0, 16.0),
+            &hud.clone().aligned(HAlign::Left, VAlign::Middle),
+            &assets,
+            &mut renderer,
+        );
+        flow.render(&world.canvas, &assets, &mut renderer);
+        if session.playing {
+            touch.render(&mut renderer);
+        }
+
+        // Updated last so the click that closes the menu doesn't also reach
+        // the game this frame.
+        if !session.playing {
+            match session.rebinding {
+                // The menu waits, so the key being bound can't also press a
+                // button.
+                Some(action) => {
+                    if let Some(key) = get_last_key_pressed() {
+                        if key != KeyCode::Escape {
+                            actions.rebind(action, Binding::Key(key), 1.0);
+                        }
+                        session.rebinding = None;
+                        menu = rebuild_menu(&menu, &assets, &session, &actions);
+                    }
+                }
+                None => {
+                    let input = UiInput::from_macroquad().with_gamepads(&pads);
+                    menu.update(&input, &mut session);
+                    if session.rebinding.is_some() {
+                        menu = rebuild_menu(&menu, &assets, &session, &actions);
+                    }
+                }
+            }
+            menu.render(&assets, &mut renderer);
+        }
+
+        if session.playing {
+            ship.update(get_frame_time());
+            ship.apply(&mut ship_sprite);
+            enemy_small_sprite.update();
+        }
         next_frame().await
     }
 }
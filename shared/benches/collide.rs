@@ -0,0 +1,38 @@
+use criterion::{BenchmarkId, Criterion, criterion_group, criterion_main};
+use macroquad::{math::Rect, rand::RandGenerator};
+use shared::{SpatialHash, brute_force_pairs};
+
+// Roughly what the shooter looks like after a few minutes of play: lots of
+// 32x32 enemies spread over a screen-sized area.
+fn scattered_rects(n: usize) -> Vec<Rect> {
+    let rng = RandGenerator::new();
+    rng.srand(42);
+    (0..n)
+        .map(|_| {
+            Rect::new(
+                rng.gen_range(-640.0, 640.0),
+                rng.gen_range(-2000.0, 400.0),
+                32.0,
+                32.0,
+            )
+        })
+        .collect()
+}
+
+fn collide(c: &mut Criterion) {
+    let mut group = c.benchmark_group("collide");
+    for n in [50, 200, 1000] {
+        let rects = scattered_rects(n);
+        group.bench_with_input(BenchmarkId::new("brute_force", n), &rects, |b, rects| {
+            b.iter(|| brute_force_pairs(rects))
+        });
+        group.bench_with_input(BenchmarkId::new("spatial_hash", n), &rects, |b, rects| {
+            let mut hash = SpatialHash::default();
+            b.iter(|| hash.overlapping_pairs(rects))
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(benches, collide);
+criterion_main!(benches);
@@ -0,0 +1,116 @@
+use macroquad::math::Rect;
+
+/// Grid cell size used by the collision systems, roughly one sprite across.
+pub const DEFAULT_CELL_SIZE: f32 = 64.0;
+/// Rects touching more cells than this, or with non-finite edges, skip the
+/// grid and are checked against every other rect instead.
+pub const MAX_CELLS_PER_RECT: i64 = 256;
+
+/// Uniform grid broad phase.
+///
+/// Each rect is bucketed into every cell it touches, and only rects sharing a
+/// cell are tested against each other. Buckets are built by sorting
+/// `(cell, index)` entries rather than hashing. Rects too big for the grid
+/// go on an overflow list that is tested by brute force. The exact test is
+/// still [`Rect::overlaps`], so the result matches [`brute_force_pairs`].
+#[derive(Debug)]
+pub struct SpatialHash {
+    cell_size: f32,
+    entries: Vec<((i64, i64), usize)>,
+}
+
+impl Default for SpatialHash {
+    fn default() -> Self {
+        Self::new(DEFAULT_CELL_SIZE)
+    }
+}
+
+impl SpatialHash {
+    /// # Panics
+    ///
+    /// If `cell_size` is not a positive, finite number.
+    pub fn new(cell_size: f32) -> Self {
+        assert!(
+            cell_size > 0.0 && cell_size.is_finite(),
+            "cell size must be positive and finite, got {cell_size}"
+        );
+        Self {
+            cell_size,
+            entries: Vec::new(),
+        }
+    }
+
+    fn cell(&self, v: f32) -> i64 {
+        (v / self.cell_size).floor() as i64
+    }
+
+    /// Every pair `(i, j)` with `i < j` whose rects overlap, sorted.
+    pub fn overlapping_pairs(&mut self, rects: &[Rect]) -> Vec<(usize, usize)> {
+        self.entries.clear();
+        let mut overflow = Vec::new();
+
+        for (i, rect) in rects.iter().enumerate() {
+            // `Rect::overlaps` is inclusive, so a rect whose edge sits exactly
+            // on a cell boundary must be bucketed into both cells.
+            let x0 = self.cell(rect.x.min(rect.x + rect.w));
+            let x1 = self.cell(rect.x.max(rect.x + rect.w));
+            let y0 = self.cell(rect.y.min(rect.y + rect.h));
+            let y1 = self.cell(rect.y.max(rect.y + rect.h));
+
+            let finite = [rect.x, rect.y, rect.w, rect.h]
+                .iter()
+                .all(|v| v.is_finite());
+            let span = |a: i64, b: i64| b.saturating_sub(a).saturating_add(1);
+            let cells = span(x0, x1).saturating_mul(span(y0, y1));
+            if !finite || cells > MAX_CELLS_PER_RECT {
+                overflow.push(i);
+                continue;
+            }
+            for cx in x0..=x1 {
+                for cy in y0..=y1 {
+                    self.entries.push(((cx, cy), i));
+                }
+            }
+        }
+
+        // Sorting groups each cell's entries together, in index order.
+        self.entries.sort_unstable();
+        let mut pairs = Vec::new();
+        for bucket in self.entries.chunk_by(|a, b| a.0 == b.0) {
+            for (n, &(_, i)) in bucket.iter().enumerate() {
+                for &(_, j) in &bucket[n + 1..] {
+                    if rects[i].overlaps(&rects[j]) {
+                        pairs.push((i, j));
+                    }
+                }
+            }
+        }
+
+        for &i in &overflow {
+            for j in (0..rects.len()).filter(|&j| j != i) {
+                if rects[i].overlaps(&rects[j]) {
+                    pairs.push((i.min(j), i.max(j)));
+                }
+            }
+        }
+
+        // Rects spanning several cells show up once per shared cell, and
+        // pairs of overflowing rects once from each side.
+        pairs.sort_unstable();
+        pairs.dedup();
+        pairs
+    }
+}
+
+/// Reference O(n²) pass, kept for tests and benchmarks.
+pub fn brute_force_pairs(rects: &[Rect]) -> Vec<(usize, usize)> {
+    let mut pairs = Vec::new();
+    for i in 0..rects.len() {
+        for j in i + 1..rects.len() {
+            if rects[i].overlaps(&rects[j]) {
+                pairs.push((i, j));
+            }
+        }
+    }
+    pairs
+}
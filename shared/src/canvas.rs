@@ -0,0 +1,160 @@
+use macroquad::camera::Camera2D;
+use macroquad::color::Color;
+use macroquad::math::{Rect, Vec2, vec2};
+
+use crate::Renderer;
+
+/// How a [`VirtualCanvas`] grows to fill the window.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum Scaling {
+    /// Whole multiples only, so pixel art stays crisp. Windows smaller than
+    /// the canvas fall back to [`Scaling::Fractional`] rather than show
+    /// nothing.
+    Integer,
+    /// As large as fits.
+    #[default]
+    Fractional,
+}
+
+/// A fixed-size play area scaled into whatever window the game runs in, with
+/// bars filling the rest.
+///
+/// Gameplay works in canvas units, so a bigger window shows the same game
+/// bigger rather than more of it. World y points up, like [`crate::World`]'s
+/// camera, unless [`VirtualCanvas::y_down`] is set. Call
+/// [`VirtualCanvas::resize`] every frame with the window's size.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct VirtualCanvas {
+    pub size: Vec2,
+    pub scaling: Scaling,
+    /// The world point shown at the centre of the canvas.
+    pub target: Vec2,
+    /// Whether world y grows downwards, like screen pixels.
+    pub y_down: bool,
+    screen: Vec2,
+}
+
+impl Default for VirtualCanvas {
+    fn default() -> Self {
+        Self::new(800.0, 600.0)
+    }
+}
+
+impl VirtualCanvas {
+    /// A `width` by `height` canvas with the world origin at its bottom left
+    /// corner, in a window of the same size.
+    pub fn new(width: f32, height: f32) -> Self {
+        let size = vec2(width, height);
+        Self {
+            size,
+            scaling: Scaling::default(),
+            target: size / 2.0,
+            y_down: false,
+            screen: size,
+        }
+    }
+
+    pub fn with_scaling(mut self, scaling: Scaling) -> Self {
+        self.scaling = scaling;
+        self
+    }
+
+    pub fn with_target(mut self, x: f32, y: f32) -> Self {
+        self.target = vec2(x, y);
+        self
+    }
+
+    /// Makes world y grow downwards, with the origin at the top left corner.
+    pub fn with_y_down(mut self) -> Self {
+        self.y_down = true;
+        self
+    }
+
+    /// Fits the canvas to a window `screen` pixels in size, returning whether
+    /// that changed anything.
+    pub fn resize(&mut self, screen: Vec2) -> bool {
+        let changed = self.screen != screen;
+        self.screen = screen;
+        changed
+    }
+
+    /// The window size as of the last [`VirtualCanvas::resize`].
+    pub fn screen(&self) -> Vec2 {
+        self.screen
+    }
+
+    /// Screen pixels per canvas unit.
+    pub fn scale(&self) -> f32 {
+        let fit = (self.screen.x / self.size.x).min(self.screen.y / self.size.y);
+        match self.scaling {
+            Scaling::Integer if fit >= 1.0 => fit.floor(),
+            _ => fit,
+        }
+    }
+
+    /// Where the canvas lands on screen, in pixels. Centred, but kept on
+    /// whole pixels so integer scaling stays crisp.
+    pub fn viewport(&self) -> Rect {
+        let size = self.size * self.scale();
+        let corner = ((self.screen - size) / 2.0).floor();
+        Rect::new(corner.x, corner.y, size.x, size.y)
+    }
+
+    /// Draws the canvas into [`VirtualCanvas::viewport`].
+    pub fn camera(&self) -> Camera2D {
+        let scale = self.scale();
+        let centre = self.viewport().center();
+        let up = if self.y_down { 1.0 } else { -1.0 };
+        Camera2D {
+            target: self.target,
+            zoom: vec2(
+                2.0 * scale / self.screen.x,
+                up * 2.0 * scale / self.screen.y,
+            ),
+            // The viewport's centre in clip space, which is y up.
+            offset: vec2(
+                centre.x * 2.0 / self.screen.x - 1.0,
+                1.0 - centre.y * 2.0 / self.screen.y,
+            ),
+            ..Default::default()
+        }
+    }
+
+    /// The screen pixel showing world point `point`.
+    pub fn world_to_screen(&self, point: Vec2) -> Vec2 {
+        let offset = (point - self.target) * self.scale();
+        self.viewport().center() + self.flip(offset)
+    }
+
+    /// The world point under screen pixel `point`, such as the mouse.
+    pub fn screen_to_world(&self, point: Vec2) -> Vec2 {
+        let offset = (point - self.viewport().center()) / self.scale();
+        self.target + self.flip(offset)
+    }
+
+    // Between world and screen directions, which differ unless y is down.
+    fn flip(&self, offset: Vec2) -> Vec2 {
+        if self.y_down {
+            offset
+        } else {
+            vec2(offset.x, -offset.y)
+        }
+    }
+
+    /// Fills the screen outside [`VirtualCanvas::viewport`] with `color`.
+    /// Leaves the renderer on its default camera.
+    pub fn render_letterbox(&self, renderer: &mut dyn Renderer, color: Color) {
+        let view = self.viewport();
+        let screen = self.screen;
+        renderer.set_default_camera();
+        let bars = [
+            Rect::new(0.0, 0.0, screen.x, view.top()),
+            Rect::new(0.0, view.bottom(), screen.x, screen.y - view.bottom()),
+            Rect::new(0.0, view.top(), view.left(), view.h),
+            Rect::new(view.right(), view.top(), screen.x - view.right(), view.h),
+        ];
+        for bar in bars.iter().filter(|bar| bar.w > 0.0 && bar.h > 0.0) {
+            renderer.draw_rectangle(bar.x, bar.y, bar.w, bar.h, color);
+        }
+    }
+}
@@ -0,0 +1,41 @@
+use crate::{Entity, EntityId};
+
+/// Structural changes queued by systems and applied by [`crate::World::flush`].
+///
+/// Spawning or despawning while iterating `World::entities` shifts indices
+/// under the loop, so systems record what they want here instead.
+#[derive(Debug, Default)]
+pub struct Commands {
+    spawns: Vec<Entity>,
+    despawns: Vec<EntityId>,
+}
+
+impl Commands {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn spawn(&mut self, entity: Entity) {
+        self.spawns.push(entity);
+    }
+
+    pub fn despawn(&mut self, id: EntityId) {
+        self.despawns.push(id);
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.spawns.is_empty() && self.despawns.is_empty()
+    }
+
+    pub fn clear(&mut self) {
+        self.spawns.clear();
+        self.despawns.clear();
+    }
+
+    pub(crate) fn take(&mut self) -> (Vec<Entity>, Vec<EntityId>) {
+        (
+            std::mem::take(&mut self.spawns),
+            std::mem::take(&mut self.despawns),
+        )
+    }
+}
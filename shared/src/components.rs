@@ -26,9 +26,42 @@ pub struct Jump {
     pub velocity: Velocity,
 }
 
+/// Which collision layer an entity sits on and which layers it reacts to.
+///
+/// Two entities only collide when each one's mask includes the other's layer.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CollisionLayers {
+    pub layer: u32,
+    pub mask: u32,
+}
+
+impl CollisionLayers {
+    /// Layer 1, colliding with everything, which matches entities that
+    /// never opted into layers.
+    pub const DEFAULT: CollisionLayers = CollisionLayers {
+        layer: 1,
+        mask: u32::MAX,
+    };
+
+    pub fn new(layer: u32, mask: u32) -> Self {
+        Self { layer, mask }
+    }
+
+    pub fn interacts(&self, other: &CollisionLayers) -> bool {
+        self.layer & other.mask != 0 && other.layer & self.mask != 0
+    }
+}
+
+impl Default for CollisionLayers {
+    fn default() -> Self {
+        Self::DEFAULT
+    }
+}
+
 #[derive(Debug)]
 pub struct Collide {
     pub is_collided: bool,
+    pub layers: CollisionLayers,
 }
 
 #[derive(Debug)]
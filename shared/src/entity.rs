@@ -2,10 +2,23 @@ use macroquad::color::Color;
 
 use crate::*;
 
+/// Generational handle to an entity owned by a [`World`].
+///
+/// Handles are only ever created by [`World::spawn`]. Once the entity is
+/// despawned its slot may be reused, but the generation is bumped so stale
+/// handles stop resolving instead of pointing at the new occupant.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct EntityId {
+    pub(crate) index: u32,
+    pub(crate) generation: u32,
+}
+
 #[derive(Debug)]
 pub struct Entity {
-    pub id: i32,
+    pub(crate) id: EntityId,
     pub transform: Rect,
+    /// `transform` as of the start of the last update, for interpolation.
+    pub previous_transform: Rect,
     pub default_position: Transform,
 
     pub render: Option<components::Render>,
@@ -15,20 +28,16 @@ pub struct Entity {
 }
 
 impl Entity {
-    pub fn new(id: i32, x: f32, y: f32) -> Self {
+    pub fn new(x: f32, y: f32) -> Self {
         Self {
-            id,
+            id: EntityId::default(),
             jump: None,
             collide: None,
             render: None,
             movement: None,
             default_position: Transform { x, y },
-            transform: Rect {
-                x,
-                y,
-                w: 64.0,
-                h: 64.0,
-            },
+            transform: Rect::new(x, y, 64.0, 64.0),
+            previous_transform: Rect::new(x, y, 64.0, 64.0),
         }
     }
 
@@ -37,12 +46,30 @@ impl Entity {
             return false;
         };
         println!("Hi {}", c.is_collided);
-        return c.is_collided;
+        c.is_collided
+    }
+
+    /// The handle assigned by [`World::spawn`].
+    pub fn id(&self) -> EntityId {
+        self.id
     }
 
     pub fn set_dimensions(&mut self, w: f32, h: f32) {
         self.transform.w = w;
         self.transform.h = h;
+        self.previous_transform.w = w;
+        self.previous_transform.h = h;
+    }
+
+    /// The transform blended between the previous and current update.
+    pub fn interpolated(&self, alpha: f32) -> Rect {
+        let (a, b) = (self.previous_transform, self.transform);
+        Rect {
+            x: a.x + (b.x - a.x) * alpha,
+            y: a.y + (b.y - a.y) * alpha,
+            w: b.w,
+            h: b.h,
+        }
     }
 
     pub fn reset(&mut self) {
@@ -50,13 +77,15 @@ impl Entity {
             return;
         };
         c.is_collided = false;
-        self.transform.x = self.default_position.x;
-        self.transform.y = self.default_position.y;
+        self.set_position(self.default_position.x, self.default_position.y);
     }
 
+    /// Moves the entity without interpolating from its old position, for
+    /// wrap-arounds and respawns.
     pub fn set_position(&mut self, x: f32, y: f32) {
         self.transform.x = x;
         self.transform.y = y;
+        self.previous_transform = self.transform;
     }
 
     pub fn with_jump(mut self, force: f32, ground_level: f32) -> Self {
@@ -70,8 +99,15 @@ impl Entity {
         self
     }
 
-    pub fn with_collide(mut self) -> Self {
-        self.collide = Some(components::Collide { is_collided: false });
+    pub fn with_collide(self) -> Self {
+        self.with_collide_layers(CollisionLayers::DEFAULT)
+    }
+
+    pub fn with_collide_layers(mut self, layers: CollisionLayers) -> Self {
+        self.collide = Some(components::Collide {
+            is_collided: false,
+            layers,
+        });
         self
     }
 
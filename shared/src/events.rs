@@ -0,0 +1,38 @@
+use crate::EntityId;
+
+/// A change in contact between two colliding entities, produced by
+/// [`crate::World::collide_system`].
+///
+/// The pair is ordered by handle, so the same two entities always come out
+/// the same way round.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CollisionEvent {
+    /// The pair started overlapping this pass.
+    Started(EntityId, EntityId),
+    /// The pair was already overlapping last pass and still is.
+    Ongoing(EntityId, EntityId),
+    /// The pair stopped overlapping, or one of them was despawned.
+    Ended(EntityId, EntityId),
+}
+
+impl CollisionEvent {
+    pub fn entities(&self) -> (EntityId, EntityId) {
+        match *self {
+            Self::Started(a, b) | Self::Ongoing(a, b) | Self::Ended(a, b) => (a, b),
+        }
+    }
+
+    pub fn involves(&self, id: EntityId) -> bool {
+        let (a, b) = self.entities();
+        a == id || b == id
+    }
+
+    /// The entity on the other side of the contact, if `id` is part of it.
+    pub fn other(&self, id: EntityId) -> Option<EntityId> {
+        match self.entities() {
+            (a, b) if a == id => Some(b),
+            (a, b) if b == id => Some(a),
+            _ => None,
+        }
+    }
+}
@@ -1,22 +1,38 @@
+mod broadphase;
+mod canvas;
+mod commands;
 mod components;
 mod entity;
+mod events;
+mod render;
+mod rng;
+#[cfg(all(feature = "snapshot", not(target_arch = "wasm32")))]
+mod snapshot;
 mod systems;
 mod tests;
-mod ui;
+mod time;
 mod utils;
 
+pub use crate::broadphase::*;
+pub use crate::canvas::*;
+pub use crate::commands::*;
 pub use crate::components::*;
 pub use crate::entity::*;
+pub use crate::events::*;
+pub use crate::render::*;
+pub use crate::rng::*;
+#[cfg(all(feature = "snapshot", not(target_arch = "wasm32")))]
+pub use crate::snapshot::*;
 pub use crate::systems::*;
-pub use crate::ui::*;
+pub use crate::time::*;
 pub use crate::utils::*;
 
+use std::collections::BTreeSet;
+
 use macroquad::camera::Camera2D;
 use macroquad::camera::set_camera;
 use macroquad::math::Rect;
 use macroquad::math::vec2;
-use macroquad::window::screen_height;
-use macroquad::window::screen_width;
 
 #[derive(Debug, PartialEq)]
 pub enum GameState {
@@ -24,18 +40,49 @@ pub enum GameState {
     GameOver,
 }
 
+/// Bookkeeping for one entry in the [`World`] slot map.
+#[derive(Debug)]
+struct Slot {
+    generation: u32,
+    // Index into `World::entities` while the slot is occupied.
+    dense: Option<usize>,
+}
+
 pub struct World {
-    pub entities: Vec<Entity>,
-    pub origin: Transform,
+    entities: Vec<Entity>,
+    slots: Vec<Slot>,
+    free: Vec<u32>,
+    /// What the camera shows. Gameplay sizes things from this rather than
+    /// the window.
+    pub canvas: VirtualCanvas,
     pub state: GameState,
+    /// Spawns and despawns deferred until the next [`World::flush`].
+    pub commands: Commands,
+    /// Randomness for game logic, so runs can be replayed from a seed.
+    pub rng: Rng,
+    // Pairs overlapping as of the last `collide_system` pass.
+    contacts: BTreeSet<(EntityId, EntityId)>,
+    collision_events: Vec<CollisionEvent>,
+}
+
+impl Default for World {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 impl World {
     pub fn new() -> Self {
         Self {
-            origin: Transform { x: 0.0, y: 0.0 },
+            canvas: VirtualCanvas::default().with_target(0.0, 0.0),
             entities: Vec::new(),
+            slots: Vec::new(),
+            free: Vec::new(),
             state: GameState::Running,
+            commands: Commands::new(),
+            rng: Rng::default(),
+            contacts: BTreeSet::new(),
+            collision_events: Vec::new(),
         }
     }
 
@@ -43,82 +90,183 @@ impl World {
         self.entities.iter_mut().for_each(|e| e.reset());
     }
 
-    pub fn despawn(&mut self, id: i32) {
-        let index = self.entities.iter().position(|x| x.id == id).unwrap();
-        self.entities.remove(index);
+    /// Live entities, densely packed. Order is not stable across despawns.
+    pub fn entities(&self) -> &[Entity] {
+        &self.entities
     }
 
-    // Add pixels_per_unit: f32
-    pub fn set_origin(&mut self, origin_x: f32, origin_y: f32) {
-        let mut camera = Camera2D::default();
+    pub fn entities_mut(&mut self) -> &mut [Entity] {
+        &mut self.entities
+    }
+
+    /// Borrows the entities alongside the command buffer, so a system can
+    /// walk the world and queue changes in the same pass.
+    pub fn entities_and_commands(&mut self) -> (&mut [Entity], &mut Commands) {
+        (&mut self.entities, &mut self.commands)
+    }
+
+    /// Applies queued despawns, then queued spawns. Stale handles queued for
+    /// despawn are ignored.
+    pub fn flush(&mut self) {
+        let (spawns, despawns) = self.commands.take();
+        for id in despawns {
+            self.despawn(id);
+        }
+        for entity in spawns {
+            self.spawn(entity);
+        }
+    }
+
+    /// Removes the entity behind `id`, returning it, or `None` if the handle
+    /// is stale or was never spawned in this world.
+    pub fn despawn(&mut self, id: EntityId) -> Option<Entity> {
+        let index = self.dense_index(id)?;
+        let slot = &mut self.slots[id.index as usize];
+        slot.dense = None;
+        slot.generation = slot.generation.wrapping_add(1);
+        self.free.push(id.index);
+
+        let entity = self.entities.swap_remove(index);
+        if let Some(moved) = self.entities.get(index) {
+            self.slots[moved.id.index as usize].dense = Some(index);
+        }
+        Some(entity)
+    }
+
+    /// Despawns every entity, invalidating all outstanding handles and
+    /// dropping any queued commands and collision state.
+    pub fn clear(&mut self) {
+        self.commands.clear();
+        self.contacts.clear();
+        self.collision_events.clear();
+        for entity in self.entities.drain(..) {
+            let slot = &mut self.slots[entity.id.index as usize];
+            slot.dense = None;
+            slot.generation = slot.generation.wrapping_add(1);
+            self.free.push(entity.id.index);
+        }
+    }
 
-        camera.target = vec2(origin_x, origin_y);
-        // Add (screen_width() / pixels_per_unit)
-        camera.zoom = vec2(2.0 / screen_width(), -2.0 / screen_height());
-        self.origin.x = origin_x;
-        self.origin.y = origin_y;
-        set_camera(&camera);
+    /// Camera looking at the world origin through [`World::canvas`].
+    pub fn camera(&self) -> Camera2D {
+        self.canvas.camera()
+    }
+
+    pub fn set_origin(&mut self, origin_x: f32, origin_y: f32) {
+        self.canvas.target = vec2(origin_x, origin_y);
+        set_camera(&self.camera());
     }
 
     pub fn set_default_origin(&mut self) {
-        self.set_origin(self.origin.x, self.origin.y);
+        self.set_origin(self.canvas.target.x, self.canvas.target.y);
+    }
+
+    fn dense_index(&self, id: EntityId) -> Option<usize> {
+        let slot = self.slots.get(id.index as usize)?;
+        if slot.generation != id.generation {
+            return None;
+        }
+        slot.dense
     }
 
-    pub fn find(&self, id: i32) -> Option<&Entity> {
-        self.entities.iter().find(|ent| id == ent.id)
+    pub fn find(&self, id: EntityId) -> Option<&Entity> {
+        let index = self.dense_index(id)?;
+        Some(&self.entities[index])
     }
 
-    pub fn find_mut(&mut self, id: i32) -> Option<&mut Entity> {
-        self.entities.iter_mut().find(|ent| id == ent.id)
+    pub fn find_mut(&mut self, id: EntityId) -> Option<&mut Entity> {
+        let index = self.dense_index(id)?;
+        Some(&mut self.entities[index])
     }
 
-    pub fn spawn(&mut self, entity: Entity) {
+    /// Adds `entity` to the world and returns the handle allocated for it.
+    pub fn spawn(&mut self, mut entity: Entity) -> EntityId {
+        let index = match self.free.pop() {
+            Some(index) => index,
+            None => {
+                self.slots.push(Slot {
+                    generation: 0,
+                    dense: None,
+                });
+                (self.slots.len() - 1) as u32
+            }
+        };
+        let slot = &mut self.slots[index as usize];
+        slot.dense = Some(self.entities.len());
+
+        let id = EntityId {
+            index,
+            generation: slot.generation,
+        };
+        entity.id = id;
         self.entities.push(entity);
+        id
+    }
+
+    pub fn render(&self, renderer: &mut dyn Renderer) {
+        render_system(&self.entities, renderer);
     }
 
-    pub fn render(&mut self) {
-        render_system(&mut self.entities);
+    /// Draws the world `alpha` of the way between the last two updates,
+    /// usually [`FixedStep::alpha`].
+    pub fn render_interpolated(&self, alpha: f32, renderer: &mut dyn Renderer) {
+        interpolated_render_system(&self.entities, alpha, renderer);
     }
 
+    fn store_previous_transforms(&mut self) {
+        for entity in &mut self.entities {
+            entity.previous_transform = entity.transform;
+        }
+    }
+
+    /// Updates `is_collided` flags and replaces the pending collision events
+    /// with the contact changes since the previous pass.
     pub fn collide_system(&mut self) {
-        let len = self.entities.len();
-
-        for i in 0..len {
-            for j in i + 1..len {
-                let (a, b) = {
-                    let (left, right) = self.entities.split_at_mut(j);
-                    (&mut left[i], &mut right[0])
-                };
-
-                let Some(ref mut col_1) = a.collide else {
-                    continue;
-                };
-                let Some(ref mut col_2) = b.collide else {
-                    continue;
-                };
-                col_1.is_collided = false;
-                col_2.is_collided = false;
-
-                if a.transform.overlaps(&b.transform) {
-                    if a.id == 1 || b.id == 1 {
-                        self.state = GameState::GameOver;
-                    }
-                    col_1.is_collided = true;
-                    col_2.is_collided = true;
-                }
-            }
+        let pairs = collision_pairs(&self.entities);
+        mark_collided(&mut self.entities, &pairs);
+
+        let contacts: BTreeSet<(EntityId, EntityId)> = pairs
+            .iter()
+            .map(|&(a, b)| {
+                let (a, b) = (self.entities[a].id, self.entities[b].id);
+                (a.min(b), a.max(b))
+            })
+            .collect();
+
+        self.collision_events.clear();
+        for &(a, b) in &contacts {
+            self.collision_events
+                .push(if self.contacts.contains(&(a, b)) {
+                    CollisionEvent::Ongoing(a, b)
+                } else {
+                    CollisionEvent::Started(a, b)
+                });
+        }
+        for &(a, b) in self.contacts.difference(&contacts) {
+            self.collision_events.push(CollisionEvent::Ended(a, b));
         }
+        self.contacts = contacts;
+    }
+
+    /// Events from the latest [`World::collide_system`] pass. Anything not
+    /// drained is discarded by the next pass.
+    pub fn drain_collision_events(&mut self) -> std::vec::Drain<'_, CollisionEvent> {
+        self.collision_events.drain(..)
     }
 
     pub fn update(&mut self, input: &components::Input) {
+        self.store_previous_transforms();
         collide_system(&mut self.entities);
         jump_system(&mut self.entities, input);
         movement_system(&mut self.entities, input);
+        self.flush();
     }
 
     pub fn new_update(&mut self, input: &components::Input) {
+        self.store_previous_transforms();
         self.collide_system();
         jump_system(&mut self.entities, input);
         movement_system(&mut self.entities, input);
+        self.flush();
     }
 }
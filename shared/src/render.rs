@@ -0,0 +1,307 @@
+use macroquad::camera::{self, Camera, Camera2D};
+use macroquad::color::Color;
+use macroquad::math::{Mat4, Rect, Vec2};
+use macroquad::miniquad::{RawId, TextureId};
+use macroquad::text::{Font, TextDimensions, TextParams};
+use macroquad::texture::{DrawTextureParams, Texture2D};
+use macroquad::{shapes, text, texture, window};
+
+/// Everything the games draw goes through this, so render systems can run
+/// against a real window or be recorded as data in tests.
+pub trait Renderer {
+    fn set_camera(&mut self, camera: &Camera2D);
+    fn set_default_camera(&mut self);
+    fn clear_background(&mut self, color: Color);
+    fn draw_rectangle(&mut self, x: f32, y: f32, w: f32, h: f32, color: Color);
+    fn draw_rectangle_lines(
+        &mut self,
+        x: f32,
+        y: f32,
+        w: f32,
+        h: f32,
+        thickness: f32,
+        color: Color,
+    );
+    /// A filled circle centred on `(x, y)`.
+    fn draw_circle(&mut self, x: f32, y: f32, radius: f32, color: Color);
+    fn draw_texture_ex(
+        &mut self,
+        texture: &Texture2D,
+        x: f32,
+        y: f32,
+        color: Color,
+        params: DrawTextureParams,
+    );
+    fn draw_text(&mut self, text: &str, x: f32, y: f32, font_size: f32, color: Color);
+    /// Like [`Renderer::draw_text`], in `font` or the default font if `None`.
+    fn draw_text_ex(
+        &mut self,
+        text: &str,
+        x: f32,
+        y: f32,
+        font: Option<&Font>,
+        font_size: f32,
+        color: Color,
+    );
+    /// How big `text` comes out when drawn by this renderer.
+    fn measure_text(&self, text: &str, font: Option<&Font>, font_size: f32) -> TextDimensions;
+}
+
+/// Text metrics for renderers that don't rasterise glyphs: every character
+/// is half as wide as `font_size`, with the baseline three quarters down.
+///
+/// Fonts need a window to measure, so this keeps text layout testable.
+pub fn fixed_text_size(text: &str, font_size: f32) -> TextDimensions {
+    TextDimensions {
+        width: text.chars().count() as f32 * font_size * 0.5,
+        height: font_size,
+        offset_y: font_size * 0.75,
+    }
+}
+
+/// Draws straight to the macroquad window.
+#[derive(Debug, Default)]
+pub struct MacroquadRenderer;
+
+impl Renderer for MacroquadRenderer {
+    fn set_camera(&mut self, camera: &Camera2D) {
+        camera::set_camera(camera);
+    }
+
+    fn set_default_camera(&mut self) {
+        camera::set_default_camera();
+    }
+
+    fn clear_background(&mut self, color: Color) {
+        window::clear_background(color);
+    }
+
+    fn draw_rectangle(&mut self, x: f32, y: f32, w: f32, h: f32, color: Color) {
+        shapes::draw_rectangle(x, y, w, h, color);
+    }
+
+    fn draw_rectangle_lines(
+        &mut self,
+        x: f32,
+        y: f32,
+        w: f32,
+        h: f32,
+        thickness: f32,
+        color: Color,
+    ) {
+        shapes::draw_rectangle_lines(x, y, w, h, thickness, color);
+    }
+
+    fn draw_circle(&mut self, x: f32, y: f32, radius: f32, color: Color) {
+        shapes::draw_circle(x, y, radius, color);
+    }
+
+    fn draw_texture_ex(
+        &mut self,
+        texture: &Texture2D,
+        x: f32,
+        y: f32,
+        color: Color,
+        params: DrawTextureParams,
+    ) {
+        texture::draw_texture_ex(texture, x, y, color, params);
+    }
+
+    fn draw_text(&mut self, text: &str, x: f32, y: f32, font_size: f32, color: Color) {
+        text::draw_text(text, x, y, font_size, color);
+    }
+
+    fn draw_text_ex(
+        &mut self,
+        text: &str,
+        x: f32,
+        y: f32,
+        font: Option<&Font>,
+        font_size: f32,
+        color: Color,
+    ) {
+        text::draw_text_ex(
+            text,
+            x,
+            y,
+            TextParams {
+                font,
+                font_size: font_size as u16,
+                color,
+                ..Default::default()
+            },
+        );
+    }
+
+    fn measure_text(&self, text: &str, font: Option<&Font>, font_size: f32) -> TextDimensions {
+        text::measure_text(text, font, font_size as u16, 1.0)
+    }
+}
+
+/// One call made on a [`RecordingRenderer`].
+#[derive(Debug, Clone, PartialEq)]
+pub enum DrawCall {
+    /// The camera's view matrix.
+    SetCamera(Mat4),
+    SetDefaultCamera,
+    Clear(Color),
+    Rectangle {
+        rect: Rect,
+        color: Color,
+    },
+    RectangleLines {
+        rect: Rect,
+        thickness: f32,
+        color: Color,
+    },
+    Circle {
+        centre: Vec2,
+        radius: f32,
+        color: Color,
+    },
+    Texture {
+        texture: Texture2D,
+        x: f32,
+        y: f32,
+        color: Color,
+        dest_size: Option<Vec2>,
+        source: Option<Rect>,
+        rotation: f32,
+        flip_x: bool,
+        flip_y: bool,
+        pivot: Option<Vec2>,
+    },
+    Text {
+        text: String,
+        x: f32,
+        y: f32,
+        /// Whether a font was given, rather than the default.
+        custom_font: bool,
+        font_size: f32,
+        color: Color,
+    },
+}
+
+/// Captures draw calls instead of drawing, so tests can assert on exactly
+/// what a render system drew and where. Needs no window.
+#[derive(Debug, Default)]
+pub struct RecordingRenderer {
+    pub calls: Vec<DrawCall>,
+}
+
+impl RecordingRenderer {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Only the texture draws, which is what most sprite tests care about.
+    pub fn textures(&self) -> impl Iterator<Item = &DrawCall> {
+        self.calls
+            .iter()
+            .filter(|c| matches!(c, DrawCall::Texture { .. }))
+    }
+}
+
+impl Renderer for RecordingRenderer {
+    fn set_camera(&mut self, camera: &Camera2D) {
+        self.calls.push(DrawCall::SetCamera(camera.matrix()));
+    }
+
+    fn set_default_camera(&mut self) {
+        self.calls.push(DrawCall::SetDefaultCamera);
+    }
+
+    fn clear_background(&mut self, color: Color) {
+        self.calls.push(DrawCall::Clear(color));
+    }
+
+    fn draw_rectangle(&mut self, x: f32, y: f32, w: f32, h: f32, color: Color) {
+        self.calls.push(DrawCall::Rectangle {
+            rect: Rect::new(x, y, w, h),
+            color,
+        });
+    }
+
+    fn draw_rectangle_lines(
+        &mut self,
+        x: f32,
+        y: f32,
+        w: f32,
+        h: f32,
+        thickness: f32,
+        color: Color,
+    ) {
+        self.calls.push(DrawCall::RectangleLines {
+            rect: Rect::new(x, y, w, h),
+            thickness,
+            color,
+        });
+    }
+
+    fn draw_circle(&mut self, x: f32, y: f32, radius: f32, color: Color) {
+        self.calls.push(DrawCall::Circle {
+            centre: Vec2::new(x, y),
+            radius,
+            color,
+        });
+    }
+
+    fn draw_texture_ex(
+        &mut self,
+        texture: &Texture2D,
+        x: f32,
+        y: f32,
+        color: Color,
+        params: DrawTextureParams,
+    ) {
+        self.calls.push(DrawCall::Texture {
+            texture: texture.clone(),
+            x,
+            y,
+            color,
+            dest_size: params.dest_size,
+            source: params.source,
+            rotation: params.rotation,
+            flip_x: params.flip_x,
+            flip_y: params.flip_y,
+            pivot: params.pivot,
+        });
+    }
+
+    fn draw_text(&mut self, text: &str, x: f32, y: f32, font_size: f32, color: Color) {
+        self.draw_text_ex(text, x, y, None, font_size, color);
+    }
+
+    fn draw_text_ex(
+        &mut self,
+        text: &str,
+        x: f32,
+        y: f32,
+        font: Option<&Font>,
+        font_size: f32,
+        color: Color,
+    ) {
+        self.calls.push(DrawCall::Text {
+            text: text.to_string(),
+            x,
+            y,
+            custom_font: font.is_some(),
+            font_size,
+            color,
+        });
+    }
+
+    /// Uses [`fixed_text_size`] whatever the font.
+    fn measure_text(&self, text: &str, _font: Option<&Font>, font_size: f32) -> TextDimensions {
+        fixed_text_size(text, font_size)
+    }
+}
+
+/// A texture that never touches the GPU, distinguished only by `id`.
+///
+/// Lets entities carrying sprites be built and rendered into a
+/// [`RecordingRenderer`] without a window. Drawing it with the real renderer
+/// is undefined.
+pub fn placeholder_texture(id: u32) -> Texture2D {
+    Texture2D::from_miniquad_texture(TextureId::from_raw_id(RawId::OpenGl(id)))
+}
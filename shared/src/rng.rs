@@ -0,0 +1,51 @@
+use macroquad::rand::{RandGenerator, RandomRange};
+
+/// Seedable random number generator owned by a game.
+///
+/// Game logic draws from this rather than macroquad's global `rand`, so a
+/// seed plus an input sequence always plays out the same way.
+pub struct Rng {
+    seed: u64,
+    inner: RandGenerator,
+}
+
+impl Rng {
+    pub fn new(seed: u64) -> Self {
+        let inner = RandGenerator::new();
+        inner.srand(seed);
+        Self { seed, inner }
+    }
+
+    /// A seed that differs from run to run, for normal play.
+    pub fn seed_from_clock() -> u64 {
+        macroquad::miniquad::date::now() as u64
+    }
+
+    /// The seed this generator was last started from.
+    pub fn seed(&self) -> u64 {
+        self.seed
+    }
+
+    /// Restarts the sequence from `seed`.
+    pub fn reseed(&mut self, seed: u64) {
+        self.seed = seed;
+        self.inner.srand(seed);
+    }
+
+    /// A value in `low..high`.
+    pub fn gen_range<T: RandomRange>(&mut self, low: T, high: T) -> T {
+        self.inner.gen_range(low, high)
+    }
+}
+
+impl Default for Rng {
+    fn default() -> Self {
+        Self::new(0)
+    }
+}
+
+impl std::fmt::Debug for Rng {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Rng").field("seed", &self.seed).finish()
+    }
+}
@@ -0,0 +1,322 @@
+use std::fmt;
+use std::path::Path;
+
+use macroquad::camera::{Camera, Camera2D};
+use macroquad::color::Color;
+use macroquad::math::{Mat4, Vec2, vec2, vec3};
+use macroquad::text::{Font, TextDimensions};
+use macroquad::texture::{DrawTextureParams, Image, Texture2D};
+
+use crate::{Renderer, fixed_text_size};
+
+/// Set to regenerate golden images instead of comparing against them.
+pub const UPDATE_GOLDEN_ENV: &str = "UPDATE_GOLDEN";
+
+/// Rasterises draw calls into an [`Image`] on the CPU, so a frame can be
+/// captured and compared in tests without a window or GPU.
+///
+/// Follows macroquad's camera and texture maths, including flips, but keeps
+/// to what the games use: rotation is ignored, sampling is nearest-neighbour
+/// and text isn't drawn, since fonts need a GL context.
+pub struct ImageRenderer {
+    image: Image,
+    view: Mat4,
+    // Textures are opaque GPU handles, so their pixels are registered here.
+    textures: Vec<(Texture2D, Image)>,
+}
+
+impl ImageRenderer {
+    pub fn new(width: u16, height: u16) -> Self {
+        let mut renderer = Self {
+            image: Image::gen_image_color(width, height, Color::new(0.0, 0.0, 0.0, 1.0)),
+            view: Mat4::IDENTITY,
+            textures: vec![],
+        };
+        renderer.set_default_camera();
+        renderer
+    }
+
+    /// Uses `image` as the pixels of `texture` when it's drawn, typically a
+    /// [`crate::placeholder_texture`] paired with the PNG it stands in for.
+    pub fn with_texture(mut self, texture: Texture2D, image: Image) -> Self {
+        self.textures.push((texture, image));
+        self
+    }
+
+    pub fn image(&self) -> &Image {
+        &self.image
+    }
+
+    fn to_pixel(&self, point: Vec2) -> Vec2 {
+        let ndc = self.view.project_point3(vec3(point.x, point.y, 0.0));
+        vec2(
+            (ndc.x + 1.0) * 0.5 * self.image.width as f32,
+            (1.0 - ndc.y) * 0.5 * self.image.height as f32,
+        )
+    }
+
+    /// Fills the quad `(x, y, w, h)` in world space. `shade` gets the
+    /// position within the quad, each axis from 0 to 1, and returns the colour
+    /// to blend in there. Negative sizes are allowed and mirror the quad.
+    fn fill(&mut self, x: f32, y: f32, w: f32, h: f32, shade: impl Fn(f32, f32) -> Option<Color>) {
+        if w == 0.0 || h == 0.0 {
+            return;
+        }
+        let a = self.to_pixel(vec2(x, y));
+        let b = self.to_pixel(vec2(x + w, y + h));
+        let (min, max) = (a.min(b), a.max(b));
+        let x0 = min.x.round().max(0.0) as u32;
+        let y0 = min.y.round().max(0.0) as u32;
+        let x1 = (max.x.round().max(0.0) as u32).min(self.image.width as u32);
+        let y1 = (max.y.round().max(0.0) as u32).min(self.image.height as u32);
+
+        // Axis-aligned, so each pixel maps back linearly between the corners.
+        for py in y0..y1 {
+            let v = (py as f32 + 0.5 - a.y) / (b.y - a.y);
+            for px in x0..x1 {
+                let u = (px as f32 + 0.5 - a.x) / (b.x - a.x);
+                if let Some(color) = shade(u, v) {
+                    let below = self.image.get_pixel(px, py);
+                    self.image.set_pixel(px, py, blend(color, below));
+                }
+            }
+        }
+    }
+}
+
+fn blend(src: Color, dst: Color) -> Color {
+    let inv = 1.0 - src.a;
+    Color::new(
+        src.r * src.a + dst.r * inv,
+        src.g * src.a + dst.g * inv,
+        src.b * src.a + dst.b * inv,
+        src.a + dst.a * inv,
+    )
+}
+
+fn tint(a: Color, b: Color) -> Color {
+    Color::new(a.r * b.r, a.g * b.g, a.b * b.b, a.a * b.a)
+}
+
+impl Renderer for ImageRenderer {
+    fn set_camera(&mut self, camera: &Camera2D) {
+        self.view = camera.matrix();
+    }
+
+    fn set_default_camera(&mut self) {
+        let (w, h) = (self.image.width as f32, self.image.height as f32);
+        self.view = Mat4::orthographic_rh_gl(0.0, w, h, 0.0, -1.0, 1.0);
+    }
+
+    fn clear_background(&mut self, color: Color) {
+        let (w, h) = (self.image.width, self.image.height);
+        self.image = Image::gen_image_color(w, h, color);
+    }
+
+    fn draw_rectangle(&mut self, x: f32, y: f32, w: f32, h: f32, color: Color) {
+        self.fill(x, y, w, h, |_, _| Some(color));
+    }
+
+    fn draw_rectangle_lines(
+        &mut self,
+        x: f32,
+        y: f32,
+        w: f32,
+        h: f32,
+        thickness: f32,
+        color: Color,
+    ) {
+        self.fill(x, y, w, thickness, |_, _| Some(color));
+        self.fill(x, y + h - thickness, w, thickness, |_, _| Some(color));
+        self.fill(x, y, thickness, h, |_, _| Some(color));
+        self.fill(x + w - thickness, y, thickness, h, |_, _| Some(color));
+    }
+
+    fn draw_circle(&mut self, x: f32, y: f32, radius: f32, color: Color) {
+        let size = radius * 2.0;
+        self.fill(x - radius, y - radius, size, size, |u, v| {
+            let inside = (u - 0.5).powi(2) + (v - 0.5).powi(2) <= 0.25;
+            inside.then_some(color)
+        });
+    }
+
+    fn draw_texture_ex(
+        &mut self,
+        texture: &Texture2D,
+        x: f32,
+        y: f32,
+        color: Color,
+        params: DrawTextureParams,
+    ) {
+        let Some(index) = self.textures.iter().position(|(t, _)| t == texture) else {
+            panic!("no image registered for {texture:?}");
+        };
+        // Taken out while drawing so `fill` can borrow the target mutably.
+        let (handle, pixels) = self.textures.swap_remove(index);
+
+        let source = params.source.unwrap_or(macroquad::math::Rect::new(
+            0.0,
+            0.0,
+            pixels.width as f32,
+            pixels.height as f32,
+        ));
+        let size = params.dest_size.unwrap_or(source.size());
+        let (mut x, mut y, mut w, mut h) = (x, y, size.x, size.y);
+        // Same as macroquad: a flip mirrors the quad in place.
+        if params.flip_x {
+            x += w;
+            w = -w;
+        }
+        if params.flip_y {
+            y += h;
+            h = -h;
+        }
+
+        self.fill(x, y, w, h, |u, v| {
+            let tx = (source.x + u * source.w).floor() as i64;
+            let ty = (source.y + v * source.h).floor() as i64;
+            if tx < 0 || ty < 0 || tx >= pixels.width as i64 || ty >= pixels.height as i64 {
+                return None;
+            }
+            Some(tint(pixels.get_pixel(tx as u32, ty as u32), color))
+        });
+
+        self.textures.push((handle, pixels));
+    }
+
+    fn draw_text(&mut self, _text: &str, _x: f32, _y: f32, _font_size: f32, _color: Color) {}
+
+    fn draw_text_ex(
+        &mut self,
+        _text: &str,
+        _x: f32,
+        _y: f32,
+        _font: Option<&Font>,
+        _font_size: f32,
+        _color: Color,
+    ) {
+    }
+
+    /// Uses [`fixed_text_size`] whatever the font.
+    fn measure_text(&self, text: &str, _font: Option<&Font>, font_size: f32) -> TextDimensions {
+        fixed_text_size(text, font_size)
+    }
+}
+
+/// How far a captured frame may drift from its golden image and still pass.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Tolerance {
+    /// Largest difference allowed in any one colour channel, out of 255.
+    pub channel: u8,
+    /// How many pixels may exceed `channel` before the frames differ.
+    pub pixels: usize,
+}
+
+impl Tolerance {
+    pub const EXACT: Tolerance = Tolerance {
+        channel: 0,
+        pixels: 0,
+    };
+}
+
+#[derive(Debug, PartialEq)]
+pub enum SnapshotDiff {
+    Size {
+        expected: (u16, u16),
+        actual: (u16, u16),
+    },
+    /// `pixels` pixels differ by more than the channel tolerance.
+    Pixels { pixels: usize },
+}
+
+impl fmt::Display for SnapshotDiff {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SnapshotDiff::Size { expected, actual } => {
+                write!(f, "expected a {expected:?} frame, got {actual:?}")
+            }
+            SnapshotDiff::Pixels { pixels } => write!(f, "{pixels} pixels differ"),
+        }
+    }
+}
+
+/// Compares two frames, returning how they differ beyond `tolerance`.
+pub fn compare_images(
+    expected: &Image,
+    actual: &Image,
+    tolerance: Tolerance,
+) -> Result<(), SnapshotDiff> {
+    if (expected.width, expected.height) != (actual.width, actual.height) {
+        return Err(SnapshotDiff::Size {
+            expected: (expected.width, expected.height),
+            actual: (actual.width, actual.height),
+        });
+    }
+    let pixels = expected
+        .get_image_data()
+        .iter()
+        .zip(actual.get_image_data())
+        .filter(|(a, b)| {
+            a.iter()
+                .zip(*b)
+                .any(|(a, b)| a.abs_diff(*b) > tolerance.channel)
+        })
+        .count();
+    if pixels > tolerance.pixels {
+        return Err(SnapshotDiff::Pixels { pixels });
+    }
+    Ok(())
+}
+
+pub fn load_png(path: impl AsRef<Path>) -> image::ImageResult<Image> {
+    let decoded = image::open(path)?.to_rgba8();
+    Ok(Image {
+        width: decoded.width() as u16,
+        height: decoded.height() as u16,
+        bytes: decoded.into_raw(),
+    })
+}
+
+pub fn save_png(image: &Image, path: impl AsRef<Path>) -> image::ImageResult<()> {
+    image::save_buffer(
+        path,
+        &image.bytes,
+        image.width as u32,
+        image.height as u32,
+        image::ColorType::Rgba8,
+    )
+}
+
+/// Fails the test if `actual` doesn't match the PNG at `golden`.
+///
+/// On a mismatch the captured frame is written next to the golden as
+/// `<name>.actual.png` for inspection. Run with `UPDATE_GOLDEN=1` to accept
+/// the new frame, which also creates goldens that don't exist yet.
+#[track_caller]
+pub fn assert_golden(actual: &Image, golden: impl AsRef<Path>, tolerance: Tolerance) {
+    let golden = golden.as_ref();
+    if std::env::var_os(UPDATE_GOLDEN_ENV).is_some() {
+        if let Some(dir) = golden.parent() {
+            std::fs::create_dir_all(dir).expect("couldn't create golden image directory");
+        }
+        save_png(actual, golden).expect("couldn't write golden image");
+        return;
+    }
+
+    let expected = match load_png(golden) {
+        Ok(expected) => expected,
+        Err(e) => panic!(
+            "couldn't read golden image {}: {e}; run with {UPDATE_GOLDEN_ENV}=1 to create it",
+            golden.display()
+        ),
+    };
+    if let Err(diff) = compare_images(&expected, actual, tolerance) {
+        let captured = golden.with_extension("actual.png");
+        save_png(actual, &captured).expect("couldn't write captured image");
+        panic!(
+            "frame doesn't match {}: {diff}; captured frame written to {}",
+            golden.display(),
+            captured.display()
+        );
+    }
+}
@@ -1,5 +1,3 @@
-use macroquad::shapes::draw_rectangle;
-
 use crate::*;
 
 pub fn movement_system(entities: &mut [Entity], input: &components::Input) {
@@ -16,42 +14,66 @@ pub fn movement_system(entities: &mut [Entity], input: &components::Input) {
     }
 }
 
-pub fn collide_system(entities: &mut [Entity]) {
-    let len = entities.len();
-
-    for i in 0..len {
-        for j in i + 1..len {
-            let (a, b) = {
-                let (left, right) = entities.split_at_mut(j);
-                (&mut left[i], &mut right[0])
-            };
+/// Indices of every pair of entities with a `Collide` component whose
+/// transforms overlap and whose layers interact, sorted.
+pub fn collision_pairs(entities: &[Entity]) -> Vec<(usize, usize)> {
+    let colliders: Vec<usize> = entities
+        .iter()
+        .enumerate()
+        .filter(|(_, e)| e.collide.is_some())
+        .map(|(i, _)| i)
+        .collect();
+    let rects: Vec<Rect> = colliders.iter().map(|&i| entities[i].transform).collect();
 
-            let Some(ref mut col_1) = a.collide else {
-                continue;
-            };
-            let Some(ref mut col_2) = b.collide else {
-                continue;
+    SpatialHash::default()
+        .overlapping_pairs(&rects)
+        .into_iter()
+        .map(|(a, b)| (colliders[a], colliders[b]))
+        .filter(|&(a, b)| {
+            let (Some(a), Some(b)) = (&entities[a].collide, &entities[b].collide) else {
+                return false;
             };
-            col_1.is_collided = false;
-            col_2.is_collided = false;
+            a.layers.interacts(&b.layers)
+        })
+        .collect()
+}
 
-            if a.transform.overlaps(&b.transform) {
-                col_1.is_collided = true;
-                col_2.is_collided = true;
+/// Sets `is_collided` on every entity that appears in `pairs` and clears it
+/// everywhere else.
+pub fn mark_collided(entities: &mut [Entity], pairs: &[(usize, usize)]) {
+    for collide in entities.iter_mut().filter_map(|e| e.collide.as_mut()) {
+        collide.is_collided = false;
+    }
+    for &(a, b) in pairs {
+        for i in [a, b] {
+            if let Some(ref mut collide) = entities[i].collide {
+                collide.is_collided = true;
             }
         }
     }
 }
 
-pub fn render_system(entities: &[Entity]) {
+pub fn collide_system(entities: &mut [Entity]) {
+    let pairs = collision_pairs(entities);
+    mark_collided(entities, &pairs);
+}
+
+pub fn render_system(entities: &[Entity], renderer: &mut dyn Renderer) {
+    interpolated_render_system(entities, 1.0, renderer);
+}
+
+/// [`render_system`], drawing each entity `alpha` of the way from its
+/// previous transform to its current one.
+pub fn interpolated_render_system(entities: &[Entity], alpha: f32, renderer: &mut dyn Renderer) {
     for entity in entities {
         let Some(render) = &entity.render else {
             continue;
         };
 
-        draw_rectangle(
-            entity.transform.x,
-            entity.transform.y,
+        let transform = entity.interpolated(alpha);
+        renderer.draw_rectangle(
+            transform.x,
+            transform.y,
             render.shape.w,
             render.shape.h,
             render.color,
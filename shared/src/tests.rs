@@ -1,10 +1,15 @@
 #[cfg(test)]
 mod test {
+    use macroquad::camera::Camera;
+    use macroquad::color::{BLACK, RED, WHITE};
+    use macroquad::math::{Rect, Vec2, vec2};
+    use proptest::prelude::{Strategy, prop, prop_assert_eq, proptest};
+
     use crate::*;
 
     #[test]
     fn movement_moves_entity_when_not_colliding() {
-        let mut entities = vec![Entity::new(1, 0.0, 0.0).with_move(10.0, 0.0)];
+        let mut entities = vec![Entity::new(0.0, 0.0).with_move(10.0, 0.0)];
 
         let input = components::Input {
             dt: 1.0,
@@ -19,7 +24,7 @@ mod test {
 
     #[test]
     fn movement_stops_when_collided() {
-        let mut entity = Entity::new(1, 0.0, 0.0).with_move(10.0, 0.0).with_collide();
+        let mut entity = Entity::new(0.0, 0.0).with_move(10.0, 0.0).with_collide();
 
         entity.collide.as_mut().unwrap().is_collided = true;
 
@@ -36,7 +41,7 @@ mod test {
 
     #[test]
     fn jump_applies_force_when_grounded() {
-        let mut entities = vec![Entity::new(1, 0.0, 100.0).with_jump(300.0, 100.0)];
+        let mut entities = vec![Entity::new(0.0, 100.0).with_jump(300.0, 100.0)];
 
         let input = components::Input {
             dt: 1.0,
@@ -50,7 +55,7 @@ mod test {
 
     #[test]
     fn gravity_applies_when_airborne() {
-        let mut entity = Entity::new(1, 0.0, 90.0).with_jump(300.0, 100.0);
+        let mut entity = Entity::new(0.0, 90.0).with_jump(300.0, 100.0);
 
         // Simulate upward velocity
         entity.jump.as_mut().unwrap().velocity.y = 100.0;
@@ -70,9 +75,9 @@ mod test {
     #[test]
     fn collision_detects_overlap() {
         let mut entities = vec![
-            Entity::new(1, 0.0, 0.0).with_collide(),
-            Entity::new(2, 32.0, 32.0).with_collide(), // overlaps 64x64
-                                                       //
+            Entity::new(0.0, 0.0).with_collide(),
+            Entity::new(32.0, 32.0).with_collide(), // overlaps 64x64
+                                                    //
         ];
 
         collide_system(&mut entities);
@@ -84,8 +89,8 @@ mod test {
     #[test]
     fn collision_not_detected_when_separated() {
         let mut entities = vec![
-            Entity::new(1, 0.0, 0.0).with_collide(),
-            Entity::new(2, 200.0, 200.0).with_collide(),
+            Entity::new(0.0, 0.0).with_collide(),
+            Entity::new(200.0, 200.0).with_collide(),
         ];
 
         collide_system(&mut entities);
@@ -99,7 +104,7 @@ mod test {
         let mut world = World::new();
 
         world.spawn(
-            Entity::new(1, 0.0, 100.0)
+            Entity::new(0.0, 100.0)
                 .with_jump(200.0, 100.0)
                 .with_move(10.0, 0.0)
                 .with_collide(),
@@ -116,4 +121,716 @@ mod test {
         assert!(e.transform.x > 0.0);
         assert!(e.transform.y > 100.0);
     }
+
+    #[test]
+    fn spawn_allocates_distinct_ids() {
+        let mut world = World::new();
+
+        let a = world.spawn(Entity::new(0.0, 0.0));
+        let b = world.spawn(Entity::new(10.0, 0.0));
+
+        assert_ne!(a, b);
+        assert_eq!(world.find(a).unwrap().id(), a);
+        assert_eq!(world.find(b).unwrap().transform.x, 10.0);
+    }
+
+    #[test]
+    fn stale_id_does_not_resolve_after_despawn() {
+        let mut world = World::new();
+
+        let a = world.spawn(Entity::new(0.0, 0.0));
+        assert!(world.despawn(a).is_some());
+
+        // The slot is reused, but the old handle must not see the new entity.
+        let b = world.spawn(Entity::new(5.0, 0.0));
+
+        assert_ne!(a, b);
+        assert!(world.find(a).is_none());
+        assert!(world.find_mut(a).is_none());
+        assert!(world.despawn(a).is_none());
+        assert_eq!(world.find(b).unwrap().transform.x, 5.0);
+    }
+
+    #[test]
+    fn despawn_keeps_other_handles_valid() {
+        let mut world = World::new();
+
+        let a = world.spawn(Entity::new(1.0, 0.0));
+        let b = world.spawn(Entity::new(2.0, 0.0));
+        let c = world.spawn(Entity::new(3.0, 0.0));
+
+        world.despawn(a);
+
+        assert_eq!(world.entities().len(), 2);
+        assert_eq!(world.find(b).unwrap().transform.x, 2.0);
+        assert_eq!(world.find(c).unwrap().transform.x, 3.0);
+    }
+
+    #[test]
+    fn clear_invalidates_every_handle() {
+        let mut world = World::new();
+
+        let a = world.spawn(Entity::new(0.0, 0.0));
+        let b = world.spawn(Entity::new(0.0, 0.0));
+        world.clear();
+
+        assert!(world.entities().is_empty());
+        assert!(world.find(a).is_none());
+        assert!(world.find(b).is_none());
+    }
+
+    #[test]
+    fn queued_commands_wait_for_flush() {
+        let mut world = World::new();
+        let a = world.spawn(Entity::new(0.0, 0.0));
+
+        world.commands.despawn(a);
+        world.commands.spawn(Entity::new(1.0, 0.0));
+
+        assert_eq!(world.entities().len(), 1);
+        assert!(world.find(a).is_some());
+
+        world.flush();
+
+        assert!(world.find(a).is_none());
+        assert_eq!(world.entities().len(), 1);
+        assert_eq!(world.entities()[0].transform.x, 1.0);
+        assert!(world.commands.is_empty());
+    }
+
+    #[test]
+    fn update_flushes_commands_queued_mid_frame() {
+        let mut world = World::new();
+        let ids: Vec<EntityId> = (0..4)
+            .map(|i| world.spawn(Entity::new(i as f32, 0.0)))
+            .collect();
+
+        let (entities, commands) = world.entities_and_commands();
+        for e in entities.iter().filter(|e| e.transform.x < 2.0) {
+            commands.despawn(e.id());
+        }
+        // Duplicate and stale despawns are harmless.
+        commands.despawn(ids[0]);
+
+        let input = components::Input {
+            dt: 0.0,
+            is_jump: false,
+        };
+        world.update(&input);
+
+        assert_eq!(world.entities().len(), 2);
+        assert!(world.find(ids[0]).is_none());
+        assert!(world.find(ids[1]).is_none());
+        assert!(world.find(ids[2]).is_some());
+        assert!(world.find(ids[3]).is_some());
+    }
+
+    #[test]
+    fn collision_flag_survives_later_non_overlapping_pair() {
+        // 0 touches 1, but not 2. Checking (0, 2) last must not clear 0's flag.
+        let mut entities = vec![
+            Entity::new(0.0, 0.0).with_collide(),
+            Entity::new(32.0, 0.0).with_collide(),
+            Entity::new(500.0, 0.0).with_collide(),
+        ];
+
+        collide_system(&mut entities);
+
+        assert!(entities[0].is_collided());
+        assert!(entities[1].is_collided());
+        assert!(!entities[2].is_collided());
+    }
+
+    #[test]
+    fn spatial_hash_matches_touching_edges_on_cell_boundary() {
+        // `Rect::overlaps` counts shared edges; 64.0 is exactly a cell edge.
+        let rects = vec![
+            Rect::new(0.0, 0.0, 64.0, 64.0),
+            Rect::new(64.0, 0.0, 64.0, 64.0),
+            Rect::new(-64.0, -64.0, 64.0, 64.0),
+        ];
+
+        let pairs = SpatialHash::new(64.0).overlapping_pairs(&rects);
+
+        assert_eq!(pairs, brute_force_pairs(&rects));
+        assert_eq!(pairs, vec![(0, 1), (0, 2)]);
+    }
+
+    #[test]
+    fn spatial_hash_handles_huge_and_infinite_rects() {
+        let rects = vec![
+            Rect::new(0.0, 0.0, 10.0, 10.0),
+            Rect::new(f32::NEG_INFINITY, 0.0, f32::INFINITY, 5.0),
+            Rect::new(-1.0e30, -1.0e30, 2.0e30, 2.0e30),
+            Rect::new(f32::NAN, 0.0, 10.0, 10.0),
+            Rect::new(500.0, 500.0, 10.0, 10.0),
+        ];
+
+        let pairs = SpatialHash::new(1.0).overlapping_pairs(&rects);
+
+        assert_eq!(pairs, brute_force_pairs(&rects));
+        assert!(pairs.contains(&(0, 2)) && pairs.contains(&(2, 4)));
+    }
+
+    fn arb_rect() -> impl Strategy<Value = Rect> {
+        (
+            -500.0f32..500.0,
+            -500.0f32..500.0,
+            0.0f32..200.0,
+            0.0f32..200.0,
+        )
+            .prop_map(|(x, y, w, h)| Rect::new(x, y, w, h))
+    }
+
+    proptest! {
+        #[test]
+        fn spatial_hash_matches_brute_force(
+            rects in prop::collection::vec(arb_rect(), 0..80),
+            cell_size in (1.0f32..256.0)
+                .prop_filter("cell size must be positive and finite", |s| *s > 0.0 && s.is_finite()),
+        ) {
+            let pairs = SpatialHash::new(cell_size).overlapping_pairs(&rects);
+            prop_assert_eq!(pairs, brute_force_pairs(&rects));
+        }
+    }
+
+    #[test]
+    #[should_panic(expected = "cell size must be positive and finite")]
+    fn spatial_hash_rejects_zero_cell_size() {
+        SpatialHash::new(0.0);
+    }
+
+    #[test]
+    #[should_panic(expected = "cell size must be positive and finite")]
+    fn spatial_hash_rejects_non_finite_cell_size() {
+        SpatialHash::new(f32::NAN);
+    }
+
+    #[test]
+    fn collision_events_track_contact_lifecycle() {
+        let mut world = World::new();
+        let a = world.spawn(Entity::new(0.0, 0.0).with_collide());
+        let b = world.spawn(Entity::new(32.0, 0.0).with_collide());
+        let c = world.spawn(Entity::new(500.0, 0.0).with_collide());
+
+        world.collide_system();
+        let events: Vec<_> = world.drain_collision_events().collect();
+        assert_eq!(events, vec![CollisionEvent::Started(a, b)]);
+
+        world.collide_system();
+        let events: Vec<_> = world.drain_collision_events().collect();
+        assert_eq!(events, vec![CollisionEvent::Ongoing(a, b)]);
+
+        world.find_mut(b).unwrap().set_position(500.0, 0.0);
+        world.collide_system();
+        let events: Vec<_> = world.drain_collision_events().collect();
+        assert_eq!(
+            events,
+            vec![CollisionEvent::Started(b, c), CollisionEvent::Ended(a, b)]
+        );
+        assert_eq!(events[0].other(c), Some(b));
+        assert!(!events[0].involves(a));
+    }
+
+    #[test]
+    fn collision_event_ends_when_entity_despawns() {
+        let mut world = World::new();
+        let a = world.spawn(Entity::new(0.0, 0.0).with_collide());
+        let b = world.spawn(Entity::new(32.0, 0.0).with_collide());
+
+        world.collide_system();
+        world.despawn(b);
+        world.collide_system();
+
+        let events: Vec<_> = world.drain_collision_events().collect();
+        assert_eq!(events, vec![CollisionEvent::Ended(a, b)]);
+    }
+
+    #[test]
+    fn clear_forgets_contacts() {
+        let mut world = World::new();
+        world.spawn(Entity::new(0.0, 0.0).with_collide());
+        world.spawn(Entity::new(32.0, 0.0).with_collide());
+        world.collide_system();
+
+        world.clear();
+        assert_eq!(world.drain_collision_events().count(), 0);
+        world.collide_system();
+        assert_eq!(world.drain_collision_events().count(), 0);
+    }
+
+    #[test]
+    fn undrained_collision_events_are_replaced_by_next_pass() {
+        let mut world = World::new();
+        world.spawn(Entity::new(0.0, 0.0).with_collide());
+        world.spawn(Entity::new(32.0, 0.0).with_collide());
+
+        world.collide_system();
+        world.collide_system();
+
+        assert_eq!(world.drain_collision_events().count(), 1);
+        assert_eq!(world.drain_collision_events().count(), 0);
+    }
+
+    #[test]
+    fn collision_respects_layers_and_masks() {
+        const PLAYER: u32 = 1 << 0;
+        const ENEMY: u32 = 1 << 1;
+        const BULLET: u32 = 1 << 2;
+
+        let mut world = World::new();
+        let player = world
+            .spawn(Entity::new(0.0, 0.0).with_collide_layers(CollisionLayers::new(PLAYER, ENEMY)));
+        let enemy = world.spawn(
+            Entity::new(32.0, 0.0)
+                .with_collide_layers(CollisionLayers::new(ENEMY, PLAYER | BULLET)),
+        );
+        let other_enemy = world.spawn(
+            Entity::new(40.0, 0.0)
+                .with_collide_layers(CollisionLayers::new(ENEMY, PLAYER | BULLET)),
+        );
+        let bullet = world
+            .spawn(Entity::new(0.0, 10.0).with_collide_layers(CollisionLayers::new(BULLET, ENEMY)));
+
+        world.collide_system();
+        let events: Vec<_> = world.drain_collision_events().collect();
+
+        // Everything overlaps, but enemies ignore each other and the player
+        // ignores bullets.
+        assert_eq!(
+            events,
+            vec![
+                CollisionEvent::Started(player, enemy),
+                CollisionEvent::Started(player, other_enemy),
+                CollisionEvent::Started(enemy, bullet),
+                CollisionEvent::Started(other_enemy, bullet),
+            ]
+        );
+    }
+
+    fn jump_input(is_jump: bool) -> components::Input {
+        components::Input { dt: 0.0, is_jump }
+    }
+
+    #[test]
+    fn fixed_step_runs_whole_steps_and_keeps_remainder() {
+        let mut clock = FixedStep::new(0.1);
+        let mut dts = vec![];
+
+        let steps = clock.advance(0.25, &jump_input(false), |input| dts.push(input.dt));
+
+        assert_eq!(steps, 2);
+        assert_eq!(dts, vec![0.1, 0.1]);
+        assert!((clock.alpha() - 0.5).abs() < 0.001);
+    }
+
+    #[test]
+    fn fixed_step_delivers_press_to_one_step() {
+        let mut clock = FixedStep::new(0.1);
+        let mut jumps = vec![];
+
+        clock.advance(0.35, &jump_input(true), |input| jumps.push(input.is_jump));
+
+        assert_eq!(jumps, vec![true, false, false]);
+    }
+
+    #[test]
+    fn fixed_step_holds_press_from_short_frame() {
+        let mut clock = FixedStep::new(0.1);
+        let mut jumps = vec![];
+
+        // Too short to tick; the press must survive into the next frame.
+        clock.advance(0.05, &jump_input(true), |input| jumps.push(input.is_jump));
+        clock.advance(0.05, &jump_input(false), |input| jumps.push(input.is_jump));
+
+        assert_eq!(jumps, vec![true]);
+    }
+
+    #[test]
+    fn fixed_step_drops_backlog_after_stall() {
+        let mut clock = FixedStep::new(0.1);
+
+        let steps = clock.advance(60.0, &jump_input(false), |_| {});
+
+        assert!(steps < 10);
+        assert!(clock.alpha() < 1.0);
+    }
+
+    #[test]
+    #[should_panic(expected = "step must be positive and finite")]
+    fn fixed_step_rejects_zero_step() {
+        FixedStep::<components::Input>::new(0.0);
+    }
+
+    #[test]
+    fn render_interpolated_blends_last_two_updates() {
+        let mut world = World::new();
+        let id = world.spawn(
+            Entity::new(0.0, 0.0)
+                .with_move(10.0, 0.0)
+                .with_render(4.0, 4.0, RED),
+        );
+        let mut clock = FixedStep::new(0.25);
+        clock.advance(0.375, &jump_input(false), |input| world.update(input));
+
+        let mut renderer = RecordingRenderer::new();
+        world.render_interpolated(clock.alpha(), &mut renderer);
+        assert_eq!(
+            renderer.calls,
+            vec![DrawCall::Rectangle {
+                rect: Rect::new(1.25, 0.0, 4.0, 4.0),
+                color: RED,
+            }]
+        );
+
+        // A teleport doesn't smear across the screen.
+        world.find_mut(id).unwrap().set_position(50.0, 0.0);
+        assert_eq!(world.find(id).unwrap().interpolated(0.5).x, 50.0);
+    }
+
+    #[test]
+    fn paused_fixed_step_runs_nothing_and_forgets_the_time() {
+        let mut world = World::new();
+        let id = world.spawn(Entity::new(0.0, 0.0).with_move(10.0, 0.0));
+        let mut clock = FixedStep::new(0.1);
+
+        clock.set_paused(true);
+        world.fixed_update(&mut clock, 0.05, &jump_input(true));
+        world.fixed_update(&mut clock, 5.0, &jump_input(false));
+        assert_eq!(world.find(id).unwrap().transform.x, 0.0);
+
+        // Neither the paused time nor the press carries over.
+        clock.set_paused(false);
+        let mut jumps = vec![];
+        let steps = clock.advance(0.15, &jump_input(false), |input| jumps.push(input.is_jump));
+        assert_eq!(steps, 1);
+        assert_eq!(jumps, vec![false]);
+    }
+
+    #[test]
+    fn pause_overlay_toggles_the_clock_and_skips_the_resuming_frame() {
+        let pause = PauseOverlay::new();
+        let mut clock = FixedStep::<components::Input>::default();
+        let toggle = PauseRequest {
+            toggle: true,
+            ..Default::default()
+        };
+        let focus_lost = PauseRequest {
+            focus_lost: true,
+            ..Default::default()
+        };
+        let resume = PauseRequest {
+            resume: true,
+            ..Default::default()
+        };
+
+        assert!(pause.update(&mut clock, PauseRequest::default(), true));
+        assert!(!pause.update(&mut clock, toggle, true));
+        assert!(clock.is_paused());
+        // Losing focus again doesn't resume, but confirm does.
+        assert!(!pause.update(&mut clock, focus_lost, true));
+        assert!(clock.is_paused());
+        assert!(!pause.update(&mut clock, resume, true));
+        assert!(!clock.is_paused());
+        assert!(pause.update(&mut clock, PauseRequest::default(), true));
+
+        // A finished run can't be paused, and confirm alone never pauses.
+        assert!(pause.update(&mut clock, focus_lost, false));
+        assert!(pause.update(&mut clock, resume, true));
+        assert!(!clock.is_paused());
+    }
+
+    #[test]
+    fn pause_overlay_draws_only_while_paused() {
+        let pause = PauseOverlay::new();
+        let canvas = VirtualCanvas::new(800.0, 600.0);
+        let mut clock = FixedStep::<components::Input>::default();
+        let mut renderer = RecordingRenderer::new();
+
+        pause.render(&clock, &canvas, &mut renderer);
+        assert!(renderer.calls.is_empty());
+
+        clock.set_paused(true);
+        pause.render(&clock, &canvas, &mut renderer);
+        assert!(renderer.calls.contains(&DrawCall::Rectangle {
+            rect: Rect::new(0.0, 0.0, canvas.screen().x, canvas.screen().y),
+            color: pause.shade,
+        }));
+        assert!(renderer.calls.iter().any(
+            |call| matches!(call, DrawCall::Text { text, color, .. } if text == "PAUSED" && *color == WHITE)
+        ));
+    }
+
+    #[test]
+    fn fixed_update_is_independent_of_frame_rate() {
+        fn simulate(frame_time: f32, frames: usize) -> (f32, f32) {
+            let mut world = World::new();
+            let id = world.spawn(
+                Entity::new(0.0, 0.0)
+                    .with_jump(300.0, 0.0)
+                    .with_move(37.0, 0.0),
+            );
+            let mut clock = FixedStep::new(1.0 / 60.0);
+            for frame in 0..frames {
+                let input = jump_input(frame == 0);
+                world.fixed_update(&mut clock, frame_time, &input);
+            }
+            let e = world.find(id).unwrap();
+            (e.transform.x, e.transform.y)
+        }
+
+        // Half a second played at 30, 60 and 120 fps ends in the same place.
+        let slow = simulate(1.0 / 30.0, 15);
+        let normal = simulate(1.0 / 60.0, 30);
+        let fast = simulate(1.0 / 120.0, 60);
+
+        assert_eq!(slow, normal);
+        assert_eq!(normal, fast);
+    }
+
+    #[test]
+    fn rng_replays_from_seed() {
+        let mut a = Rng::new(7);
+        let mut b = Rng::new(7);
+        let mut c = Rng::new(8);
+
+        let seq_a: Vec<i32> = (0..16).map(|_| a.gen_range(0, 1000)).collect();
+        let seq_b: Vec<i32> = (0..16).map(|_| b.gen_range(0, 1000)).collect();
+        let seq_c: Vec<i32> = (0..16).map(|_| c.gen_range(0, 1000)).collect();
+
+        assert_eq!(seq_a, seq_b);
+        assert_ne!(seq_a, seq_c);
+
+        a.reseed(7);
+        let again: Vec<i32> = (0..16).map(|_| a.gen_range(0, 1000)).collect();
+        assert_eq!(again, seq_a);
+        assert_eq!(a.seed(), 7);
+    }
+
+    #[test]
+    fn render_system_draws_only_renderable_entities() {
+        let entities = vec![
+            Entity::new(10.0, 20.0).with_render(30.0, 40.0, RED),
+            Entity::new(0.0, 0.0).with_collide(),
+        ];
+        let mut renderer = RecordingRenderer::new();
+
+        render_system(&entities, &mut renderer);
+
+        assert_eq!(
+            renderer.calls,
+            vec![DrawCall::Rectangle {
+                rect: Rect::new(10.0, 20.0, 30.0, 40.0),
+                color: RED,
+            }]
+        );
+    }
+
+    #[test]
+    fn debug_outlines_every_entity() {
+        let entities = vec![Entity::new(0.0, 0.0), Entity::new(100.0, 0.0)];
+        let mut renderer = RecordingRenderer::new();
+
+        debug(&entities, &mut renderer);
+
+        assert_eq!(renderer.calls.len(), 2);
+        assert_eq!(
+            renderer.calls[1],
+            DrawCall::RectangleLines {
+                rect: Rect::new(100.0, 0.0, 64.0, 64.0),
+                thickness: 2.0,
+                color: WHITE,
+            }
+        );
+    }
+
+    #[cfg(feature = "snapshot")]
+    mod snapshot {
+        use macroquad::color::{BLACK, Color, WHITE};
+        use macroquad::texture::{DrawTextureParams, Image};
+
+        use crate::*;
+
+        // Survives the round trip through 8-bit pixels unchanged.
+        const PURE_RED: Color = Color::new(1.0, 0.0, 0.0, 1.0);
+
+        #[test]
+        fn image_renderer_fills_rectangles_in_screen_space() {
+            let mut renderer = ImageRenderer::new(8, 8);
+
+            renderer.draw_rectangle(2.0, 2.0, 4.0, 2.0, PURE_RED);
+
+            let image = renderer.image();
+            assert_eq!(image.get_pixel(2, 2), PURE_RED);
+            assert_eq!(image.get_pixel(5, 3), PURE_RED);
+            assert_eq!(image.get_pixel(1, 2), BLACK);
+            assert_eq!(image.get_pixel(2, 4), BLACK);
+        }
+
+        #[test]
+        fn image_renderer_fills_circles_but_not_their_corners() {
+            let mut renderer = ImageRenderer::new(8, 8);
+
+            renderer.draw_circle(4.0, 4.0, 4.0, PURE_RED);
+
+            let image = renderer.image();
+            assert_eq!(image.get_pixel(4, 4), PURE_RED);
+            assert_eq!(image.get_pixel(0, 4), PURE_RED);
+            assert_eq!(image.get_pixel(0, 0), BLACK);
+            assert_eq!(image.get_pixel(7, 7), BLACK);
+        }
+
+        #[test]
+        fn image_renderer_flips_textures_in_place() {
+            // Top row red, bottom row white.
+            let mut pixels = Image::gen_image_color(1, 2, WHITE);
+            pixels.set_pixel(0, 0, PURE_RED);
+            let texture = placeholder_texture(1);
+            let draw = |flip_y| {
+                let mut renderer =
+                    ImageRenderer::new(1, 2).with_texture(texture.clone(), pixels.clone());
+                renderer.draw_texture_ex(
+                    &texture,
+                    0.0,
+                    0.0,
+                    WHITE,
+                    DrawTextureParams {
+                        flip_y,
+                        ..Default::default()
+                    },
+                );
+                renderer.image().get_pixel(0, 0)
+            };
+
+            assert_eq!(draw(false), PURE_RED);
+            assert_eq!(draw(true), WHITE);
+        }
+
+        #[test]
+        fn compare_images_respects_tolerance() {
+            let expected = Image::gen_image_color(4, 4, BLACK);
+            let mut actual = expected.clone();
+            actual.set_pixel(0, 0, Color::from_rgba(3, 0, 0, 255));
+            actual.set_pixel(1, 0, WHITE);
+
+            assert_eq!(
+                compare_images(&expected, &actual, Tolerance::EXACT),
+                Err(SnapshotDiff::Pixels { pixels: 2 })
+            );
+            let loose = Tolerance {
+                channel: 4,
+                pixels: 1,
+            };
+            assert_eq!(compare_images(&expected, &actual, loose), Ok(()));
+            assert!(matches!(
+                compare_images(&expected, &Image::gen_image_color(2, 2, BLACK), loose),
+                Err(SnapshotDiff::Size { .. })
+            ));
+        }
+    }
+
+    fn canvas_in(screen: Vec2) -> VirtualCanvas {
+        let mut canvas = VirtualCanvas::new(320.0, 180.0);
+        canvas.resize(screen);
+        canvas
+    }
+
+    #[test]
+    fn integer_scaling_floors_and_centres_on_whole_pixels() {
+        let canvas = canvas_in(vec2(1000.0, 700.0)).with_scaling(Scaling::Integer);
+
+        assert_eq!(canvas.scale(), 3.0);
+        assert_eq!(canvas.viewport(), Rect::new(20.0, 80.0, 960.0, 540.0));
+    }
+
+    #[test]
+    fn integer_scaling_shrinks_to_fit_small_windows() {
+        let canvas = canvas_in(vec2(160.0, 100.0)).with_scaling(Scaling::Integer);
+
+        assert_eq!(canvas.scale(), 0.5);
+    }
+
+    #[test]
+    fn fractional_scaling_fills_the_shorter_fit() {
+        let canvas = canvas_in(vec2(1000.0, 700.0));
+
+        assert_eq!(canvas.scale(), 3.125);
+        assert_eq!(canvas.viewport(), Rect::new(0.0, 68.0, 1000.0, 562.5));
+    }
+
+    #[test]
+    fn resize_reports_only_changes() {
+        let mut canvas = canvas_in(vec2(640.0, 360.0));
+
+        assert!(!canvas.resize(vec2(640.0, 360.0)));
+        assert!(canvas.resize(vec2(800.0, 600.0)));
+        assert_eq!(canvas.screen(), vec2(800.0, 600.0));
+    }
+
+    // Where the camera's matrix puts `point`, in screen pixels.
+    fn project(canvas: &VirtualCanvas, point: Vec2) -> Vec2 {
+        let clip = canvas.camera().matrix().project_point3(point.extend(0.0));
+        let screen = canvas.screen();
+        vec2(
+            (clip.x + 1.0) / 2.0 * screen.x,
+            (1.0 - clip.y) / 2.0 * screen.y,
+        )
+    }
+
+    #[test]
+    fn screen_conversions_agree_with_the_camera() {
+        for canvas in [
+            canvas_in(vec2(1000.0, 700.0)).with_scaling(Scaling::Integer),
+            canvas_in(vec2(1000.0, 700.0)).with_target(-50.0, 20.0),
+            canvas_in(vec2(333.0, 555.0)).with_y_down(),
+        ] {
+            for point in [vec2(0.0, 0.0), vec2(320.0, 180.0), vec2(-12.5, 99.0)] {
+                let screen = canvas.world_to_screen(point);
+                assert!(screen.distance(project(&canvas, point)) < 1e-3);
+                assert!(canvas.screen_to_world(screen).distance(point) < 1e-3);
+            }
+        }
+    }
+
+    #[test]
+    fn canvas_origin_is_its_bottom_left_corner_unless_y_is_down() {
+        let canvas = canvas_in(vec2(640.0, 360.0));
+        assert_eq!(canvas.world_to_screen(Vec2::ZERO), vec2(0.0, 360.0));
+
+        let canvas = canvas.with_y_down();
+        assert_eq!(canvas.world_to_screen(Vec2::ZERO), Vec2::ZERO);
+    }
+
+    #[test]
+    fn letterbox_covers_only_the_screen_outside_the_canvas() {
+        let mut renderer = RecordingRenderer::new();
+
+        canvas_in(vec2(1000.0, 700.0))
+            .with_scaling(Scaling::Integer)
+            .render_letterbox(&mut renderer, BLACK);
+
+        let bar = |x, y, w, h| DrawCall::Rectangle {
+            rect: Rect::new(x, y, w, h),
+            color: BLACK,
+        };
+        assert_eq!(
+            renderer.calls,
+            vec![
+                DrawCall::SetDefaultCamera,
+                bar(0.0, 0.0, 1000.0, 80.0),
+                bar(0.0, 620.0, 1000.0, 80.0),
+                bar(0.0, 80.0, 20.0, 540.0),
+                bar(980.0, 80.0, 20.0, 540.0),
+            ]
+        );
+    }
+
+    #[test]
+    fn letterbox_draws_nothing_when_the_canvas_fills_the_screen() {
+        let mut renderer = RecordingRenderer::new();
+
+        canvas_in(vec2(640.0, 360.0)).render_letterbox(&mut renderer, BLACK);
+
+        assert_eq!(renderer.calls, vec![DrawCall::SetDefaultCamera]);
+    }
 }
@@ -0,0 +1,223 @@
+use macroquad::color::{Color, WHITE};
+
+use crate::{Input, Renderer, VirtualCanvas, World};
+
+/// Simulation rate used by the games: 60 updates a second.
+pub const DEFAULT_STEP: f32 = 1.0 / 60.0;
+
+/// Most fixed updates run for a single frame. After a long stall (a dragged
+/// window, a backgrounded tab) the backlog is dropped rather than replayed.
+const MAX_STEPS_PER_FRAME: u32 = 8;
+
+/// Input that can be handed to a [`FixedStep`] clock.
+///
+/// A frame's input may feed zero, one or several fixed updates. One-shot
+/// presses must reach exactly one of them, while held state applies to all.
+pub trait StepInput {
+    /// A copy of this input carrying `dt` for a single fixed update.
+    fn with_dt(&self, dt: f32) -> Self;
+
+    /// Folds a later frame's input into this one. Held state is replaced,
+    /// one-shot presses are kept if either frame had them.
+    fn merge(&mut self, later: &Self);
+
+    /// Clears one-shot presses once a fixed update has seen them.
+    fn consume_presses(&mut self);
+}
+
+impl StepInput for Input {
+    fn with_dt(&self, dt: f32) -> Self {
+        Input {
+            dt,
+            is_jump: self.is_jump,
+        }
+    }
+
+    fn merge(&mut self, later: &Self) {
+        self.is_jump |= later.is_jump;
+    }
+
+    fn consume_presses(&mut self) {
+        self.is_jump = false;
+    }
+}
+
+/// Accumulates real frame time and turns it into fixed-size updates.
+///
+/// Every update sees the same `dt`, so a run depends only on the input and
+/// the number of ticks, not on the frame rate of the machine playing it.
+#[derive(Debug)]
+pub struct FixedStep<I> {
+    step: f32,
+    accumulator: f32,
+    // Input from frames too short to run an update, so presses aren't lost.
+    pending: Option<I>,
+    paused: bool,
+}
+
+impl<I: StepInput> Default for FixedStep<I> {
+    fn default() -> Self {
+        Self::new(DEFAULT_STEP)
+    }
+}
+
+impl<I: StepInput> FixedStep<I> {
+    /// # Panics
+    ///
+    /// If `step` is not a positive, finite number of seconds.
+    pub fn new(step: f32) -> Self {
+        assert!(
+            step > 0.0 && step.is_finite(),
+            "step must be positive and finite, got {step}"
+        );
+        Self {
+            step,
+            accumulator: 0.0,
+            pending: None,
+            paused: false,
+        }
+    }
+
+    pub fn step(&self) -> f32 {
+        self.step
+    }
+
+    /// Stops or restarts the clock. While paused, [`FixedStep::advance`]
+    /// runs no updates and throws away the time and input it's given, so the
+    /// game doesn't race to catch up when it resumes.
+    pub fn set_paused(&mut self, paused: bool) {
+        self.paused = paused;
+    }
+
+    pub fn is_paused(&self) -> bool {
+        self.paused
+    }
+
+    /// How far rendering is between the last update and the next one, from
+    /// 0 to 1. Render systems blend previous and current positions with it,
+    /// as [`World::render_interpolated`] does.
+    pub fn alpha(&self) -> f32 {
+        self.accumulator / self.step
+    }
+
+    /// Adds `frame_time` to the clock and calls `tick` once per fixed update
+    /// it now covers. Returns the number of updates run.
+    pub fn advance(&mut self, frame_time: f32, input: &I, mut tick: impl FnMut(&I)) -> u32 {
+        if self.paused {
+            self.pending = None;
+            return 0;
+        }
+        let mut input = match self.pending.take() {
+            Some(mut pending) => {
+                pending.merge(input);
+                pending
+            }
+            None => input.with_dt(self.step),
+        };
+
+        self.accumulator += frame_time;
+        let mut steps = 0;
+        while self.accumulator >= self.step && steps < MAX_STEPS_PER_FRAME {
+            tick(&input.with_dt(self.step));
+            input.consume_presses();
+            self.accumulator -= self.step;
+            steps += 1;
+        }
+
+        if steps == MAX_STEPS_PER_FRAME {
+            self.accumulator %= self.step;
+        }
+        if steps == 0 {
+            self.pending = Some(input);
+        }
+        steps
+    }
+}
+
+/// What the player asked of a [`PauseOverlay`] this frame.
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub struct PauseRequest {
+    /// The pause button, which both pauses and resumes.
+    pub toggle: bool,
+    /// Resumes but never pauses, like confirm.
+    pub resume: bool,
+    /// The window or tab lost focus. Pauses but never resumes.
+    pub focus_lost: bool,
+}
+
+/// Pauses and resumes a [`FixedStep`] for games that run their own loop,
+/// and shades the screen while it's stopped.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct PauseOverlay {
+    pub shade: Color,
+    pub font_size: f32,
+}
+
+impl Default for PauseOverlay {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl PauseOverlay {
+    pub fn new() -> Self {
+        Self {
+            shade: Color::new(0.0, 0.0, 0.0, 0.5),
+            font_size: 50.0,
+        }
+    }
+
+    /// Pauses or resumes `clock` as `request` asks. Only pauses if
+    /// `can_pause`, so a finished run stays unpaused. Returns whether the
+    /// game should update this frame: play picks up on the frame after the
+    /// one that resumes it, so the press that resumed does nothing else.
+    pub fn update<I: StepInput>(
+        &self,
+        clock: &mut FixedStep<I>,
+        request: PauseRequest,
+        can_pause: bool,
+    ) -> bool {
+        let was_paused = clock.is_paused();
+        if was_paused && (request.toggle || request.resume) {
+            clock.set_paused(false);
+        } else if !was_paused && can_pause && (request.toggle || request.focus_lost) {
+            clock.set_paused(true);
+        }
+        !was_paused && !clock.is_paused()
+    }
+
+    /// Shades the screen and writes PAUSED over the canvas while `clock` is
+    /// paused.
+    pub fn render<I: StepInput>(
+        &self,
+        clock: &FixedStep<I>,
+        canvas: &VirtualCanvas,
+        renderer: &mut dyn Renderer,
+    ) {
+        if !clock.is_paused() {
+            return;
+        }
+        renderer.set_default_camera();
+        let screen = canvas.screen();
+        renderer.draw_rectangle(0.0, 0.0, screen.x, screen.y, self.shade);
+
+        let text = "PAUSED";
+        let size = renderer.measure_text(text, None, self.font_size);
+        let centre = canvas.viewport().center();
+        renderer.draw_text(
+            text,
+            centre.x - size.width / 2.0,
+            centre.y + size.offset_y - size.height / 2.0,
+            self.font_size,
+            WHITE,
+        );
+    }
+}
+
+impl World {
+    /// Runs [`World::update`] at the clock's fixed rate for one frame. A
+    /// paused clock leaves the world as it is.
+    pub fn fixed_update(&mut self, clock: &mut FixedStep<Input>, frame_time: f32, input: &Input) {
+        clock.advance(frame_time, input, |input| self.update(input));
+    }
+}
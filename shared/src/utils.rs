@@ -1,10 +1,10 @@
-use macroquad::{color::WHITE, shapes::draw_rectangle_lines};
+use macroquad::color::WHITE;
 
-use crate::Entity;
+use crate::{Entity, Renderer};
 
-pub fn debug(entities: &[Entity]) {
+pub fn debug(entities: &[Entity], renderer: &mut dyn Renderer) {
     for e in entities {
-        draw_rectangle_lines(
+        renderer.draw_rectangle_lines(
             e.transform.x,
             e.transform.y,
             e.transform.w,
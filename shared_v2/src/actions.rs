@@ -0,0 +1,455 @@
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+
+use macroquad::input::{
+    KeyCode, MouseButton, TouchPhase, is_key_down, is_key_pressed, is_mouse_button_down,
+    is_mouse_button_pressed, touches,
+};
+
+use crate::{Gamepads, Input, MemoryStorage, PadAxis, PadButton, Storage};
+
+/// Something a player can do, whatever they press to do it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Action {
+    Jump,
+    Fire,
+    /// Left is negative, right positive.
+    MoveX,
+    /// Down is negative, up positive, like the games' world space.
+    MoveY,
+    Pause,
+    /// Starts a run, or picks the focused menu entry.
+    Confirm,
+}
+
+impl Action {
+    pub const ALL: [Action; 6] = [
+        Action::Jump,
+        Action::Fire,
+        Action::MoveX,
+        Action::MoveY,
+        Action::Pause,
+        Action::Confirm,
+    ];
+
+    /// The name bindings are saved under.
+    pub fn name(self) -> &'static str {
+        match self {
+            Action::Jump => "jump",
+            Action::Fire => "fire",
+            Action::MoveX => "move_x",
+            Action::MoveY => "move_y",
+            Action::Pause => "pause",
+            Action::Confirm => "confirm",
+        }
+    }
+
+    fn storage_key(self) -> String {
+        format!("input.{}", self.name())
+    }
+}
+
+/// A button, key, stick or touch that can trigger an [`Action`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Binding {
+    Key(KeyCode),
+    Mouse(MouseButton),
+    /// Any finger on the screen.
+    Touch,
+    Pad(PadButton),
+    /// Reads from -1 to 1 rather than 0 to 1.
+    Stick(PadAxis),
+}
+
+impl Binding {
+    /// Whether both come from the same kind of device, so rebinding one
+    /// replaces the other.
+    pub fn same_device(self, other: Binding) -> bool {
+        std::mem::discriminant(&self) == std::mem::discriminant(&other)
+    }
+
+    fn to_token(self) -> String {
+        match self {
+            Binding::Key(key) => format!("key:{}", key_name(key)),
+            Binding::Mouse(button) => format!("mouse:{button:?}"),
+            Binding::Touch => "touch".to_string(),
+            Binding::Pad(button) => format!("pad:{button:?}"),
+            Binding::Stick(axis) => format!("stick:{axis:?}"),
+        }
+    }
+
+    fn parse(token: &str) -> Option<Self> {
+        match token.split_once(':') {
+            Some(("key", name)) => KEYS
+                .iter()
+                .find(|(_, n)| *n == name)
+                .map(|(key, _)| Binding::Key(*key)),
+            Some(("mouse", "Left")) => Some(Binding::Mouse(MouseButton::Left)),
+            Some(("mouse", "Middle")) => Some(Binding::Mouse(MouseButton::Middle)),
+            Some(("mouse", "Right")) => Some(Binding::Mouse(MouseButton::Right)),
+            Some(("pad", name)) => PadButton::ALL
+                .into_iter()
+                .find(|b| format!("{b:?}") == name)
+                .map(Binding::Pad),
+            Some(("stick", name)) => PadAxis::ALL
+                .into_iter()
+                .find(|a| format!("{a:?}") == name)
+                .map(Binding::Stick),
+            None if token == "touch" => Some(Binding::Touch),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for Binding {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Binding::Key(key) => write!(f, "{}", key_name(*key)),
+            Binding::Mouse(button) => write!(f, "{button:?} mouse"),
+            Binding::Touch => write!(f, "Touch"),
+            Binding::Pad(button) => write!(f, "{button:?} button"),
+            Binding::Stick(axis) => write!(f, "{axis:?} stick"),
+        }
+    }
+}
+
+/// Where bindings are read from: the real devices, or a script in tests.
+/// Sources read 0 for bindings they don't know about.
+pub trait InputSource {
+    /// How far `binding` is held this frame, from 0 to 1, or -1 to 1 for a
+    /// stick.
+    fn value(&self, binding: Binding) -> f32;
+    /// Whether `binding` went down this frame.
+    fn pressed(&self, binding: Binding) -> bool;
+}
+
+impl<T: InputSource + ?Sized> InputSource for &T {
+    fn value(&self, binding: Binding) -> f32 {
+        (**self).value(binding)
+    }
+
+    fn pressed(&self, binding: Binding) -> bool {
+        (**self).pressed(binding)
+    }
+}
+
+/// Reads both, so a keyboard and a pad can drive the same actions.
+impl<A: InputSource, B: InputSource> InputSource for (A, B) {
+    fn value(&self, binding: Binding) -> f32 {
+        self.0.value(binding) + self.1.value(binding)
+    }
+
+    fn pressed(&self, binding: Binding) -> bool {
+        self.0.pressed(binding) || self.1.pressed(binding)
+    }
+}
+
+impl InputSource for Gamepads {
+    fn value(&self, binding: Binding) -> f32 {
+        match binding {
+            Binding::Pad(button) => self.button(button),
+            Binding::Stick(axis) => self.axis(axis),
+            _ => 0.0,
+        }
+    }
+
+    fn pressed(&self, binding: Binding) -> bool {
+        match binding {
+            Binding::Pad(button) => self.button_pressed(button),
+            _ => false,
+        }
+    }
+}
+
+/// Reads macroquad's keyboard, mouse and touch state. Pair it with
+/// [`Gamepads`] for pads.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct MacroquadInput;
+
+// macroquad also reports touches as mouse clicks, so a finger on a touch
+// control would fire whatever the mouse is bound to as well.
+fn mouse_is_touch() -> bool {
+    !touches().is_empty()
+}
+
+impl InputSource for MacroquadInput {
+    fn value(&self, binding: Binding) -> f32 {
+        let down = match binding {
+            Binding::Key(key) => is_key_down(key),
+            Binding::Mouse(button) => !mouse_is_touch() && is_mouse_button_down(button),
+            Binding::Touch => !touches().is_empty(),
+            Binding::Pad(_) | Binding::Stick(_) => false,
+        };
+        if down { 1.0 } else { 0.0 }
+    }
+
+    fn pressed(&self, binding: Binding) -> bool {
+        match binding {
+            Binding::Key(key) => is_key_pressed(key),
+            Binding::Mouse(button) => !mouse_is_touch() && is_mouse_button_pressed(button),
+            Binding::Touch => touches().iter().any(|t| t.phase == TouchPhase::Started),
+            Binding::Pad(_) | Binding::Stick(_) => false,
+        }
+    }
+}
+
+/// Bindings held and pressed by hand, for tests.
+#[derive(Debug, Default, Clone)]
+pub struct SimulatedInput {
+    held: HashSet<Binding>,
+    pressed: HashSet<Binding>,
+}
+
+impl SimulatedInput {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Puts `binding` down, pressing it this frame.
+    pub fn press(&mut self, binding: Binding) {
+        self.held.insert(binding);
+        self.pressed.insert(binding);
+    }
+
+    pub fn release(&mut self, binding: Binding) {
+        self.held.remove(&binding);
+    }
+
+    /// Ends the frame, so presses become plain holds.
+    pub fn next_frame(&mut self) {
+        self.pressed.clear();
+    }
+}
+
+impl InputSource for SimulatedInput {
+    fn value(&self, binding: Binding) -> f32 {
+        if self.held.contains(&binding) {
+            1.0
+        } else {
+            0.0
+        }
+    }
+
+    fn pressed(&self, binding: Binding) -> bool {
+        self.pressed.contains(&binding)
+    }
+}
+
+/// The bindings for every [`Action`], and the [`Input`] they make each
+/// frame.
+///
+/// Each binding carries the value it adds to its action: 1 for buttons, and
+/// -1 or 1 for the two directions of an axis. Rebinding is saved to
+/// [`Storage`] and read back by [`ActionMap::with_storage`], one key per
+/// action.
+pub struct ActionMap {
+    bindings: HashMap<Action, Vec<(Binding, f32)>>,
+    storage: Box<dyn Storage>,
+}
+
+impl Default for ActionMap {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl ActionMap {
+    /// The default bindings: arrows and WASD to move, space to jump and
+    /// fire, P or escape to pause, and enter or space to confirm. On a pad,
+    /// the left stick or d-pad moves, South jumps and confirms, West fires
+    /// and Start pauses.
+    pub fn new() -> Self {
+        use Binding::{Key, Mouse, Pad, Stick, Touch};
+        let defaults = [
+            (
+                Action::Jump,
+                vec![
+                    Key(KeyCode::Space),
+                    Key(KeyCode::Up),
+                    Touch,
+                    Pad(PadButton::South),
+                ],
+            ),
+            (
+                Action::Fire,
+                vec![
+                    Key(KeyCode::Space),
+                    Mouse(MouseButton::Left),
+                    Pad(PadButton::West),
+                ],
+            ),
+            (
+                Action::Pause,
+                vec![Key(KeyCode::Escape), Key(KeyCode::P), Pad(PadButton::Start)],
+            ),
+            (
+                Action::Confirm,
+                vec![
+                    Key(KeyCode::Enter),
+                    Key(KeyCode::Space),
+                    Touch,
+                    Pad(PadButton::South),
+                ],
+            ),
+        ];
+        let mut bindings: HashMap<_, _> = defaults
+            .into_iter()
+            .map(|(action, bindings)| (action, bindings.into_iter().map(|b| (b, 1.0)).collect()))
+            .collect();
+        let axis = |negative: [Binding; 3], positive: [Binding; 3], stick: (Binding, f32)| {
+            let negative = negative.map(|binding| (binding, -1.0));
+            let positive = positive.map(|binding| (binding, 1.0));
+            negative
+                .into_iter()
+                .chain(positive)
+                .chain([stick])
+                .collect()
+        };
+        bindings.insert(
+            Action::MoveX,
+            axis(
+                [
+                    Key(KeyCode::Left),
+                    Key(KeyCode::A),
+                    Pad(PadButton::DPadLeft),
+                ],
+                [
+                    Key(KeyCode::Right),
+                    Key(KeyCode::D),
+                    Pad(PadButton::DPadRight),
+                ],
+                (Stick(PadAxis::LeftX), 1.0),
+            ),
+        );
+        // Pads read down as positive, the other way round from the games.
+        bindings.insert(
+            Action::MoveY,
+            axis(
+                [
+                    Key(KeyCode::Down),
+                    Key(KeyCode::S),
+                    Pad(PadButton::DPadDown),
+                ],
+                [Key(KeyCode::Up), Key(KeyCode::W), Pad(PadButton::DPadUp)],
+                (Stick(PadAxis::LeftY), -1.0),
+            ),
+        );
+        Self {
+            bindings,
+            storage: Box::new(MemoryStorage::default()),
+        }
+    }
+
+    /// Restores the bindings saved in `storage`, and saves future rebinds
+    /// there. Actions with nothing saved keep their defaults.
+    pub fn with_storage(mut self, storage: Box<dyn Storage>) -> Self {
+        for action in Action::ALL {
+            let Some(saved) = storage.get(&action.storage_key()) else {
+                continue;
+            };
+            let bindings = saved
+                .split_whitespace()
+                .filter_map(|token| match token.strip_prefix('-') {
+                    Some(token) => Some((Binding::parse(token)?, -1.0)),
+                    None => Some((Binding::parse(token)?, 1.0)),
+                })
+                .collect();
+            self.bindings.insert(action, bindings);
+        }
+        self.storage = storage;
+        self
+    }
+
+    pub fn bindings(&self, action: Action) -> &[(Binding, f32)] {
+        self.bindings.get(&action).map_or(&[], Vec::as_slice)
+    }
+
+    /// Adds `binding` to `action`, keeping the ones it already has.
+    pub fn bind(&mut self, action: Action, binding: Binding, value: f32) {
+        self.bindings
+            .entry(action)
+            .or_default()
+            .push((binding, value));
+        self.save(action);
+    }
+
+    /// Binds `action` to `binding`, replacing whatever it had on the same
+    /// device for the same direction. Rebinding jump to a key leaves its
+    /// touch binding alone, and rebinding left leaves right alone.
+    pub fn rebind(&mut self, action: Action, binding: Binding, value: f32) {
+        let bindings = self.bindings.entry(action).or_default();
+        bindings.retain(|&(b, v)| !(b.same_device(binding) && v.signum() == value.signum()));
+        bindings.push((binding, value));
+        self.save(action);
+    }
+
+    fn save(&mut self, action: Action) {
+        let tokens: Vec<String> = self
+            .bindings(action)
+            .iter()
+            .map(|(binding, value)| {
+                let sign = if *value < 0.0 { "-" } else { "" };
+                format!("{sign}{}", binding.to_token())
+            })
+            .collect();
+        self.storage.set(&action.storage_key(), &tokens.join(" "));
+    }
+
+    /// The sum of every binding's value for `action`, from -1 to 1.
+    pub fn value(&self, action: Action, source: &dyn InputSource) -> f32 {
+        self.bindings(action)
+            .iter()
+            .map(|(binding, value)| source.value(*binding) * value)
+            .sum::<f32>()
+            .clamp(-1.0, 1.0)
+    }
+
+    pub fn is_down(&self, action: Action, source: &dyn InputSource) -> bool {
+        self.value(action, source) != 0.0
+    }
+
+    pub fn is_pressed(&self, action: Action, source: &dyn InputSource) -> bool {
+        self.bindings(action)
+            .iter()
+            .any(|(binding, _)| source.pressed(*binding))
+    }
+
+    /// This frame's [`Input`], lasting `dt` seconds.
+    pub fn input(&self, dt: f32, source: &dyn InputSource) -> Input {
+        Input {
+            dt,
+            jump: self.is_pressed(Action::Jump, source),
+            fire: self.is_pressed(Action::Fire, source),
+            confirm: self.is_pressed(Action::Confirm, source),
+            pause: self.is_pressed(Action::Pause, source),
+            focus_lost: false,
+            move_x: self.value(Action::MoveX, source),
+            move_y: self.value(Action::MoveY, source),
+        }
+    }
+}
+
+fn key_name(key: KeyCode) -> &'static str {
+    KEYS.iter()
+        .find(|(k, _)| *k == key)
+        .map_or("Unknown", |(_, name)| name)
+}
+
+// KeyCode has no way back from its name, so saved bindings are looked up
+// here.
+macro_rules! key_names {
+    ($($key:ident)*) => {
+        const KEYS: &[(KeyCode, &str)] = &[$((KeyCode::$key, stringify!($key))),*];
+    };
+}
+
+key_names!(
+    Space Apostrophe Comma Minus Period Slash Key0 Key1 Key2 Key3 Key4 Key5 Key6 Key7 Key8 Key9
+    Semicolon Equal A B C D E F G H I J K L M N O P Q R S T U V W X Y Z LeftBracket Backslash
+    RightBracket GraveAccent World1 World2 Escape Enter Tab Backspace Insert Delete Right Left
+    Down Up PageUp PageDown Home End CapsLock ScrollLock NumLock PrintScreen Pause F1 F2 F3 F4
+    F5 F6 F7 F8 F9 F10 F11 F12 F13 F14 F15 F16 F17 F18 F19 F20 F21 F22 F23 F24 F25 Kp0 Kp1 Kp2
+    Kp3 Kp4 Kp5 Kp6 Kp7 Kp8 Kp9 KpDecimal KpDivide KpMultiply KpSubtract KpAdd KpEnter KpEqual
+    LeftShift LeftControl LeftAlt LeftSuper RightShift RightControl RightAlt RightSuper Menu Back
+    Unknown
+);
@@ -0,0 +1,206 @@
+use macroquad::experimental::animation::{AnimatedSprite, Animation};
+
+use crate::{Handle, Physics, Texture, World};
+
+/// Names a clip, by its [`Animation::name`].
+pub type AnimationState = &'static str;
+
+pub const IDLE: AnimationState = "idle";
+pub const RUN: AnimationState = "run";
+pub const JUMP: AnimationState = "jump";
+pub const HURT: AnimationState = "hurt";
+pub const DEATH: AnimationState = "death";
+
+/// What a clip does after its last frame.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ClipEnd {
+    Loop,
+    /// Plays once, then goes back to the animator's default.
+    Return,
+    /// Plays once and stays on the last frame.
+    Hold,
+}
+
+struct Clip {
+    texture: Handle<Texture>,
+    animation: Animation,
+    end: ClipEnd,
+}
+
+/// Picks which clip a [`crate::Sprite`] shows and steps through its frames.
+///
+/// Looping clips follow whatever state the entity is in, usually from
+/// [`physics_state`]. One-shot clips, like a hurt flinch or a death, are
+/// played for events and can't be cut short by a looping one, only by
+/// another one-shot. Each clip can come from its own texture, so sheets
+/// split one animation per file work as they are.
+pub struct Animator {
+    clips: Vec<Clip>,
+    default: AnimationState,
+    current: usize,
+    time: f32,
+}
+
+impl Animator {
+    /// Falls back to `default` for states without a clip, so it needs one
+    /// itself.
+    pub fn new(default: AnimationState) -> Self {
+        Self {
+            clips: vec![],
+            default,
+            current: 0,
+            time: 0.0,
+        }
+    }
+
+    /// Adds a clip for the state named by `animation.name`.
+    pub fn with_clip(
+        mut self,
+        texture: Handle<Texture>,
+        animation: Animation,
+        end: ClipEnd,
+    ) -> Self {
+        self.clips.push(Clip {
+            texture,
+            animation,
+            end,
+        });
+        self.current = self.find(self.default).unwrap_or(0);
+        self
+    }
+
+    fn find(&self, state: AnimationState) -> Option<usize> {
+        self.clips.iter().position(|c| c.animation.name == state)
+    }
+
+    /// A sprite holding every clip, for [`Animator::apply`] to drive.
+    pub fn sprite(&self, tile_width: u32, tile_height: u32) -> AnimatedSprite {
+        let animations: Vec<Animation> = self.clips.iter().map(|c| c.animation.clone()).collect();
+        AnimatedSprite::new(tile_width, tile_height, &animations, false)
+    }
+
+    /// The playing clip's state, or the default while there are no clips.
+    pub fn state(&self) -> &str {
+        self.clip()
+            .map_or(self.default, |clip| &clip.animation.name)
+    }
+
+    pub fn default_state(&self) -> AnimationState {
+        self.default
+    }
+
+    /// Seconds into the playing clip, wrapped for looping clips.
+    pub fn elapsed(&self) -> f32 {
+        self.time
+    }
+
+    fn clip(&self) -> Option<&Clip> {
+        self.clips.get(self.current)
+    }
+
+    /// Switches to `state`'s clip, or the default's if it has none. Does
+    /// nothing if that's already playing, or if it loops and a one-shot is
+    /// still going.
+    pub fn play(&mut self, state: AnimationState) {
+        let Some(next) = self.find(state).or_else(|| self.find(self.default)) else {
+            return;
+        };
+        let busy = self.clips[self.current].end != ClipEnd::Loop;
+        if next == self.current || (busy && self.clips[next].end == ClipEnd::Loop) {
+            return;
+        }
+        self.current = next;
+        self.time = 0.0;
+    }
+
+    /// Advances the clip by `dt` seconds.
+    pub fn update(&mut self, dt: f32) {
+        let Some(clip) = self.clip() else {
+            return;
+        };
+        let end = clip.end;
+        let length = clip_length(clip);
+        self.time += dt;
+        // A clip with no frames has no length to wrap by.
+        if end == ClipEnd::Loop && length > 0.0 {
+            self.time %= length;
+        }
+        if end == ClipEnd::Return && self.time >= length {
+            self.current = self.find(self.default).unwrap_or(0);
+            self.time = 0.0;
+        }
+    }
+
+    /// Whether a one-shot clip has shown all its frames. Looping clips never
+    /// finish.
+    pub fn is_finished(&self) -> bool {
+        self.clip()
+            .is_some_and(|clip| clip.end != ClipEnd::Loop && self.time >= clip_length(clip))
+    }
+
+    /// The frame showing, counting from 0 within the clip.
+    pub fn frame(&self) -> u32 {
+        let Some(clip) = self.clip() else {
+            return 0;
+        };
+        let frames = clip.animation.frames.max(1);
+        let frame = (self.time * clip.animation.fps as f32) as u32;
+        match clip.end {
+            ClipEnd::Loop => frame % frames,
+            ClipEnd::Return | ClipEnd::Hold => frame.min(frames - 1),
+        }
+    }
+
+    /// The playing clip's texture. `None` until a clip is added.
+    pub fn texture(&self) -> Option<Handle<Texture>> {
+        self.clip().map(|clip| clip.texture)
+    }
+
+    /// Shows the current frame on `sprite`, made by [`Animator::sprite`].
+    /// Leaves it alone while there are no clips.
+    pub fn apply(&self, sprite: &mut AnimatedSprite) {
+        if self.clips.is_empty() {
+            return;
+        }
+        sprite.set_animation(self.current);
+        sprite.set_frame(self.frame());
+    }
+}
+
+fn clip_length(clip: &Clip) -> f32 {
+    clip.animation.frames as f32 / clip.animation.fps.max(1) as f32
+}
+
+/// The looping state that `physics` suggests: in the air, moving, or still.
+pub fn physics_state(physics: &Physics) -> AnimationState {
+    if !physics.is_grounded {
+        JUMP
+    } else if physics.velocity.x != 0.0 {
+        RUN
+    } else {
+        IDLE
+    }
+}
+
+/// Steps every animated sprite by `dt` seconds, following its entity's
+/// physics. Call it once a frame, while the game isn't paused.
+pub fn animate_sprites(world: &mut World, dt: f32) {
+    for entity in world.entities_mut() {
+        let Some(sprite) = &mut entity.sprite else {
+            continue;
+        };
+        let Some(animator) = &mut sprite.animator else {
+            continue;
+        };
+        if let Some(physics) = &entity.physics {
+            animator.play(physics_state(physics));
+        }
+        animator.update(dt);
+        // One without clips has nothing to show.
+        let Some(texture) = animator.texture() else {
+            continue;
+        };
+        sprite.texture = texture;
+        animator.apply(&mut sprite.sprite);
+    }
+}
@@ -0,0 +1,507 @@
+use std::collections::HashMap;
+use std::fmt;
+use std::hash::{Hash, Hasher};
+use std::marker::PhantomData;
+
+use macroquad::color::{Color, DARKGRAY, RED, WHITE};
+use macroquad::experimental::coroutines::{Coroutine, start_coroutine};
+use macroquad::file::load_file;
+use macroquad::material::{Material, MaterialParams, load_material};
+use macroquad::math::Vec2;
+use macroquad::miniquad::{ShaderSource, UniformDesc, UniformType};
+use macroquad::text::{Font, load_ttf_font_from_bytes};
+use macroquad::texture::{FilterMode, Image, Texture2D};
+use macroquad::window::{next_frame, screen_height, screen_width};
+
+use crate::{MacroquadRenderer, Renderer};
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum AssetKind {
+    Texture(FilterMode),
+    Sound,
+    Font,
+    Shader,
+    /// Config and other files the game parses itself.
+    Data,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct ManifestEntry {
+    pub path: String,
+    pub kind: AssetKind,
+}
+
+/// Every file a game needs before it can start, loaded up front by
+/// [`load_assets`].
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct AssetManifest {
+    pub entries: Vec<ManifestEntry>,
+}
+
+impl AssetManifest {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    fn with(mut self, path: &str, kind: AssetKind) -> Self {
+        self.entries.push(ManifestEntry {
+            path: path.to_string(),
+            kind,
+        });
+        self
+    }
+
+    pub fn with_texture(self, path: &str, filter: FilterMode) -> Self {
+        self.with(path, AssetKind::Texture(filter))
+    }
+
+    pub fn with_sound(self, path: &str) -> Self {
+        self.with(path, AssetKind::Sound)
+    }
+
+    pub fn with_font(self, path: &str) -> Self {
+        self.with(path, AssetKind::Font)
+    }
+
+    pub fn with_shader(self, path: &str) -> Self {
+        self.with(path, AssetKind::Shader)
+    }
+
+    pub fn with_data(self, path: &str) -> Self {
+        self.with(path, AssetKind::Data)
+    }
+}
+
+/// Encoded audio, kept as loaded until something plays it.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Sound {
+    pub bytes: Vec<u8>,
+}
+
+/// A fragment shader, compiled against [`SHADER_VERTEX`] into a
+/// [`Material`] to draw with.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Shader {
+    pub source: String,
+    pub material: Material,
+}
+
+/// The vertex shader every [`Shader`] is compiled with. It hands the fragment
+/// shader `uv`, `color` and, as on Shadertoy, `iTime` in seconds.
+pub const SHADER_VERTEX: &str = r#"#version 100
+attribute vec3 position;
+attribute vec2 texcoord;
+attribute vec4 color0;
+
+varying vec4 color;
+varying vec2 uv;
+varying float iTime;
+
+uniform mat4 Model;
+uniform mat4 Projection;
+uniform vec4 _Time;
+
+void main() {
+    gl_Position = Projection * Model * vec4(position, 1);
+    color = color0 / 255.0;
+    uv = texcoord;
+    iTime = _Time.x;
+}
+"#;
+
+impl Shader {
+    /// Compiles `source`, declaring the uniforms and textures it lists so
+    /// they can be set on the material.
+    pub fn compile(source: String) -> Result<Self, String> {
+        let (uniforms, textures) = shader_uniforms(&source);
+        let material = load_material(
+            ShaderSource::Glsl {
+                vertex: SHADER_VERTEX,
+                fragment: &source,
+            },
+            MaterialParams {
+                uniforms,
+                textures,
+                ..Default::default()
+            },
+        )
+        .map_err(|e| e.to_string())?;
+        Ok(Self { source, material })
+    }
+}
+
+/// The uniforms and textures a fragment shader declares, one per line,
+/// leaving out the ones macroquad sets itself.
+pub(crate) fn shader_uniforms(source: &str) -> (Vec<UniformDesc>, Vec<String>) {
+    let mut uniforms = vec![];
+    let mut textures = vec![];
+    for line in source.lines() {
+        let Some(declaration) = line.trim().strip_prefix("uniform ") else {
+            continue;
+        };
+        // The type and name come last, after any precision.
+        let words: Vec<&str> = declaration
+            .trim_end_matches(';')
+            .split_whitespace()
+            .collect();
+        let [.., kind, name] = words[..] else {
+            continue;
+        };
+        if matches!(name, "Model" | "Projection" | "_Time" | "Texture") {
+            continue;
+        }
+        let kind = match kind {
+            "float" => UniformType::Float1,
+            "vec2" => UniformType::Float2,
+            "vec3" => UniformType::Float3,
+            "vec4" => UniformType::Float4,
+            "int" => UniformType::Int1,
+            "ivec2" => UniformType::Int2,
+            "ivec3" => UniformType::Int3,
+            "ivec4" => UniformType::Int4,
+            "mat4" => UniformType::Mat4,
+            "sampler2D" => {
+                textures.push(name.to_string());
+                continue;
+            }
+            _ => continue,
+        };
+        uniforms.push(UniformDesc::new(name, kind));
+    }
+    (uniforms, textures)
+}
+
+/// The raw contents of a [`AssetKind::Data`] file.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Data {
+    pub bytes: Vec<u8>,
+}
+
+impl Data {
+    pub fn as_str(&self) -> Option<&str> {
+        std::str::from_utf8(&self.bytes).ok()
+    }
+}
+
+/// Textures are stored as macroquad hands them out.
+pub type Texture = Texture2D;
+
+/// Refers to an asset in an [`AssetServer`] without owning it. Cheap to copy,
+/// so entities and systems hold these rather than the assets themselves.
+pub struct Handle<T> {
+    index: u32,
+    _asset: PhantomData<fn() -> T>,
+}
+
+impl<T> Clone for Handle<T> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<T> Copy for Handle<T> {}
+
+impl<T> PartialEq for Handle<T> {
+    fn eq(&self, other: &Self) -> bool {
+        self.index == other.index
+    }
+}
+
+impl<T> Eq for Handle<T> {}
+
+impl<T> Hash for Handle<T> {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.index.hash(state);
+    }
+}
+
+impl<T> fmt::Debug for Handle<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Handle<{}>({})", std::any::type_name::<T>(), self.index)
+    }
+}
+
+/// The assets of one type in an [`AssetServer`].
+pub struct AssetStore<T> {
+    assets: Vec<T>,
+    by_name: HashMap<String, Handle<T>>,
+}
+
+impl<T> Default for AssetStore<T> {
+    fn default() -> Self {
+        Self {
+            assets: vec![],
+            by_name: HashMap::new(),
+        }
+    }
+}
+
+/// Anything an [`AssetServer`] can hold.
+pub trait Asset: Sized {
+    fn store(server: &AssetServer) -> &AssetStore<Self>;
+    fn store_mut(server: &mut AssetServer) -> &mut AssetStore<Self>;
+}
+
+macro_rules! impl_asset {
+    ($asset:ty, $field:ident) => {
+        impl Asset for $asset {
+            fn store(server: &AssetServer) -> &AssetStore<Self> {
+                &server.$field
+            }
+
+            fn store_mut(server: &mut AssetServer) -> &mut AssetStore<Self> {
+                &mut server.$field
+            }
+        }
+    };
+}
+
+impl_asset!(Texture, textures);
+impl_asset!(Sound, sounds);
+impl_asset!(Font, fonts);
+impl_asset!(Shader, shaders);
+impl_asset!(Data, data);
+
+/// Every asset the game has loaded, each stored once under its name
+/// (usually its path) and referred to by [`Handle`].
+#[derive(Default)]
+pub struct AssetServer {
+    textures: AssetStore<Texture>,
+    sounds: AssetStore<Sound>,
+    fonts: AssetStore<Font>,
+    shaders: AssetStore<Shader>,
+    data: AssetStore<Data>,
+}
+
+impl AssetServer {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Stores `asset` under `name`. A name that's already taken keeps its
+    /// asset and handle, so adding the same file twice is harmless.
+    pub fn add<T: Asset>(&mut self, name: &str, asset: T) -> Handle<T> {
+        let store = T::store_mut(self);
+        if let Some(&handle) = store.by_name.get(name) {
+            return handle;
+        }
+        let handle = Handle {
+            index: store.assets.len() as u32,
+            _asset: PhantomData,
+        };
+        store.assets.push(asset);
+        store.by_name.insert(name.to_string(), handle);
+        handle
+    }
+
+    /// Stores `asset` under `name`, swapping out any asset already there so
+    /// existing handles see the new one.
+    pub fn replace<T: Asset>(&mut self, name: &str, asset: T) -> Handle<T> {
+        match self.handle(name) {
+            Some(handle) => {
+                *self.get_mut(handle) = asset;
+                handle
+            }
+            None => self.add(name, asset),
+        }
+    }
+
+    /// Handles always come from a server, so this only panics when given
+    /// one from a different server.
+    pub fn get<T: Asset>(&self, handle: Handle<T>) -> &T {
+        &T::store(self).assets[handle.index as usize]
+    }
+
+    pub fn get_mut<T: Asset>(&mut self, handle: Handle<T>) -> &mut T {
+        &mut T::store_mut(self).assets[handle.index as usize]
+    }
+
+    pub fn handle<T: Asset>(&self, name: &str) -> Option<Handle<T>> {
+        T::store(self).by_name.get(name).copied()
+    }
+
+    pub fn get_by_name<T: Asset>(&self, name: &str) -> Option<&T> {
+        self.handle(name).map(|handle| self.get(handle))
+    }
+
+    /// Every asset of one type, in the order they were added.
+    pub fn iter<'a, T: Asset + 'a>(&'a self) -> impl Iterator<Item = (Handle<T>, &'a T)> {
+        T::store(self)
+            .assets
+            .iter()
+            .enumerate()
+            .map(|(index, asset)| {
+                let handle = Handle {
+                    index: index as u32,
+                    _asset: PhantomData,
+                };
+                (handle, asset)
+            })
+    }
+
+    pub(crate) fn replace_loaded(&mut self, name: &str, asset: Loaded) {
+        match asset {
+            Loaded::Texture(texture) => {
+                self.replace(name, texture);
+            }
+            Loaded::Sound(sound) => {
+                self.replace(name, sound);
+            }
+            Loaded::Font(font) => {
+                self.replace(name, font);
+            }
+            Loaded::Shader(shader) => {
+                self.replace(name, shader);
+            }
+            Loaded::Data(data) => {
+                self.replace(name, data);
+            }
+        }
+    }
+}
+
+pub(crate) enum Loaded {
+    Texture(Texture),
+    Sound(Sound),
+    Font(Font),
+    Shader(Shader),
+    Data(Data),
+}
+
+/// Turns the contents of a file into the asset `kind` says it is.
+pub(crate) fn decode(kind: AssetKind, bytes: Vec<u8>) -> Result<Loaded, String> {
+    Ok(match kind {
+        AssetKind::Texture(filter) => {
+            let image = Image::from_file_with_format(&bytes, None).map_err(|e| e.to_string())?;
+            let texture = Texture2D::from_image(&image);
+            texture.set_filter(filter);
+            Loaded::Texture(texture)
+        }
+        AssetKind::Sound => Loaded::Sound(Sound { bytes }),
+        AssetKind::Font => {
+            Loaded::Font(load_ttf_font_from_bytes(&bytes).map_err(|e| e.to_string())?)
+        }
+        AssetKind::Shader => {
+            let source = String::from_utf8(bytes).map_err(|e| e.to_string())?;
+            Loaded::Shader(Shader::compile(source)?)
+        }
+        AssetKind::Data => Loaded::Data(Data { bytes }),
+    })
+}
+
+async fn load_entry(entry: ManifestEntry) -> Result<Loaded, String> {
+    let bytes = load_file(&entry.path).await.map_err(|e| e.to_string())?;
+    decode(entry.kind, bytes)
+}
+
+/// How far [`load_assets`] has got.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct LoadProgress {
+    pub total: usize,
+    pub finished: usize,
+    /// Paths that couldn't be loaded, with the reason.
+    pub failed: Vec<(String, String)>,
+}
+
+impl LoadProgress {
+    /// From 0 to 1. An empty manifest counts as fully loaded.
+    pub fn fraction(&self) -> f32 {
+        if self.total == 0 {
+            return 1.0;
+        }
+        self.finished as f32 / self.total as f32
+    }
+}
+
+const BAR_HEIGHT: f32 = 24.0;
+const TEXT_SIZE: f32 = 24.0;
+
+/// A progress bar across the middle of a `screen` sized window.
+pub fn draw_loading_screen(progress: &LoadProgress, screen: Vec2, renderer: &mut dyn Renderer) {
+    renderer.set_default_camera();
+    renderer.clear_background(Color::new(0.0, 0.0, 0.0, 1.0));
+
+    let width = screen.x * 0.6;
+    let x = (screen.x - width) / 2.0;
+    let y = (screen.y - BAR_HEIGHT) / 2.0;
+    renderer.draw_rectangle(x, y, width, BAR_HEIGHT, DARKGRAY);
+    renderer.draw_rectangle(x, y, width * progress.fraction(), BAR_HEIGHT, WHITE);
+    renderer.draw_text(
+        &format!("Loading {}/{}", progress.finished, progress.total),
+        x,
+        y - TEXT_SIZE / 2.0,
+        TEXT_SIZE,
+        WHITE,
+    );
+}
+
+/// Lists every file that failed to load, one per line.
+pub fn draw_load_errors(progress: &LoadProgress, renderer: &mut dyn Renderer) {
+    renderer.set_default_camera();
+    renderer.clear_background(Color::new(0.0, 0.0, 0.0, 1.0));
+
+    let mut y = TEXT_SIZE * 2.0;
+    renderer.draw_text("Couldn't load:", TEXT_SIZE, y, TEXT_SIZE, RED);
+    for (path, reason) in &progress.failed {
+        y += TEXT_SIZE * 1.5;
+        renderer.draw_text(&format!("{path}: {reason}"), TEXT_SIZE, y, TEXT_SIZE, WHITE);
+    }
+}
+
+/// Loads every entry in `manifest` at once into a new [`AssetServer`],
+/// drawing a progress bar until they're all done. Paths listed more than once
+/// are loaded once.
+///
+/// If any file fails, this shows the list of failures and never returns,
+/// since the game can't start without its assets.
+pub async fn load_assets(manifest: &AssetManifest) -> AssetServer {
+    let mut renderer = MacroquadRenderer;
+    let mut entries: Vec<&ManifestEntry> = vec![];
+    for entry in &manifest.entries {
+        if !entries.contains(&entry) {
+            entries.push(entry);
+        }
+    }
+    let tasks: Vec<(String, Coroutine<Result<Loaded, String>>)> = entries
+        .into_iter()
+        .map(|entry| {
+            (
+                entry.path.clone(),
+                start_coroutine(load_entry(entry.clone())),
+            )
+        })
+        .collect();
+
+    let mut progress = LoadProgress {
+        total: tasks.len(),
+        ..Default::default()
+    };
+    loop {
+        progress.finished = tasks.iter().filter(|(_, task)| task.is_done()).count();
+        if progress.finished == progress.total {
+            break;
+        }
+        draw_loading_screen(
+            &progress,
+            Vec2::new(screen_width(), screen_height()),
+            &mut renderer,
+        );
+        next_frame().await;
+    }
+
+    let mut assets = AssetServer::new();
+    for (path, task) in tasks {
+        match task.retrieve() {
+            Some(Ok(asset)) => assets.replace_loaded(&path, asset),
+            Some(Err(reason)) => progress.failed.push((path, reason)),
+            None => progress.failed.push((path, "stopped".to_string())),
+        }
+    }
+    if progress.failed.is_empty() {
+        return assets;
+    }
+    loop {
+        draw_load_errors(&progress, &mut renderer);
+        next_frame().await;
+    }
+}
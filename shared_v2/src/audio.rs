@@ -0,0 +1,280 @@
+use crate::{Handle, MemoryStorage, Sound, Storage};
+
+/// Plays decoded sounds. [`Audio`] decides what plays and how loud.
+pub trait AudioBackend {
+    fn play(&mut self, sound: Handle<Sound>, looped: bool, volume: f32);
+    fn stop(&mut self, sound: Handle<Sound>);
+    fn set_volume(&mut self, sound: Handle<Sound>, volume: f32);
+}
+
+/// Plays nothing. The default, for tests and builds without audio.
+#[derive(Debug, Default)]
+pub struct NullAudio;
+
+impl AudioBackend for NullAudio {
+    fn play(&mut self, _sound: Handle<Sound>, _looped: bool, _volume: f32) {}
+    fn stop(&mut self, _sound: Handle<Sound>) {}
+    fn set_volume(&mut self, _sound: Handle<Sound>, _volume: f32) {}
+}
+
+/// One call made on a [`RecordingAudio`].
+#[derive(Debug, Clone, PartialEq)]
+pub enum AudioCall {
+    Play {
+        sound: Handle<Sound>,
+        looped: bool,
+        volume: f32,
+    },
+    Stop(Handle<Sound>),
+    SetVolume(Handle<Sound>, f32),
+}
+
+/// Silent like [`NullAudio`], but keeps every call so tests can check what
+/// gameplay asked to hear. Clones share the same log, so keep one and hand
+/// the other to [`Audio::new`].
+#[derive(Debug, Default, Clone)]
+pub struct RecordingAudio {
+    calls: std::rc::Rc<std::cell::RefCell<Vec<AudioCall>>>,
+}
+
+impl RecordingAudio {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn calls(&self) -> Vec<AudioCall> {
+        self.calls.borrow().clone()
+    }
+}
+
+impl AudioBackend for RecordingAudio {
+    fn play(&mut self, sound: Handle<Sound>, looped: bool, volume: f32) {
+        self.calls.borrow_mut().push(AudioCall::Play {
+            sound,
+            looped,
+            volume,
+        });
+    }
+
+    fn stop(&mut self, sound: Handle<Sound>) {
+        self.calls.borrow_mut().push(AudioCall::Stop(sound));
+    }
+
+    fn set_volume(&mut self, sound: Handle<Sound>, volume: f32) {
+        self.calls
+            .borrow_mut()
+            .push(AudioCall::SetVolume(sound, volume));
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Bus {
+    Music,
+    Sfx,
+}
+
+/// Volume levels from 0 to 1. Each bus is scaled by `master`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Volumes {
+    pub master: f32,
+    pub music: f32,
+    pub sfx: f32,
+    pub muted: bool,
+}
+
+impl Default for Volumes {
+    fn default() -> Self {
+        Self {
+            master: 1.0,
+            music: 1.0,
+            sfx: 1.0,
+            muted: false,
+        }
+    }
+}
+
+impl Volumes {
+    /// What a sound on `bus` actually plays at.
+    pub fn effective(&self, bus: Bus) -> f32 {
+        if self.muted {
+            return 0.0;
+        }
+        let level = match bus {
+            Bus::Music => self.music,
+            Bus::Sfx => self.sfx,
+        };
+        self.master * level
+    }
+}
+
+const MASTER_KEY: &str = "audio.master";
+const MUSIC_KEY: &str = "audio.music";
+const SFX_KEY: &str = "audio.sfx";
+const MUTED_KEY: &str = "audio.muted";
+
+/// Background music, one-shot effects and the volume buses they play on.
+///
+/// Volume and mute are saved to [`Storage`] whenever they change and read
+/// back by [`Audio::with_storage`], so they carry over between sessions.
+pub struct Audio {
+    backend: Box<dyn AudioBackend>,
+    storage: Box<dyn Storage>,
+    volumes: Volumes,
+    music: Option<Handle<Sound>>,
+}
+
+impl Default for Audio {
+    fn default() -> Self {
+        Self::new(Box::new(NullAudio))
+    }
+}
+
+impl Audio {
+    pub fn new(backend: Box<dyn AudioBackend>) -> Self {
+        Self {
+            backend,
+            storage: Box::new(MemoryStorage::default()),
+            volumes: Volumes::default(),
+            music: None,
+        }
+    }
+
+    /// Restores the volumes saved in `storage`, and saves future changes
+    /// there.
+    pub fn with_storage(mut self, storage: Box<dyn Storage>) -> Self {
+        let read = |key, default: f32| {
+            storage
+                .get(key)
+                .and_then(|v| v.parse::<f32>().ok())
+                .map_or(default, |v| v.clamp(0.0, 1.0))
+        };
+        let defaults = Volumes::default();
+        self.volumes = Volumes {
+            master: read(MASTER_KEY, defaults.master),
+            music: read(MUSIC_KEY, defaults.music),
+            sfx: read(SFX_KEY, defaults.sfx),
+            muted: storage.get(MUTED_KEY).is_some_and(|v| v == "true"),
+        };
+        self.storage = storage;
+        self.apply_music_volume();
+        self
+    }
+
+    pub fn volumes(&self) -> Volumes {
+        self.volumes
+    }
+
+    /// The track [`Audio::play_music`] last started, if it's still playing.
+    pub fn music(&self) -> Option<Handle<Sound>> {
+        self.music
+    }
+
+    /// Loops `sound` as background music, replacing any current track.
+    /// Asking for the track that's already playing leaves it be.
+    pub fn play_music(&mut self, sound: Handle<Sound>) {
+        if self.music == Some(sound) {
+            return;
+        }
+        self.stop_music();
+        self.backend
+            .play(sound, true, self.volumes.effective(Bus::Music));
+        self.music = Some(sound);
+    }
+
+    pub fn stop_music(&mut self) {
+        if let Some(music) = self.music.take() {
+            self.backend.stop(music);
+        }
+    }
+
+    /// Plays `sound` once. Nothing is sent to the backend while muted.
+    pub fn play_sfx(&mut self, sound: Handle<Sound>) {
+        let volume = self.volumes.effective(Bus::Sfx);
+        if volume > 0.0 {
+            self.backend.play(sound, false, volume);
+        }
+    }
+
+    pub fn set_master_volume(&mut self, volume: f32) {
+        self.volumes.master = volume.clamp(0.0, 1.0);
+        self.storage
+            .set(MASTER_KEY, &self.volumes.master.to_string());
+        self.apply_music_volume();
+    }
+
+    pub fn set_music_volume(&mut self, volume: f32) {
+        self.volumes.music = volume.clamp(0.0, 1.0);
+        self.storage.set(MUSIC_KEY, &self.volumes.music.to_string());
+        self.apply_music_volume();
+    }
+
+    pub fn set_sfx_volume(&mut self, volume: f32) {
+        self.volumes.sfx = volume.clamp(0.0, 1.0);
+        self.storage.set(SFX_KEY, &self.volumes.sfx.to_string());
+    }
+
+    pub fn set_muted(&mut self, muted: bool) {
+        self.volumes.muted = muted;
+        self.storage.set(MUTED_KEY, &muted.to_string());
+        self.apply_music_volume();
+    }
+
+    pub fn toggle_mute(&mut self) {
+        self.set_muted(!self.volumes.muted);
+    }
+
+    fn apply_music_volume(&mut self) {
+        if let Some(music) = self.music {
+            let volume = self.volumes.effective(Bus::Music);
+            self.backend.set_volume(music, volume);
+        }
+    }
+}
+
+/// Plays sounds through macroquad. Needs the `audio` feature.
+#[cfg(feature = "audio")]
+pub struct MacroquadAudio {
+    sounds: std::collections::HashMap<Handle<Sound>, macroquad::audio::Sound>,
+}
+
+#[cfg(feature = "audio")]
+impl MacroquadAudio {
+    /// Decodes every sound in `assets`. Ones that fail to decode stay
+    /// silent rather than stopping the game.
+    pub async fn new(assets: &crate::AssetServer) -> Self {
+        let mut sounds = std::collections::HashMap::new();
+        for (handle, sound) in assets.iter::<Sound>() {
+            match macroquad::audio::load_sound_from_bytes(&sound.bytes).await {
+                Ok(decoded) => {
+                    sounds.insert(handle, decoded);
+                }
+                Err(e) => macroquad::logging::warn!("couldn't decode {handle:?}: {e}"),
+            }
+        }
+        Self { sounds }
+    }
+}
+
+#[cfg(feature = "audio")]
+impl AudioBackend for MacroquadAudio {
+    fn play(&mut self, sound: Handle<Sound>, looped: bool, volume: f32) {
+        if let Some(sound) = self.sounds.get(&sound) {
+            macroquad::audio::play_sound(
+                sound,
+                macroquad::audio::PlaySoundParams { looped, volume },
+            );
+        }
+    }
+
+    fn stop(&mut self, sound: Handle<Sound>) {
+        if let Some(sound) = self.sounds.get(&sound) {
+            macroquad::audio::stop_sound(sound);
+        }
+    }
+
+    fn set_volume(&mut self, sound: Handle<Sound>, volume: f32) {
+        if let Some(sound) = self.sounds.get(&sound) {
+            macroquad::audio::set_sound_volume(sound, volume);
+        }
+    }
+}
@@ -0,0 +1,173 @@
+use macroquad::camera::Camera2D;
+use macroquad::math::{Rect, Vec2, vec2};
+
+use crate::{GameState, Input, Rng, Tag, VirtualCanvas, World};
+
+/// How far the view moves at full trauma, in canvas units.
+pub const MAX_SHAKE: f32 = 16.0;
+/// Trauma lost per second, so a full shake settles in under a second.
+pub const TRAUMA_DECAY: f32 = 1.5;
+/// The closest a [`Camera`] zooms out. Keeps a zero or negative zoom from
+/// dividing by zero or turning the view inside out.
+pub const MIN_ZOOM: f32 = 0.01;
+
+/// Keeps an entity in view, moving only once it leaves the dead zone.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Follow {
+    pub tag: Tag,
+    /// Half the size of the box around the view's centre the entity can
+    /// move in freely, in world units.
+    pub dead_zone: Vec2,
+    /// How quickly the view catches up, as a share of the gap closed per
+    /// second. Infinite snaps straight there.
+    pub speed: f32,
+}
+
+/// Where the game looks, drawn through a [`VirtualCanvas`].
+///
+/// It can follow an entity, stay inside level bounds, zoom, and shake. Shake
+/// is driven by trauma: events add some with [`Camera::add_trauma`], it
+/// wears off over time, and the view shakes by its square, so small knocks
+/// barely show and big ones kick. Shake has its own randomness, so it never
+/// changes a replay.
+pub struct Camera {
+    /// The world point at the centre of the view, before shake.
+    pub position: Vec2,
+    /// Canvas pixels per world unit. Above 1 shows less of the world. Used
+    /// as at least [`MIN_ZOOM`].
+    pub zoom: f32,
+    pub follow: Option<Follow>,
+    /// The world area the view keeps inside. A view bigger than the bounds
+    /// is centred on them.
+    pub bounds: Option<Rect>,
+    trauma: f32,
+    shake: Vec2,
+    rng: Rng,
+}
+
+impl Default for Camera {
+    fn default() -> Self {
+        Self::new(VirtualCanvas::default().target)
+    }
+}
+
+impl Camera {
+    pub fn new(position: Vec2) -> Self {
+        Self {
+            position,
+            zoom: 1.0,
+            follow: None,
+            bounds: None,
+            trauma: 0.0,
+            shake: Vec2::ZERO,
+            rng: Rng::new(0),
+        }
+    }
+
+    pub fn with_zoom(mut self, zoom: f32) -> Self {
+        self.zoom = zoom.max(MIN_ZOOM);
+        self
+    }
+
+    pub fn with_bounds(mut self, bounds: Rect) -> Self {
+        self.bounds = Some(bounds);
+        self
+    }
+
+    pub fn following(mut self, tag: Tag, dead_zone: Vec2, speed: f32) -> Self {
+        self.follow = Some(Follow {
+            tag,
+            dead_zone: dead_zone.abs(),
+            speed,
+        });
+        self
+    }
+
+    /// Shakes the view, from 0 for nothing to 1 for as hard as it goes.
+    /// Adds up, so a burst of hits shakes harder than one.
+    pub fn add_trauma(&mut self, amount: f32) {
+        self.trauma = (self.trauma + amount).clamp(0.0, 1.0);
+    }
+
+    pub fn trauma(&self) -> f32 {
+        self.trauma
+    }
+
+    /// Moves towards `focus`, if there's one to follow, then keeps in
+    /// bounds and shakes, for a step of `dt` seconds.
+    pub fn update(&mut self, dt: f32, focus: Option<Vec2>, canvas: &VirtualCanvas) {
+        if let (Some(follow), Some(focus)) = (self.follow, focus) {
+            // The nearest view that has focus inside the dead zone.
+            let gap = focus - self.position;
+            let dead_zone = follow.dead_zone.abs();
+            let wanted = self.position + gap - gap.clamp(-dead_zone, dead_zone);
+            let share = 1.0 - (-follow.speed * dt).exp();
+            self.position += (wanted - self.position) * share;
+        }
+        self.position = self.clamped(self.position, canvas);
+
+        self.trauma = (self.trauma - TRAUMA_DECAY * dt).max(0.0);
+        let strength = MAX_SHAKE * self.trauma * self.trauma / self.scale();
+        self.shake = vec2(self.rng.gen_range(-1.0, 1.0), self.rng.gen_range(-1.0, 1.0)) * strength;
+    }
+
+    fn clamped(&self, position: Vec2, canvas: &VirtualCanvas) -> Vec2 {
+        let Some(bounds) = self.bounds else {
+            return position;
+        };
+        let half = canvas.size / (2.0 * self.scale());
+        let clamp = |at: f32, min: f32, max: f32, half: f32| {
+            if max - min < half * 2.0 {
+                (min + max) / 2.0
+            } else {
+                at.clamp(min + half, max - half)
+            }
+        };
+        vec2(
+            clamp(position.x, bounds.left(), bounds.right(), half.x),
+            clamp(position.y, bounds.top(), bounds.bottom(), half.y),
+        )
+    }
+
+    fn scale(&self) -> f32 {
+        self.zoom.max(MIN_ZOOM)
+    }
+
+    /// The world point at the centre of the view, shake included.
+    pub fn eye(&self) -> Vec2 {
+        self.position + self.shake
+    }
+
+    /// `canvas` looking through this camera.
+    pub fn view(&self, canvas: &VirtualCanvas) -> Camera2D {
+        let eye = self.eye();
+        let mut camera = canvas.with_target(eye.x, eye.y).camera();
+        camera.zoom *= self.scale();
+        camera
+    }
+
+    /// The screen pixel showing world point `point`.
+    pub fn world_to_screen(&self, canvas: &VirtualCanvas, point: Vec2) -> Vec2 {
+        let eye = self.eye();
+        canvas
+            .with_target(eye.x, eye.y)
+            .world_to_screen(eye + (point - eye) * self.scale())
+    }
+
+    /// The world point under screen pixel `point`, such as the mouse.
+    pub fn screen_to_world(&self, canvas: &VirtualCanvas, point: Vec2) -> Vec2 {
+        let eye = self.eye();
+        eye + (canvas.with_target(eye.x, eye.y).screen_to_world(point) - eye) / self.scale()
+    }
+}
+
+/// Updates [`GameState::camera`], following the first entity with its
+/// [`Follow::tag`].
+pub fn camera_system(world: &mut World, state: &mut GameState, input: &Input) {
+    let focus = state
+        .camera
+        .follow
+        .and_then(|follow| world.with_tag(follow.tag).next())
+        .map(|entity| entity.transform.center());
+    state.camera.update(input.dt, focus, &state.canvas);
+}
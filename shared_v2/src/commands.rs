@@ -0,0 +1,48 @@
+use std::collections::BTreeSet;
+
+use crate::{Entity, EntityId, World};
+
+/// Structural changes queued by systems and applied by [`World::flush`].
+///
+/// Nothing is added or removed until the flush. Despawns are queued by
+/// [`EntityId`], so they still hit the right entity if `World::entities` is
+/// reordered in the meantime.
+#[derive(Default)]
+pub struct Commands {
+    spawns: Vec<Entity>,
+    despawns: BTreeSet<EntityId>,
+}
+
+impl Commands {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn spawn(&mut self, entity: Entity) {
+        self.spawns.push(entity);
+    }
+
+    /// Queues removal of the entity with `id`.
+    pub fn despawn(&mut self, id: EntityId) {
+        self.despawns.insert(id);
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.spawns.is_empty() && self.despawns.is_empty()
+    }
+}
+
+impl World {
+    /// Applies queued despawns, then queued spawns. Despawns of entities
+    /// already gone are ignored.
+    pub fn flush(&mut self) {
+        let despawns = std::mem::take(&mut self.commands.despawns);
+        if !despawns.is_empty() {
+            self.entities
+                .retain(|entity| !despawns.contains(&entity.id()));
+        }
+        for entity in std::mem::take(&mut self.commands.spawns) {
+            self.spawn(entity);
+        }
+    }
+}
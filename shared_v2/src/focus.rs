@@ -0,0 +1,78 @@
+/// A frame this long means the game wasn't being drawn, so the player has
+/// most likely looked away. The usual threshold for [`FocusWatcher::new`].
+pub const STALL_SECONDS: f32 = 0.5;
+
+/// Notices when the window or browser tab loses focus, so the game can pause
+/// itself rather than carry on unseen.
+///
+/// On wasm this needs the `focus` plugin from `web/template.txt`, which
+/// counts blur and hide events. Macroquad doesn't pass focus events on to
+/// desktop games, so there a stalled frame stands in for them: minimising or
+/// dragging the window stops frames until it's back.
+///
+/// A stall can't say why the frame was slow, so any frame at least as long
+/// as the threshold counts, focused or not. A hitch on a busy machine pauses
+/// the game too. The first poll never reports a loss, as the first frame
+/// takes in the game's loading.
+#[derive(Debug)]
+pub struct FocusWatcher {
+    stall_seconds: f32,
+    polled: bool,
+    #[cfg(target_arch = "wasm32")]
+    seen: u32,
+}
+
+impl Default for FocusWatcher {
+    fn default() -> Self {
+        Self::new(STALL_SECONDS)
+    }
+}
+
+impl FocusWatcher {
+    /// Treats frames of `stall_seconds` or longer as lost focus.
+    pub fn new(stall_seconds: f32) -> Self {
+        Self {
+            stall_seconds,
+            polled: false,
+            #[cfg(target_arch = "wasm32")]
+            seen: 0,
+        }
+    }
+
+    /// Whether focus was lost since the last poll, given the time the last
+    /// frame took.
+    pub fn poll(&mut self, frame_time: f32) -> bool {
+        let lost = self.lost_focus() || frame_time >= self.stall_seconds;
+        std::mem::replace(&mut self.polled, true) && lost
+    }
+}
+
+#[cfg(target_arch = "wasm32")]
+unsafe extern "C" {
+    fn focus_lost_count() -> u32;
+}
+
+// Lets the JS plugin check it matches this code.
+#[cfg(target_arch = "wasm32")]
+#[unsafe(no_mangle)]
+pub extern "C" fn focus_crate_version() -> u32 {
+    1
+}
+
+#[cfg(target_arch = "wasm32")]
+impl FocusWatcher {
+    fn lost_focus(&mut self) -> bool {
+        // SAFETY: the plugin only returns a counter.
+        let count = unsafe { focus_lost_count() };
+        let lost = count != self.seen;
+        self.seen = count;
+        lost
+    }
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+impl FocusWatcher {
+    fn lost_focus(&mut self) -> bool {
+        false
+    }
+}
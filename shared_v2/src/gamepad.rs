@@ -0,0 +1,310 @@
+use std::cell::RefCell;
+use std::rc::Rc;
+
+use macroquad::math::{Vec2, vec2};
+
+/// How far a stick can sit from centre and still read as centred.
+pub const DEFAULT_DEAD_ZONE: f32 = 0.2;
+
+/// A button in the browser's standard gamepad layout, named by position so
+/// South is A on one pad and Cross on another.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum PadButton {
+    South,
+    East,
+    West,
+    North,
+    LeftShoulder,
+    RightShoulder,
+    LeftTrigger,
+    RightTrigger,
+    Select,
+    Start,
+    LeftStick,
+    RightStick,
+    DPadUp,
+    DPadDown,
+    DPadLeft,
+    DPadRight,
+}
+
+impl PadButton {
+    /// In the order of the standard layout, so a button's index is its
+    /// position here.
+    pub const ALL: [PadButton; 16] = [
+        PadButton::South,
+        PadButton::East,
+        PadButton::West,
+        PadButton::North,
+        PadButton::LeftShoulder,
+        PadButton::RightShoulder,
+        PadButton::LeftTrigger,
+        PadButton::RightTrigger,
+        PadButton::Select,
+        PadButton::Start,
+        PadButton::LeftStick,
+        PadButton::RightStick,
+        PadButton::DPadUp,
+        PadButton::DPadDown,
+        PadButton::DPadLeft,
+        PadButton::DPadRight,
+    ];
+
+    pub fn index(self) -> usize {
+        self as usize
+    }
+}
+
+/// A stick axis, in the standard layout's order. Right and down are
+/// positive.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum PadAxis {
+    LeftX,
+    LeftY,
+    RightX,
+    RightY,
+}
+
+impl PadAxis {
+    pub const ALL: [PadAxis; 4] = [
+        PadAxis::LeftX,
+        PadAxis::LeftY,
+        PadAxis::RightX,
+        PadAxis::RightY,
+    ];
+
+    pub fn index(self) -> usize {
+        self as usize
+    }
+}
+
+/// One pad's raw readings, before the dead zone.
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub struct PadState {
+    /// How far each button is pressed, from 0 to 1, by [`PadButton::index`].
+    pub buttons: [f32; 16],
+    /// By [`PadAxis::index`], from -1 to 1.
+    pub axes: [f32; 4],
+}
+
+impl PadState {
+    pub fn with_button(mut self, button: PadButton, value: f32) -> Self {
+        self.buttons[button.index()] = value;
+        self
+    }
+
+    pub fn with_axis(mut self, axis: PadAxis, value: f32) -> Self {
+        self.axes[axis.index()] = value;
+        self
+    }
+}
+
+/// A pad being plugged in or pulled out, by slot.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum PadEvent {
+    Connected(usize),
+    Disconnected(usize),
+}
+
+/// Reads every pad slot, with `None` for empty ones.
+pub trait GamepadBackend {
+    fn poll(&mut self) -> Vec<Option<PadState>>;
+}
+
+/// Never sees a pad. The default, and what desktop builds use: macroquad
+/// has no gamepad support of its own.
+#[derive(Debug, Default)]
+pub struct NoGamepads;
+
+impl GamepadBackend for NoGamepads {
+    fn poll(&mut self) -> Vec<Option<PadState>> {
+        vec![]
+    }
+}
+
+/// Pads set by hand, for tests. Clones share their slots, so a test can keep
+/// one and hand the other to [`Gamepads`].
+#[derive(Debug, Default, Clone)]
+pub struct SimulatedPads(Rc<RefCell<Vec<Option<PadState>>>>);
+
+impl SimulatedPads {
+    /// Plugs a pad into `slot` if it's empty, and sets its readings.
+    pub fn set(&self, slot: usize, state: PadState) {
+        let mut slots = self.0.borrow_mut();
+        if slots.len() <= slot {
+            slots.resize(slot + 1, None);
+        }
+        slots[slot] = Some(state);
+    }
+
+    pub fn unplug(&self, slot: usize) {
+        if let Some(pad) = self.0.borrow_mut().get_mut(slot) {
+            *pad = None;
+        }
+    }
+}
+
+impl GamepadBackend for SimulatedPads {
+    fn poll(&mut self) -> Vec<Option<PadState>> {
+        self.0.borrow().clone()
+    }
+}
+
+/// Reads the browser's Gamepad API through the `gamepad` plugin from
+/// `web/template.txt`.
+#[cfg(target_arch = "wasm32")]
+#[derive(Debug, Default)]
+pub struct WebGamepads;
+
+#[cfg(target_arch = "wasm32")]
+unsafe extern "C" {
+    fn gamepad_slots() -> u32;
+    fn gamepad_connected(slot: u32) -> bool;
+    fn gamepad_button(slot: u32, button: u32) -> f32;
+    fn gamepad_axis(slot: u32, axis: u32) -> f32;
+}
+
+// Lets the JS plugin check it matches this code.
+#[cfg(target_arch = "wasm32")]
+#[unsafe(no_mangle)]
+pub extern "C" fn gamepad_crate_version() -> u32 {
+    1
+}
+
+#[cfg(target_arch = "wasm32")]
+impl GamepadBackend for WebGamepads {
+    fn poll(&mut self) -> Vec<Option<PadState>> {
+        // SAFETY: the plugin only reads numbers out of `navigator.getGamepads`.
+        unsafe {
+            (0..gamepad_slots())
+                .map(|slot| {
+                    if !gamepad_connected(slot) {
+                        return None;
+                    }
+                    let mut state = PadState::default();
+                    for (i, value) in state.buttons.iter_mut().enumerate() {
+                        *value = gamepad_button(slot, i as u32);
+                    }
+                    for (i, value) in state.axes.iter_mut().enumerate() {
+                        *value = gamepad_axis(slot, i as u32);
+                    }
+                    Some(state)
+                })
+                .collect()
+        }
+    }
+}
+
+/// The pad backend this build should use.
+pub fn default_gamepads() -> Box<dyn GamepadBackend> {
+    #[cfg(target_arch = "wasm32")]
+    return Box::new(WebGamepads);
+    #[cfg(not(target_arch = "wasm32"))]
+    Box::new(NoGamepads)
+}
+
+/// Every connected pad, polled once a frame.
+///
+/// Readings from all pads are combined, so whichever pad the player picks up
+/// works without choosing one first. Sticks have a radial dead zone, and
+/// what's left is rescaled so movement still starts from zero.
+pub struct Gamepads {
+    backend: Box<dyn GamepadBackend>,
+    pads: Vec<Option<PadState>>,
+    previous: Vec<Option<PadState>>,
+    pub dead_zone: f32,
+}
+
+impl Default for Gamepads {
+    fn default() -> Self {
+        Self::new(Box::new(NoGamepads))
+    }
+}
+
+impl Gamepads {
+    pub fn new(backend: Box<dyn GamepadBackend>) -> Self {
+        Self {
+            backend,
+            pads: vec![],
+            previous: vec![],
+            dead_zone: DEFAULT_DEAD_ZONE,
+        }
+    }
+
+    pub fn with_dead_zone(mut self, dead_zone: f32) -> Self {
+        self.dead_zone = dead_zone.clamp(0.0, 0.99);
+        self
+    }
+
+    /// Reads the pads for this frame, returning which were plugged in or
+    /// pulled out since the last one.
+    pub fn update(&mut self) -> Vec<PadEvent> {
+        let pads = self.backend.poll();
+        let slots = pads.len().max(self.pads.len());
+        let events = (0..slots)
+            .filter_map(|slot| {
+                let was = self.pads.get(slot).is_some_and(Option::is_some);
+                let is = pads.get(slot).is_some_and(Option::is_some);
+                match (was, is) {
+                    (false, true) => Some(PadEvent::Connected(slot)),
+                    (true, false) => Some(PadEvent::Disconnected(slot)),
+                    _ => None,
+                }
+            })
+            .collect();
+        self.previous = std::mem::replace(&mut self.pads, pads);
+        events
+    }
+
+    /// The slots with a pad in them.
+    pub fn connected(&self) -> impl Iterator<Item = usize> + '_ {
+        self.pads
+            .iter()
+            .enumerate()
+            .filter_map(|(slot, pad)| pad.map(|_| slot))
+    }
+
+    /// How far `button` is pressed on whichever pad presses it most.
+    pub fn button(&self, button: PadButton) -> f32 {
+        self.pads
+            .iter()
+            .flatten()
+            .map(|pad| pad.buttons[button.index()])
+            .fold(0.0, f32::max)
+    }
+
+    /// Whether `button` went down this frame on any pad.
+    pub fn button_pressed(&self, button: PadButton) -> bool {
+        let down = |pad: &PadState| pad.buttons[button.index()] >= 0.5;
+        self.pads.iter().enumerate().any(|(slot, pad)| {
+            let was = self.previous.get(slot).copied().flatten();
+            pad.is_some_and(|pad| down(&pad)) && !was.is_some_and(|pad| down(&pad))
+        })
+    }
+
+    /// `axis` after the dead zone, from whichever pad pushes its stick
+    /// furthest.
+    pub fn axis(&self, axis: PadAxis) -> f32 {
+        let (stick, x) = match axis {
+            PadAxis::LeftX => (0, true),
+            PadAxis::LeftY => (0, false),
+            PadAxis::RightX => (2, true),
+            PadAxis::RightY => (2, false),
+        };
+        self.pads
+            .iter()
+            .flatten()
+            .map(|pad| self.stick(vec2(pad.axes[stick], pad.axes[stick + 1])))
+            .max_by(|a, b| a.length().total_cmp(&b.length()))
+            .map_or(0.0, |v| if x { v.x } else { v.y })
+    }
+
+    fn stick(&self, raw: Vec2) -> Vec2 {
+        let length = raw.length();
+        if length <= self.dead_zone {
+            return Vec2::ZERO;
+        }
+        let scaled = ((length - self.dead_zone) / (1.0 - self.dead_zone)).min(1.0);
+        raw / length * scaled
+    }
+}
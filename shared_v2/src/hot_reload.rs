@@ -0,0 +1,144 @@
+use macroquad::logging::{info, warn};
+use macroquad::time::get_time;
+
+use crate::{AssetManifest, AssetServer};
+
+/// How often [`AssetWatcher::poll`] looks at the files on disk, in seconds.
+pub const POLL_INTERVAL: f64 = 0.5;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum ReloadEvent {
+    Reloaded(String),
+    /// The file changed but couldn't be loaded, so the old asset stays.
+    Failed {
+        path: String,
+        reason: String,
+    },
+}
+
+/// Watches the files in an [`AssetManifest`] and reloads them into an
+/// [`AssetServer`] when they change on disk.
+///
+/// Reloaded assets take the place of the old ones behind the same
+/// [`crate::Handle`], so entities and game state are left alone. Shaders are
+/// recompiled, and one that doesn't compile is reported without replacing
+/// the working material. Files are polled by modification time, which needs
+/// no platform watcher. On wasm there's no filesystem to watch and the
+/// watcher does nothing.
+pub struct AssetWatcher {
+    #[cfg(not(target_arch = "wasm32"))]
+    files: Vec<native::WatchedFile>,
+    #[cfg(not(target_arch = "wasm32"))]
+    last_poll: f64,
+}
+
+impl AssetWatcher {
+    /// Watches every entry in `manifest`, resolving paths against `root`,
+    /// the folder given to `set_pc_assets_folder`.
+    #[cfg_attr(target_arch = "wasm32", allow(unused_variables))]
+    pub fn new(root: &str, manifest: &AssetManifest) -> Self {
+        Self {
+            #[cfg(not(target_arch = "wasm32"))]
+            files: native::watch(root, manifest),
+            #[cfg(not(target_arch = "wasm32"))]
+            last_poll: 0.0,
+        }
+    }
+
+    /// Reloads anything that changed, at most every [`POLL_INTERVAL`]
+    /// seconds of `time`. Meant to be called once a frame with
+    /// `macroquad::time::get_time()`.
+    #[cfg_attr(target_arch = "wasm32", allow(unused_variables))]
+    pub fn poll(&mut self, time: f64, assets: &mut AssetServer) -> Vec<ReloadEvent> {
+        #[cfg(not(target_arch = "wasm32"))]
+        if time - self.last_poll >= POLL_INTERVAL {
+            self.last_poll = time;
+            return self.check(assets);
+        }
+        vec![]
+    }
+
+    /// [`AssetWatcher::poll`] at the current time, logging what was reloaded
+    /// and what failed. Games call this once a frame.
+    pub fn reload_changed(&mut self, assets: &mut AssetServer) {
+        for event in self.poll(get_time(), assets) {
+            match event {
+                ReloadEvent::Reloaded(path) => info!("reloaded {path}"),
+                ReloadEvent::Failed { path, reason } => warn!("couldn't reload {path}: {reason}"),
+            }
+        }
+    }
+
+    /// Reloads anything that changed since the last check.
+    #[cfg_attr(target_arch = "wasm32", allow(unused_variables))]
+    pub fn check(&mut self, assets: &mut AssetServer) -> Vec<ReloadEvent> {
+        #[cfg(not(target_arch = "wasm32"))]
+        return native::check(&mut self.files, assets);
+        #[cfg(target_arch = "wasm32")]
+        vec![]
+    }
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+mod native {
+    use std::path::{Path, PathBuf};
+    use std::time::SystemTime;
+
+    use super::ReloadEvent;
+    use crate::assets::decode;
+    use crate::{AssetKind, AssetManifest, AssetServer};
+
+    pub(super) struct WatchedFile {
+        name: String,
+        kind: AssetKind,
+        path: PathBuf,
+        modified: Option<SystemTime>,
+    }
+
+    fn modified(path: &Path) -> Option<SystemTime> {
+        std::fs::metadata(path).and_then(|m| m.modified()).ok()
+    }
+
+    pub(super) fn watch(root: &str, manifest: &AssetManifest) -> Vec<WatchedFile> {
+        manifest
+            .entries
+            .iter()
+            .map(|entry| {
+                let path = Path::new(root).join(&entry.path);
+                WatchedFile {
+                    name: entry.path.clone(),
+                    kind: entry.kind,
+                    modified: modified(&path),
+                    path,
+                }
+            })
+            .collect()
+    }
+
+    pub(super) fn check(files: &mut [WatchedFile], assets: &mut AssetServer) -> Vec<ReloadEvent> {
+        let mut events = vec![];
+        for file in files {
+            let modified = modified(&file.path);
+            // A missing file is usually an editor mid-save; wait for it.
+            if modified.is_none() || modified == file.modified {
+                continue;
+            }
+            file.modified = modified;
+
+            let loaded = std::fs::read(&file.path)
+                .map_err(|e| e.to_string())
+                .and_then(|bytes| decode(file.kind, bytes));
+            events.push(match loaded {
+                Ok(asset) => {
+                    assets.replace_loaded(&file.name, asset);
+                    ReloadEvent::Reloaded(file.name.clone())
+                }
+                Err(reason) => ReloadEvent::Failed {
+                    path: file.name.clone(),
+                    reason,
+                },
+            });
+        }
+        events
+    }
+}
@@ -1,13 +1,54 @@
+mod actions;
+mod animation;
+mod assets;
+mod audio;
+mod camera;
+mod commands;
+mod focus;
+mod gamepad;
+mod hot_reload;
+mod parallax;
+mod replay;
+mod scene;
+mod storage;
 mod tests;
+mod text;
+mod touch;
+mod widgets;
+
+pub use crate::actions::*;
+pub use crate::animation::*;
+pub use crate::assets::*;
+pub use crate::audio::*;
+pub use crate::camera::*;
+pub use crate::commands::*;
+pub use crate::focus::*;
+pub use crate::gamepad::*;
+pub use crate::hot_reload::*;
+pub use crate::parallax::*;
+pub use crate::replay::*;
+pub use crate::scene::*;
+pub use crate::storage::*;
+pub use crate::text::*;
+pub use crate::touch::*;
+pub use crate::widgets::*;
+pub use shared::{
+    CollisionLayers, DEFAULT_STEP, DrawCall, FixedStep, MacroquadRenderer, RecordingRenderer,
+    Renderer, Rng, Scaling, StepInput, VirtualCanvas, fixed_text_size, placeholder_texture,
+};
+#[cfg(all(feature = "snapshot", not(target_arch = "wasm32")))]
+pub use shared::{
+    ImageRenderer, SnapshotDiff, Tolerance, assert_golden, compare_images, load_png, save_png,
+};
+
 use std::fmt::Debug;
 
 use macroquad::experimental::animation::AnimatedSprite;
 use macroquad::{
-    color::{Color, WHITE},
+    color::{BLACK, Color, WHITE},
     math::Rect,
-    shapes::draw_rectangle_lines,
-    texture::Texture2D,
 };
+use shared::SpatialHash;
 
 #[derive(Debug)]
 pub struct Transform {
@@ -27,8 +68,19 @@ pub struct Render {
 }
 
 pub struct Sprite {
-    pub texture: Texture2D,
+    pub texture: Handle<Texture>,
     pub sprite: AnimatedSprite,
+    /// Picks `texture` and the frame, when there's more than one clip.
+    pub animator: Option<Animator>,
+}
+
+impl Sprite {
+    /// See [`Animator::play`]. Does nothing without an animator.
+    pub fn play(&mut self, state: AnimationState) {
+        if let Some(animator) = &mut self.animator {
+            animator.play(state);
+        }
+    }
 }
 
 #[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
@@ -47,46 +99,130 @@ pub struct Physics {
     pub velocity: Velocity,
 }
 
+/// Names an entity for as long as it lives in a [`World`], unlike its index
+/// in [`World::entities`], which shifts as others come and go.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct EntityId(u64);
+
 pub struct Entity {
+    /// Given out when the entity joins a [`World`].
+    id: EntityId,
     pub transform: Rect,
+    /// `transform` as of the start of the last update, for interpolation.
+    pub previous_transform: Rect,
 
     pub tag: Option<Tag>,
+    pub collider: Option<CollisionLayers>,
 
     pub render: Option<Render>,
     pub sprite: Option<Sprite>,
+    pub parallax: Option<Parallax>,
 
     pub physics: Option<Physics>,
 }
 
 // entities and components
 pub struct World {
-    pub entities: Vec<Entity>,
+    entities: Vec<Entity>,
+    /// Spawns and despawns deferred until [`World::flush`].
+    pub commands: Commands,
+    next_id: u64,
 }
 
 pub struct GameState {
     pub score: f32,
-    pub game_over: bool,
+    /// Interpolation factor between the last two fixed updates, set by
+    /// [`Game::fixed_update`] for render systems.
+    pub alpha: f32,
+    /// Randomness for game logic, so runs can be replayed from a seed.
+    pub rng: Rng,
+    /// The play area. Games resize it to the window every frame and draw
+    /// through its camera; [`Game::render`] letterboxes around it.
+    pub canvas: VirtualCanvas,
+    /// Where the canvas looks. Moved by [`camera_system`].
+    pub camera: Camera,
+    pub assets: AssetServer,
+    pub audio: Audio,
+    /// Scene changes waiting for the end of the update.
+    pub transitions: Vec<Transition>,
 }
 
+/// One fixed update's worth of player input, usually made by
+/// [`ActionMap::input`]. The flags are one-shot presses; the axes are held.
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
 pub struct Input {
     pub dt: f32,
-    pub spacebar: bool,
+    pub jump: bool,
+    pub fire: bool,
+    pub confirm: bool,
+    pub pause: bool,
+    /// The window or tab lost focus, or a pad was pulled out. Pauses like
+    /// [`Input::pause`], but never resumes.
+    pub focus_lost: bool,
+    /// See [`Action::MoveX`].
+    pub move_x: f32,
+    /// See [`Action::MoveY`].
+    pub move_y: f32,
 }
 
 pub struct Systems {
     pub update: Vec<fn(&mut World, &mut GameState, &Input)>,
-    pub render: Vec<fn(&World, &GameState)>,
+    pub render: Vec<fn(&World, &GameState, &mut dyn Renderer)>,
 }
 
 pub struct Game {
     pub world: World,
     pub state: GameState,
+    /// Run under every scene, before the scene's own systems.
     pub systems: Systems,
+    scenes: Scenes,
+    pub clock: FixedStep<Input>,
+    /// When set, every input passed to [`Game::update`] is appended to it.
+    pub recording: Option<Recording>,
+}
+
+impl Default for World {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 impl World {
     pub fn new() -> Self {
-        Self { entities: vec![] }
+        Self {
+            entities: vec![],
+            commands: Commands::new(),
+            next_id: 0,
+        }
+    }
+
+    /// Live entities, in spawn order.
+    pub fn entities(&self) -> &[Entity] {
+        &self.entities
+    }
+
+    pub fn entities_mut(&mut self) -> &mut [Entity] {
+        &mut self.entities
+    }
+
+    /// Borrows the entities alongside the command buffer, so a system can
+    /// walk the world and queue changes in the same pass.
+    pub fn entities_and_commands(&mut self) -> (&mut [Entity], &mut Commands) {
+        (&mut self.entities, &mut self.commands)
+    }
+
+    pub fn find(&self, id: EntityId) -> Option<&Entity> {
+        self.entities.iter().find(|entity| entity.id == id)
+    }
+
+    pub fn find_mut(&mut self, id: EntityId) -> Option<&mut Entity> {
+        self.entities.iter_mut().find(|entity| entity.id == id)
+    }
+
+    /// Despawns every entity and drops any queued commands.
+    pub fn clear(&mut self) {
+        self.entities.clear();
+        self.commands = Commands::new();
     }
 
     pub fn with_tag(&self, tag: Tag) -> impl Iterator<Item = &Entity> {
@@ -101,10 +237,50 @@ impl World {
             .filter(move |entity| entity.tag.is_some_and(|e| e == tag))
     }
 
-    pub fn spawn(mut self, entity: Entity) -> Self {
-        self.entities.push(entity);
+    pub fn with_entity(mut self, entity: Entity) -> Self {
+        self.spawn(entity);
         self
     }
+
+    /// Adds `entity` to the world and returns the id allocated for it.
+    pub fn spawn(&mut self, mut entity: Entity) -> EntityId {
+        self.next_id += 1;
+        let id = EntityId(self.next_id);
+        entity.id = id;
+        self.entities.push(entity);
+        id
+    }
+
+    /// Indices of every pair of entities with a collider whose transforms
+    /// overlap and whose layers interact, sorted.
+    pub fn overlapping_pairs(&self) -> Vec<(usize, usize)> {
+        let colliders: Vec<usize> = (0..self.entities.len())
+            .filter(|&i| self.entities[i].collider.is_some())
+            .collect();
+        let rects: Vec<Rect> = colliders
+            .iter()
+            .map(|&i| self.entities[i].transform)
+            .collect();
+
+        SpatialHash::default()
+            .overlapping_pairs(&rects)
+            .into_iter()
+            .map(|(a, b)| (colliders[a], colliders[b]))
+            .filter(|&(a, b)| {
+                let (Some(a), Some(b)) = (self.entities[a].collider, self.entities[b].collider)
+                else {
+                    return false;
+                };
+                a.interacts(&b)
+            })
+            .collect()
+    }
+}
+
+impl Default for Physics {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 impl Physics {
@@ -116,9 +292,9 @@ impl Physics {
     }
 }
 
-pub fn debug(entities: &[Entity]) {
+pub fn debug(entities: &[Entity], renderer: &mut dyn Renderer) {
     for e in entities {
-        draw_rectangle_lines(
+        renderer.draw_rectangle_lines(
             e.transform.x,
             e.transform.y,
             e.transform.w,
@@ -129,13 +305,13 @@ pub fn debug(entities: &[Entity]) {
     }
 }
 
-pub fn debug_sprites(entities: &[Entity]) {
+pub fn debug_sprites(entities: &[Entity], renderer: &mut dyn Renderer) {
     for e in entities {
         let Some(sprite) = &e.sprite else {
             continue;
         };
         let frame = sprite.sprite.frame();
-        draw_rectangle_lines(
+        renderer.draw_rectangle_lines(
             frame.source_rect.x,
             frame.source_rect.y,
             48.0 * 2.0,
@@ -149,21 +325,74 @@ pub fn debug_sprites(entities: &[Entity]) {
 impl Entity {
     pub fn new(rect: Rect) -> Self {
         Self {
+            id: EntityId::default(),
             transform: rect,
+            previous_transform: rect,
             tag: None,
+            collider: None,
             render: None,
             sprite: None,
+            parallax: None,
             physics: None,
         }
     }
 
+    pub fn id(&self) -> EntityId {
+        self.id
+    }
+
+    /// The transform blended between the previous and current update.
+    pub fn interpolated(&self, alpha: f32) -> Rect {
+        let (a, b) = (self.previous_transform, self.transform);
+        Rect {
+            x: a.x + (b.x - a.x) * alpha,
+            y: a.y + (b.y - a.y) * alpha,
+            w: b.w,
+            h: b.h,
+        }
+    }
+
+    /// Moves the entity without interpolating from its old position, for
+    /// wrap-arounds and respawns.
+    pub fn teleport(&mut self, x: f32, y: f32) {
+        self.transform.x = x;
+        self.transform.y = y;
+        self.previous_transform = self.transform;
+    }
+
     pub fn with_render(mut self, color: Color) -> Self {
         self.render = Some(Render { color });
         self
     }
 
-    pub fn with_sprite(mut self, texture: Texture2D, sprite: AnimatedSprite) -> Self {
-        self.sprite = Some(Sprite { texture, sprite });
+    pub fn with_sprite(mut self, texture: Handle<Texture>, sprite: AnimatedSprite) -> Self {
+        self.sprite = Some(Sprite {
+            texture,
+            sprite,
+            animator: None,
+        });
+        self
+    }
+
+    /// A sprite with `animator`'s clips, cut into tiles of the given size.
+    ///
+    /// An animator with no clips has nothing to draw, so the entity is left
+    /// without a sprite.
+    pub fn with_animator(mut self, animator: Animator, tile_width: u32, tile_height: u32) -> Self {
+        let Some(texture) = animator.texture() else {
+            self.sprite = None;
+            return self;
+        };
+        self.sprite = Some(Sprite {
+            texture,
+            sprite: animator.sprite(tile_width, tile_height),
+            animator: Some(animator),
+        });
+        self
+    }
+
+    pub fn with_parallax(mut self, parallax: Parallax) -> Self {
+        self.parallax = Some(parallax);
         self
     }
 
@@ -172,12 +401,41 @@ impl Entity {
         self
     }
 
+    pub fn with_collider(mut self, layers: CollisionLayers) -> Entity {
+        self.collider = Some(layers);
+        self
+    }
+
     pub fn with_physics(mut self, physics: Physics) -> Entity {
         self.physics = Some(physics);
         self
     }
 }
 
+impl StepInput for Input {
+    fn with_dt(&self, dt: f32) -> Self {
+        Input { dt, ..*self }
+    }
+
+    fn merge(&mut self, later: &Self) {
+        self.jump |= later.jump;
+        self.fire |= later.fire;
+        self.confirm |= later.confirm;
+        self.pause |= later.pause;
+        self.focus_lost |= later.focus_lost;
+        self.move_x = later.move_x;
+        self.move_y = later.move_y;
+    }
+
+    fn consume_presses(&mut self) {
+        self.jump = false;
+        self.fire = false;
+        self.confirm = false;
+        self.pause = false;
+        self.focus_lost = false;
+    }
+}
+
 impl Systems {
     pub fn new() -> Self {
         Self {
@@ -187,26 +445,64 @@ impl Systems {
     }
 }
 
+impl Default for Systems {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Default for GameState {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl GameState {
     pub fn new() -> Self {
         Self {
             score: 0.0,
-            game_over: false,
+            alpha: 1.0,
+            rng: Rng::default(),
+            canvas: VirtualCanvas::default(),
+            camera: Camera::default(),
+            assets: AssetServer::new(),
+            audio: Audio::default(),
+            transitions: vec![],
         }
     }
 }
 
 impl Game {
     pub fn update(&mut self, input: &Input) {
+        if let Some(recording) = &mut self.recording {
+            recording.push(input);
+        }
+        for entity in self.world.entities_mut() {
+            entity.previous_transform = entity.transform;
+        }
         for system in &self.systems.update {
             system(&mut self.world, &mut self.state, input);
         }
+        self.update_scene(input);
+        self.world.flush();
+        self.apply_transitions();
+    }
+
+    /// Runs [`Game::update`] at the clock's fixed rate for one frame of
+    /// `frame_time` seconds, then records the interpolation alpha.
+    pub fn fixed_update(&mut self, frame_time: f32, input: &Input) {
+        let mut clock = std::mem::take(&mut self.clock);
+        clock.advance(frame_time, input, |input| self.update(input));
+        self.state.alpha = clock.alpha();
+        self.clock = clock;
     }
 
-    pub fn render(&self) {
+    pub fn render(&self, renderer: &mut dyn Renderer) {
         for system in &self.systems.render {
-            system(&self.world, &self.state);
+            system(&self.world, &self.state, renderer);
         }
+        self.render_scenes(renderer);
+        self.state.canvas.render_letterbox(renderer, BLACK);
     }
 }
 
@@ -216,7 +512,7 @@ impl Game {
         self
     }
 
-    pub fn with_render_system(mut self, system: fn(&World, &GameState)) -> Self {
+    pub fn with_render_system(mut self, system: fn(&World, &GameState, &mut dyn Renderer)) -> Self {
         self.systems.render.push(system);
         self
     }
@@ -229,10 +525,52 @@ impl Game {
         self
     }
 
-    pub fn with_render_systems(mut self, systems: Vec<fn(&World, &GameState)>) -> Self {
+    pub fn with_render_systems(
+        mut self,
+        systems: Vec<fn(&World, &GameState, &mut dyn Renderer)>,
+    ) -> Self {
         self.systems.render.extend(systems);
         self
     }
+
+    pub fn with_assets(mut self, assets: AssetServer) -> Self {
+        self.state.assets = assets;
+        self
+    }
+
+    pub fn with_audio(mut self, audio: Audio) -> Self {
+        self.state.audio = audio;
+        self
+    }
+
+    /// Also points [`GameState::camera`] at the canvas's target.
+    pub fn with_canvas(mut self, canvas: VirtualCanvas) -> Self {
+        self.state.canvas = canvas;
+        self.state.camera.position = canvas.target;
+        self
+    }
+
+    pub fn with_camera(mut self, camera: Camera) -> Self {
+        self.state.camera = camera;
+        self
+    }
+
+    pub fn with_seed(mut self, seed: u64) -> Self {
+        self.state.rng.reseed(seed);
+        self
+    }
+
+    /// Starts recording from the current RNG seed, so call it after
+    /// [`Game::with_seed`].
+    pub fn with_recording(mut self) -> Self {
+        self.recording = Some(Recording::new(self.state.rng.seed()));
+        self
+    }
+
+    pub fn with_fixed_step(mut self, step: f32) -> Self {
+        self.clock = FixedStep::new(step);
+        self
+    }
 }
 
 impl Game {
@@ -241,6 +579,9 @@ impl Game {
             world,
             state: GameState::new(),
             systems: Systems::new(),
+            scenes: Scenes::default(),
+            clock: FixedStep::default(),
+            recording: None,
         }
     }
 }
@@ -0,0 +1,164 @@
+use macroquad::color::{Color, WHITE};
+use macroquad::math::{Rect, Vec2, vec2};
+use macroquad::texture::DrawTextureParams;
+
+use crate::{AssetServer, GameState, Handle, Input, Renderer, Texture, World};
+
+/// One tiled image in a [`Parallax`] background.
+#[derive(Debug, Clone)]
+pub struct ParallaxLayer {
+    pub texture: Handle<Texture>,
+    /// Scroll speed in world units a second. Positive x scrolls left,
+    /// positive y scrolls towards negative y.
+    pub speed: Vec2,
+    /// Shifts the layer vertically from the entity's position.
+    pub offset: f32,
+    pub tint: Color,
+    /// How far each tile is pulled back over the one before it, to hide a
+    /// seam where the image's edges don't quite line up.
+    pub overlap: f32,
+    /// How far the layer has scrolled. [`Parallax::render`] wraps it to the
+    /// tile size, so it may grow without bound between calls to
+    /// [`Parallax::wrap`].
+    pub scroll: Vec2,
+}
+
+impl ParallaxLayer {
+    pub fn new(texture: Handle<Texture>) -> Self {
+        Self {
+            texture,
+            speed: Vec2::ZERO,
+            offset: 0.0,
+            tint: WHITE,
+            overlap: 0.0,
+            scroll: Vec2::ZERO,
+        }
+    }
+
+    pub fn with_speed(mut self, x: f32, y: f32) -> Self {
+        self.speed = vec2(x, y);
+        self
+    }
+
+    pub fn with_offset(mut self, offset: f32) -> Self {
+        self.offset = offset;
+        self
+    }
+
+    pub fn with_tint(mut self, tint: Color) -> Self {
+        self.tint = tint;
+        self
+    }
+
+    pub fn with_overlap(mut self, overlap: f32) -> Self {
+        self.overlap = overlap;
+        self
+    }
+}
+
+/// A scrolling background made of any number of layers, drawn back to front
+/// and tiled to an area.
+///
+/// On an entity, [`scroll_parallax`] and [`render_parallax`] drive it with
+/// the entity's transform as the area, so the background stops whenever the
+/// game stops updating. Games without a [`World`] call [`Parallax::scroll`]
+/// and [`Parallax::render`] themselves.
+#[derive(Debug, Clone, Default)]
+pub struct Parallax {
+    pub layers: Vec<ParallaxLayer>,
+    /// Draws the layers upside down, for cameras where y points up.
+    pub flip_y: bool,
+}
+
+impl Parallax {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_layer(mut self, layer: ParallaxLayer) -> Self {
+        self.layers.push(layer);
+        self
+    }
+
+    pub fn flipped(mut self) -> Self {
+        self.flip_y = true;
+        self
+    }
+
+    /// Moves every layer along by its speed for `dt` seconds.
+    pub fn scroll(&mut self, dt: f32) {
+        for layer in &mut self.layers {
+            layer.scroll += layer.speed * dt;
+        }
+    }
+
+    /// Wraps each layer's scroll to one tile of `size`, so long runs don't
+    /// lose precision. Rendering is unchanged.
+    pub fn wrap(&mut self, size: Vec2) {
+        for layer in &mut self.layers {
+            let step = size - Vec2::splat(layer.overlap);
+            if step.x > 0.0 {
+                layer.scroll.x = layer.scroll.x.rem_euclid(step.x);
+            }
+            if step.y > 0.0 {
+                layer.scroll.y = layer.scroll.y.rem_euclid(step.y);
+            }
+        }
+    }
+
+    /// Draws the layers tiled over `area`, each tile the size of `area`.
+    pub fn render(&self, assets: &AssetServer, area: Rect, renderer: &mut dyn Renderer) {
+        let size = vec2(area.w, area.h);
+        for layer in &self.layers {
+            // A scrolling axis needs the next tile along to cover the gap.
+            let columns = if layer.speed.x != 0.0 { 2 } else { 1 };
+            let rows = if layer.speed.y != 0.0 { 2 } else { 1 };
+            let step = size - Vec2::splat(layer.overlap);
+            let scroll = vec2(
+                wrapped(layer.scroll.x, step.x),
+                wrapped(layer.scroll.y, step.y),
+            );
+            for column in 0..columns {
+                for row in 0..rows {
+                    renderer.draw_texture_ex(
+                        assets.get(layer.texture),
+                        area.x - scroll.x + step.x * column as f32,
+                        area.y + layer.offset - scroll.y + step.y * row as f32,
+                        layer.tint,
+                        DrawTextureParams {
+                            dest_size: Some(size),
+                            flip_y: self.flip_y,
+                            ..Default::default()
+                        },
+                    );
+                }
+            }
+        }
+    }
+}
+
+fn wrapped(scroll: f32, step: f32) -> f32 {
+    if step > 0.0 {
+        scroll.rem_euclid(step)
+    } else {
+        scroll
+    }
+}
+
+pub fn scroll_parallax(world: &mut World, _state: &mut GameState, input: &Input) {
+    for entity in world.entities_mut() {
+        let Some(ref mut parallax) = entity.parallax else {
+            continue;
+        };
+        parallax.scroll(input.dt);
+        parallax.wrap(vec2(entity.transform.w, entity.transform.h));
+    }
+}
+
+pub fn render_parallax(world: &World, state: &GameState, renderer: &mut dyn Renderer) {
+    for entity in world.entities() {
+        if let Some(ref parallax) = entity.parallax {
+            parallax.render(&state.assets, entity.transform, renderer);
+        }
+    }
+}
@@ -0,0 +1,264 @@
+use std::fmt;
+
+use crate::{FixedStep, Game, Input, Rng, SceneId};
+
+const MAGIC: &[u8; 4] = b"RPLY";
+// Version 1 had no fire, confirm or movement.
+const VERSION: u8 = 2;
+
+// Bits in the per-run flags byte. New buttons take the next free bit.
+const JUMP: u8 = 1 << 0;
+const PAUSE: u8 = 1 << 1;
+const FOCUS_LOST: u8 = 1 << 2;
+const FIRE: u8 = 1 << 3;
+const CONFIRM: u8 = 1 << 4;
+
+/// The most inputs a loaded recording can hold: an hour at 240 frames a
+/// second. Runs store a count, so a few bytes can claim billions of inputs.
+pub const MAX_INPUTS: usize = 60 * 60 * 240;
+
+#[derive(Debug)]
+pub enum ReplayError {
+    Io(std::io::Error),
+    /// The data doesn't start with the replay header.
+    BadMagic,
+    /// Written by a newer build than this one understands.
+    UnsupportedVersion(u8),
+    /// The data ends part way through a field.
+    Truncated,
+    /// Holds more than [`MAX_INPUTS`] inputs.
+    TooLong,
+    /// Data follows the last run of inputs.
+    TrailingBytes(usize),
+}
+
+impl fmt::Display for ReplayError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ReplayError::Io(e) => write!(f, "couldn't read replay: {e}"),
+            ReplayError::BadMagic => write!(f, "not a replay file"),
+            ReplayError::UnsupportedVersion(v) => write!(f, "unsupported replay version {v}"),
+            ReplayError::Truncated => write!(f, "replay file is truncated"),
+            ReplayError::TooLong => write!(f, "replay has more than {MAX_INPUTS} inputs"),
+            ReplayError::TrailingBytes(n) => write!(f, "replay has {n} unexpected trailing bytes"),
+        }
+    }
+}
+
+impl std::error::Error for ReplayError {}
+
+impl From<std::io::Error> for ReplayError {
+    fn from(e: std::io::Error) -> Self {
+        ReplayError::Io(e)
+    }
+}
+
+/// Every [`Input`] fed to [`Game::update`] during a run, plus the RNG seed it
+/// started from. Replaying both into a freshly built game reproduces the run.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct Recording {
+    pub seed: u64,
+    pub inputs: Vec<Input>,
+}
+
+impl Recording {
+    pub fn new(seed: u64) -> Self {
+        Self {
+            seed,
+            inputs: vec![],
+        }
+    }
+
+    pub fn push(&mut self, input: &Input) {
+        self.inputs.push(*input);
+    }
+
+    /// Puts `game` back where the recording started: reseeded and, for games
+    /// with a [`crate::PLAYING`] scene, at the start of a run.
+    fn rewind(&self, game: &mut Game) {
+        game.state.rng.reseed(self.seed);
+        if game.scenes().get(crate::PLAYING).is_some() {
+            game.reset_scene(crate::PLAYING);
+        }
+    }
+
+    /// Starts over when a run starts, reseeding `rng` so each run is recorded
+    /// on its own. Otherwise "watch the last run" would replay every run
+    /// before it too.
+    pub(crate) fn scene_entered(&mut self, id: SceneId, rng: &mut Rng) {
+        if id == crate::PLAYING {
+            let seed = rng.gen_range(0, u64::MAX);
+            rng.reseed(seed);
+            *self = Recording::new(seed);
+        }
+    }
+
+    /// Runs the whole recording through `game` without rendering. `game`
+    /// should be built the same way as the one that was recorded, and not be
+    /// recording itself.
+    pub fn replay(&self, game: &mut Game) {
+        self.rewind(game);
+        for input in &self.inputs {
+            game.update(input);
+        }
+    }
+
+    /// Encodes the recording. Consecutive identical inputs are stored as a
+    /// single run, so a fixed-step session is mostly a handful of bytes per
+    /// button press.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = Vec::new();
+        bytes.extend_from_slice(MAGIC);
+        bytes.push(VERSION);
+        bytes.extend_from_slice(&self.seed.to_le_bytes());
+
+        let runs = self.inputs.chunk_by(|a, b| a == b);
+        bytes.extend_from_slice(&(runs.clone().count() as u32).to_le_bytes());
+        for run in runs {
+            let input = run[0];
+            let mut flags = 0;
+            if input.jump {
+                flags |= JUMP;
+            }
+            if input.fire {
+                flags |= FIRE;
+            }
+            if input.confirm {
+                flags |= CONFIRM;
+            }
+            if input.pause {
+                flags |= PAUSE;
+            }
+            if input.focus_lost {
+                flags |= FOCUS_LOST;
+            }
+            bytes.extend_from_slice(&(run.len() as u32).to_le_bytes());
+            bytes.extend_from_slice(&input.dt.to_le_bytes());
+            bytes.push(flags);
+            bytes.extend_from_slice(&input.move_x.to_le_bytes());
+            bytes.extend_from_slice(&input.move_y.to_le_bytes());
+        }
+        bytes
+    }
+
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, ReplayError> {
+        let mut reader = Reader(bytes);
+        if reader.take(4)? != MAGIC {
+            return Err(ReplayError::BadMagic);
+        }
+        let version = reader.u8()?;
+        if !(1..=VERSION).contains(&version) {
+            return Err(ReplayError::UnsupportedVersion(version));
+        }
+
+        let mut recording = Recording::new(reader.u64()?);
+        for _ in 0..reader.u32()? {
+            let len = reader.u32()? as usize;
+            if len > MAX_INPUTS - recording.inputs.len() {
+                return Err(ReplayError::TooLong);
+            }
+            let dt = reader.f32()?;
+            let flags = reader.u8()?;
+            let (move_x, move_y) = if version >= 2 {
+                (reader.f32()?, reader.f32()?)
+            } else {
+                (0.0, 0.0)
+            };
+            let input = Input {
+                dt,
+                jump: flags & JUMP != 0,
+                fire: flags & FIRE != 0,
+                confirm: flags & CONFIRM != 0,
+                pause: flags & PAUSE != 0,
+                focus_lost: flags & FOCUS_LOST != 0,
+                move_x,
+                move_y,
+            };
+            recording.inputs.extend(std::iter::repeat_n(input, len));
+        }
+        if !reader.0.is_empty() {
+            return Err(ReplayError::TrailingBytes(reader.0.len()));
+        }
+        Ok(recording)
+    }
+
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn save(&self, path: impl AsRef<std::path::Path>) -> Result<(), ReplayError> {
+        std::fs::write(path, self.to_bytes())?;
+        Ok(())
+    }
+
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn load(path: impl AsRef<std::path::Path>) -> Result<Self, ReplayError> {
+        Self::from_bytes(&std::fs::read(path)?)
+    }
+}
+
+struct Reader<'a>(&'a [u8]);
+
+impl<'a> Reader<'a> {
+    fn take(&mut self, n: usize) -> Result<&'a [u8], ReplayError> {
+        if self.0.len() < n {
+            return Err(ReplayError::Truncated);
+        }
+        let (head, rest) = self.0.split_at(n);
+        self.0 = rest;
+        Ok(head)
+    }
+
+    fn u8(&mut self) -> Result<u8, ReplayError> {
+        Ok(self.take(1)?[0])
+    }
+
+    fn u32(&mut self) -> Result<u32, ReplayError> {
+        Ok(u32::from_le_bytes(self.take(4)?.try_into().unwrap()))
+    }
+
+    fn u64(&mut self) -> Result<u64, ReplayError> {
+        Ok(u64::from_le_bytes(self.take(8)?.try_into().unwrap()))
+    }
+
+    fn f32(&mut self) -> Result<f32, ReplayError> {
+        Ok(f32::from_le_bytes(self.take(4)?.try_into().unwrap()))
+    }
+}
+
+/// Plays a [`Recording`] back in real time, one recorded update per tick of
+/// its own fixed-step clock, for "watch last run" style screens.
+pub struct Playback {
+    inputs: Vec<Input>,
+    cursor: usize,
+    clock: FixedStep<Input>,
+}
+
+impl Playback {
+    /// Starts playing `recording` into `game`, which should be freshly built
+    /// the same way as the recorded one.
+    pub fn new(recording: &Recording, game: &mut Game) -> Self {
+        recording.rewind(game);
+        let step = recording
+            .inputs
+            .first()
+            .map_or(crate::DEFAULT_STEP, |i| i.dt);
+        Self {
+            inputs: recording.inputs.clone(),
+            cursor: 0,
+            clock: FixedStep::new(step),
+        }
+    }
+
+    pub fn advance(&mut self, game: &mut Game, frame_time: f32) {
+        let (inputs, cursor) = (&self.inputs, &mut self.cursor);
+        self.clock.advance(frame_time, &Input::default(), |_| {
+            if let Some(input) = inputs.get(*cursor) {
+                game.update(input);
+                *cursor += 1;
+            }
+        });
+        game.state.alpha = self.clock.alpha();
+    }
+
+    pub fn is_finished(&self) -> bool {
+        self.cursor >= self.inputs.len()
+    }
+}
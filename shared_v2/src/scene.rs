@@ -0,0 +1,466 @@
+use std::collections::HashMap;
+
+use macroquad::color::{Color, RED, WHITE};
+use macroquad::math::vec2;
+
+use crate::{
+    AssetServer, Game, GameState, HAlign, Input, Renderer, Systems, TextStyle, VAlign,
+    VirtualCanvas, World, render_text,
+};
+
+/// Names a scene registered with [`Game::with_scene`].
+pub type SceneId = &'static str;
+
+pub const TITLE: SceneId = "title";
+pub const PLAYING: SceneId = "playing";
+pub const PAUSED: SceneId = "paused";
+pub const GAME_OVER: SceneId = "game_over";
+
+/// Runs on a scene's world and state as it starts or stops.
+pub type SceneHook = fn(&mut World, &mut GameState);
+
+/// One screen of a game, such as the title or the game itself, with the
+/// systems that only run while it's on top of the stack.
+#[derive(Default)]
+pub struct Scene {
+    pub systems: Systems,
+    pub on_enter: Option<SceneHook>,
+    pub on_exit: Option<SceneHook>,
+    /// Draws over the scene beneath it instead of replacing it, like a pause
+    /// screen. Only the top scene ever updates.
+    pub overlay: bool,
+}
+
+impl Scene {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_update_system(mut self, system: fn(&mut World, &mut GameState, &Input)) -> Self {
+        self.systems.update.push(system);
+        self
+    }
+
+    pub fn with_render_system(mut self, system: fn(&World, &GameState, &mut dyn Renderer)) -> Self {
+        self.systems.render.push(system);
+        self
+    }
+
+    pub fn with_update_systems(
+        mut self,
+        systems: Vec<fn(&mut World, &mut GameState, &Input)>,
+    ) -> Self {
+        self.systems.update.extend(systems);
+        self
+    }
+
+    pub fn with_render_systems(
+        mut self,
+        systems: Vec<fn(&World, &GameState, &mut dyn Renderer)>,
+    ) -> Self {
+        self.systems.render.extend(systems);
+        self
+    }
+
+    pub fn with_on_enter(mut self, hook: SceneHook) -> Self {
+        self.on_enter = Some(hook);
+        self
+    }
+
+    pub fn with_on_exit(mut self, hook: SceneHook) -> Self {
+        self.on_exit = Some(hook);
+        self
+    }
+
+    pub fn overlay(mut self) -> Self {
+        self.overlay = true;
+        self
+    }
+}
+
+/// A change to the scene stack, queued on [`GameState`] by systems and
+/// applied by [`Game::update`] once every system has run.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Transition {
+    /// Puts a scene on top, pausing the one beneath without exiting it.
+    Push(SceneId),
+    /// Exits the top scene, resuming the one beneath.
+    Pop,
+    /// Exits the top scene and enters another in its place.
+    Switch(SceneId),
+    /// Exits every scene, top first, and starts over from one.
+    Reset(SceneId),
+}
+
+/// The registered scenes and the stack of those currently running.
+#[derive(Default)]
+pub struct Scenes {
+    registry: HashMap<SceneId, Scene>,
+    stack: Vec<SceneId>,
+}
+
+impl Scenes {
+    pub fn current(&self) -> Option<SceneId> {
+        self.stack.last().copied()
+    }
+
+    /// Bottom first.
+    pub fn stack(&self) -> &[SceneId] {
+        &self.stack
+    }
+
+    pub fn get(&self, id: SceneId) -> Option<&Scene> {
+        self.registry.get(id)
+    }
+
+    /// The scenes that draw this frame, bottom first: the top scene, plus
+    /// the ones beneath it for as long as the scene above is an overlay.
+    pub fn visible(&self) -> &[SceneId] {
+        let mut bottom = self.stack.len();
+        while bottom > 0 {
+            bottom -= 1;
+            let overlay = self.get(self.stack[bottom]).is_some_and(|s| s.overlay);
+            if !overlay {
+                break;
+            }
+        }
+        &self.stack[bottom..]
+    }
+}
+
+impl GameState {
+    pub fn push_scene(&mut self, id: SceneId) {
+        self.transitions.push(Transition::Push(id));
+    }
+
+    pub fn pop_scene(&mut self) {
+        self.transitions.push(Transition::Pop);
+    }
+
+    pub fn switch_scene(&mut self, id: SceneId) {
+        self.transitions.push(Transition::Switch(id));
+    }
+
+    pub fn reset_scene(&mut self, id: SceneId) {
+        self.transitions.push(Transition::Reset(id));
+    }
+}
+
+impl Game {
+    /// Registers `scene` under `id`, replacing any scene already there.
+    pub fn with_scene(mut self, id: SceneId, scene: Scene) -> Self {
+        self.scenes.registry.insert(id, scene);
+        self
+    }
+
+    /// Registers the standard title, paused and game over scenes around
+    /// `playing`, and starts on the title.
+    ///
+    /// Confirm starts a run from the title and retries from game over, and
+    /// pause toggles the pause overlay. Losing focus also pauses, which
+    /// freezes `playing`'s systems until the player resumes.
+    ///
+    /// `playing` should set up a fresh run in its enter hook and push
+    /// [`GAME_OVER`] when the run ends. Register replacements with
+    /// [`Game::with_scene`] afterwards to customise the other scenes.
+    pub fn with_standard_flow(self, playing: Scene) -> Self {
+        let mut game = self
+            .with_scene(TITLE, title_scene())
+            .with_scene(PLAYING, playing.with_update_system(pause_on_request))
+            .with_scene(PAUSED, paused_scene())
+            .with_scene(GAME_OVER, game_over_scene());
+        game.reset_scene(TITLE);
+        game
+    }
+
+    /// The scene on top of the stack.
+    pub fn scene(&self) -> Option<SceneId> {
+        self.scenes.current()
+    }
+
+    pub fn scenes(&self) -> &Scenes {
+        &self.scenes
+    }
+
+    pub fn push_scene(&mut self, id: SceneId) {
+        self.apply_transition(Transition::Push(id));
+    }
+
+    pub fn pop_scene(&mut self) {
+        self.apply_transition(Transition::Pop);
+    }
+
+    pub fn switch_scene(&mut self, id: SceneId) {
+        self.apply_transition(Transition::Switch(id));
+    }
+
+    pub fn reset_scene(&mut self, id: SceneId) {
+        self.apply_transition(Transition::Reset(id));
+    }
+
+    fn hook(&self, id: SceneId, exit: bool) -> Option<SceneHook> {
+        let scene = self.scenes.get(id)?;
+        if exit { scene.on_exit } else { scene.on_enter }
+    }
+
+    fn enter(&mut self, id: SceneId) {
+        if let Some(recording) = &mut self.recording {
+            recording.scene_entered(id, &mut self.state.rng);
+        }
+        self.scenes.stack.push(id);
+        if let Some(hook) = self.hook(id, false) {
+            hook(&mut self.world, &mut self.state);
+        }
+    }
+
+    fn exit(&mut self) {
+        let Some(id) = self.scenes.stack.pop() else {
+            return;
+        };
+        if let Some(hook) = self.hook(id, true) {
+            hook(&mut self.world, &mut self.state);
+        }
+    }
+
+    fn apply_transition(&mut self, transition: Transition) {
+        match transition {
+            Transition::Push(id) => self.enter(id),
+            Transition::Pop => self.exit(),
+            Transition::Switch(id) => {
+                self.exit();
+                self.enter(id);
+            }
+            Transition::Reset(id) => {
+                while !self.scenes.stack.is_empty() {
+                    self.exit();
+                }
+                self.enter(id);
+            }
+        }
+        // Hooks can queue transitions of their own, which happen straight
+        // after.
+        self.apply_transitions();
+    }
+
+    pub(crate) fn apply_transitions(&mut self) {
+        for transition in std::mem::take(&mut self.state.transitions) {
+            self.apply_transition(transition);
+        }
+    }
+
+    /// Runs the top scene's update systems.
+    pub(crate) fn update_scene(&mut self, input: &Input) {
+        let Some(scene) = self.scene().and_then(|id| self.scenes.get(id)) else {
+            return;
+        };
+        for system in &scene.systems.update {
+            system(&mut self.world, &mut self.state, input);
+        }
+    }
+
+    /// Runs the render systems of every visible scene, bottom first.
+    pub(crate) fn render_scenes(&self, renderer: &mut dyn Renderer) {
+        for id in self.scenes.visible() {
+            let Some(scene) = self.scenes.get(id) else {
+                continue;
+            };
+            for system in &scene.systems.render {
+                system(&self.world, &self.state, renderer);
+            }
+        }
+    }
+}
+
+fn pause_on_request(_world: &mut World, state: &mut GameState, input: &Input) {
+    if input.pause || input.focus_lost {
+        state.push_scene(PAUSED);
+    }
+}
+
+fn start_on_confirm(_world: &mut World, state: &mut GameState, input: &Input) {
+    if input.confirm {
+        state.reset_scene(PLAYING);
+    }
+}
+
+// Confirm resumes too, as phones have nothing to press pause with.
+fn resume_on_request(_world: &mut World, state: &mut GameState, input: &Input) {
+    if input.pause || input.confirm {
+        state.pop_scene();
+    }
+}
+
+/// Two lines of text centred on the canvas.
+fn render_banner(
+    canvas: &VirtualCanvas,
+    assets: &AssetServer,
+    renderer: &mut dyn Renderer,
+    title: &str,
+    color: Color,
+    hint: &str,
+) {
+    renderer.set_default_camera();
+    let centre = canvas.viewport().center();
+    let title = render_text(
+        title,
+        centre,
+        &TextStyle::new(60.0)
+            .with_color(color)
+            .aligned(HAlign::Center, VAlign::Middle),
+        assets,
+        renderer,
+    );
+    render_text(
+        hint,
+        vec2(centre.x, title.bounds.bottom() + 30.0),
+        &TextStyle::new(30.0).aligned(HAlign::Center, VAlign::Baseline),
+        assets,
+        renderer,
+    );
+}
+
+fn render_title_banner(canvas: &VirtualCanvas, assets: &AssetServer, renderer: &mut dyn Renderer) {
+    renderer.clear_background(Color::new(0.0, 0.0, 0.0, 1.0));
+    render_banner(
+        canvas,
+        assets,
+        renderer,
+        "READY?",
+        WHITE,
+        "Press space to start",
+    );
+}
+
+fn render_game_over_banner(
+    canvas: &VirtualCanvas,
+    assets: &AssetServer,
+    renderer: &mut dyn Renderer,
+) {
+    render_banner(
+        canvas,
+        assets,
+        renderer,
+        "GAME OVER!",
+        RED,
+        "Press space to retry",
+    );
+}
+
+fn render_title(_world: &World, state: &GameState, renderer: &mut dyn Renderer) {
+    render_title_banner(&state.canvas, &state.assets, renderer);
+}
+
+fn render_paused(_world: &World, state: &GameState, renderer: &mut dyn Renderer) {
+    renderer.set_default_camera();
+    let shade = Color::new(0.0, 0.0, 0.0, 0.5);
+    let screen = state.canvas.screen();
+    renderer.draw_rectangle(0.0, 0.0, screen.x, screen.y, shade);
+    render_banner(
+        &state.canvas,
+        &state.assets,
+        renderer,
+        "PAUSED",
+        WHITE,
+        "Press P or tap to resume",
+    );
+}
+
+fn render_game_over(_world: &World, state: &GameState, renderer: &mut dyn Renderer) {
+    render_game_over_banner(&state.canvas, &state.assets, renderer);
+}
+
+/// Waits for confirm, then starts [`PLAYING`].
+pub fn title_scene() -> Scene {
+    Scene::new()
+        .with_update_system(start_on_confirm)
+        .with_render_system(render_title)
+}
+
+/// Shades the game beneath and waits for pause or confirm.
+pub fn paused_scene() -> Scene {
+    Scene::new()
+        .overlay()
+        .with_update_system(resume_on_request)
+        .with_render_system(render_paused)
+}
+
+/// Shows over the finished run and restarts [`PLAYING`] on confirm.
+pub fn game_over_scene() -> Scene {
+    Scene::new()
+        .overlay()
+        .with_update_system(start_on_confirm)
+        .with_render_system(render_game_over)
+}
+
+/// The standard flow's title, play and game over scenes, for games that run
+/// their own loop instead of a [`Game`].
+///
+/// Confirm starts a run from the title and retries from game over, as in
+/// [`Game::with_standard_flow`]. The game simulates while
+/// [`SceneFlow::is_playing`], calls [`SceneFlow::game_over`] when the run
+/// ends, and sets up a fresh run whenever [`SceneFlow::update`] says one
+/// started. Pausing is left to the game's clock.
+#[derive(Debug)]
+pub struct SceneFlow {
+    scene: SceneId,
+}
+
+impl Default for SceneFlow {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl SceneFlow {
+    /// Starts on the title.
+    pub fn new() -> Self {
+        Self { scene: TITLE }
+    }
+
+    /// [`TITLE`], [`PLAYING`] or [`GAME_OVER`].
+    pub fn scene(&self) -> SceneId {
+        self.scene
+    }
+
+    pub fn is_playing(&self) -> bool {
+        self.scene == PLAYING
+    }
+
+    /// Starts a run from any scene, as confirm does from the title or game
+    /// over. For games with their own title screen.
+    pub fn start(&mut self) {
+        self.scene = PLAYING;
+    }
+
+    /// Ends the run and shows the game over banner.
+    pub fn game_over(&mut self) {
+        if self.is_playing() {
+            self.scene = GAME_OVER;
+        }
+    }
+
+    /// Starts a run if confirm was pressed on the title or game over.
+    /// Returns whether one started, so the game can reset itself. Call it
+    /// after the frame's simulation, so the press doesn't reach the run.
+    pub fn update(&mut self, confirm: bool) -> bool {
+        if confirm && !self.is_playing() {
+            self.start();
+            return true;
+        }
+        false
+    }
+
+    /// Draws the title or game over banner, if either is showing.
+    pub fn render(
+        &self,
+        canvas: &VirtualCanvas,
+        assets: &AssetServer,
+        renderer: &mut dyn Renderer,
+    ) {
+        match self.scene {
+            TITLE => render_title_banner(canvas, assets, renderer),
+            GAME_OVER => render_game_over_banner(canvas, assets, renderer),
+            _ => {}
+        }
+    }
+}
@@ -0,0 +1,105 @@
+use std::collections::HashMap;
+
+/// Small string settings that outlive the session, such as volume or key
+/// bindings.
+pub trait Storage {
+    fn get(&self, key: &str) -> Option<String>;
+    fn set(&mut self, key: &str, value: &str);
+}
+
+/// Forgets everything when dropped. The default, and what tests use.
+#[derive(Debug, Default)]
+pub struct MemoryStorage {
+    values: HashMap<String, String>,
+}
+
+impl Storage for MemoryStorage {
+    fn get(&self, key: &str) -> Option<String> {
+        self.values.get(key).cloned()
+    }
+
+    fn set(&mut self, key: &str, value: &str) {
+        self.values.insert(key.to_string(), value.to_string());
+    }
+}
+
+/// Persists to disk on desktop, as one file per key under `saves/<game>`,
+/// and to the browser's `localStorage` on wasm.
+///
+/// All the games share one origin when deployed, so keys are prefixed with
+/// the game's name. On wasm this needs the `storage` plugin from
+/// `web/template.txt`.
+#[derive(Debug)]
+pub struct LocalStorage {
+    game: String,
+}
+
+impl LocalStorage {
+    pub fn new(game: &str) -> Self {
+        Self {
+            game: game.to_string(),
+        }
+    }
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+impl LocalStorage {
+    fn path(&self, key: &str) -> std::path::PathBuf {
+        std::path::Path::new("saves").join(&self.game).join(key)
+    }
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+impl Storage for LocalStorage {
+    fn get(&self, key: &str) -> Option<String> {
+        std::fs::read_to_string(self.path(key)).ok()
+    }
+
+    // Settings are a nicety, so failing to save them isn't worth stopping
+    // the game over.
+    fn set(&mut self, key: &str, value: &str) {
+        let path = self.path(key);
+        if let Some(dir) = path.parent() {
+            let _ = std::fs::create_dir_all(dir);
+        }
+        let _ = std::fs::write(path, value);
+    }
+}
+
+#[cfg(target_arch = "wasm32")]
+unsafe extern "C" {
+    fn storage_len(key: *const u8, key_len: usize) -> i32;
+    fn storage_get(key: *const u8, key_len: usize, out: *mut u8);
+    fn storage_set(key: *const u8, key_len: usize, value: *const u8, value_len: usize);
+}
+
+// Lets the JS plugin check it matches this code.
+#[cfg(target_arch = "wasm32")]
+#[unsafe(no_mangle)]
+pub extern "C" fn storage_crate_version() -> u32 {
+    1
+}
+
+#[cfg(target_arch = "wasm32")]
+impl Storage for LocalStorage {
+    fn get(&self, key: &str) -> Option<String> {
+        let key = format!("{}.{key}", self.game);
+        // SAFETY: the plugin only reads `key` and writes exactly the length
+        // it reported into `out`.
+        unsafe {
+            let len = storage_len(key.as_ptr(), key.len());
+            if len < 0 {
+                return None;
+            }
+            let mut value = vec![0u8; len as usize];
+            storage_get(key.as_ptr(), key.len(), value.as_mut_ptr());
+            String::from_utf8(value).ok()
+        }
+    }
+
+    fn set(&mut self, key: &str, value: &str) {
+        let key = format!("{}.{key}", self.game);
+        // SAFETY: the plugin only reads the two strings.
+        unsafe { storage_set(key.as_ptr(), key.len(), value.as_ptr(), value.len()) }
+    }
+}
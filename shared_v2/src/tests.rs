@@ -1,6 +1,12 @@
 #[cfg(test)]
+#[allow(clippy::module_inception)]
 mod tests {
     use crate::*;
+    use macroquad::color::RED;
+    use macroquad::input::{KeyCode, MouseButton, Touch, TouchPhase};
+    use macroquad::math::{RectOffset, Vec2, vec2};
+    use macroquad::miniquad::UniformType;
+    use macroquad::texture::FilterMode;
 
     fn test_entity(tag: Option<Tag>) -> Entity {
         let mut e = Entity::new(Rect {
@@ -44,18 +50,31 @@ mod tests {
     #[test]
     fn world_spawn_adds_entities() {
         let world = World::new()
-            .spawn(test_entity(None))
-            .spawn(test_entity(Some(Tag::Player)));
+            .with_entity(test_entity(None))
+            .with_entity(test_entity(Some(Tag::Player)));
 
-        assert_eq!(world.entities.len(), 2);
+        assert_eq!(world.entities().len(), 2);
+    }
+
+    #[test]
+    fn spawn_returns_a_handle_to_the_new_entity() {
+        let mut world = World::new().with_entity(test_entity(None));
+        let player = world.spawn(test_entity(Some(Tag::Player)));
+
+        assert_eq!(world.find(player).unwrap().tag, Some(Tag::Player));
+        world.find_mut(player).unwrap().transform.x = 7.0;
+        assert_eq!(world.entities()[1].transform.x, 7.0);
+
+        world.clear();
+        assert!(world.find(player).is_none());
     }
 
     #[test]
     fn with_tag_filters_entities() {
         let world = World::new()
-            .spawn(test_entity(Some(Tag::Player)))
-            .spawn(test_entity(None))
-            .spawn(test_entity(Some(Tag::Player)));
+            .with_entity(test_entity(Some(Tag::Player)))
+            .with_entity(test_entity(None))
+            .with_entity(test_entity(Some(Tag::Player)));
 
         let players: Vec<&Entity> = world.with_tag(Tag::Player).collect();
 
@@ -68,8 +87,8 @@ mod tests {
     #[test]
     fn with_tag_mut_allows_mutation() {
         let mut world = World::new()
-            .spawn(test_entity(Some(Tag::Player)))
-            .spawn(test_entity(Some(Tag::Player)));
+            .with_entity(test_entity(Some(Tag::Player)))
+            .with_entity(test_entity(Some(Tag::Player)));
 
         for e in world.with_tag_mut(Tag::Player) {
             e.physics = Some(Physics::new());
@@ -89,7 +108,7 @@ mod tests {
         let state = GameState::new();
 
         assert_eq!(state.score, 0.0);
-        assert!(!state.game_over);
+        assert!(state.transitions.is_empty());
     }
 
     #[test]
@@ -109,7 +128,8 @@ mod tests {
 
         let input = Input {
             dt: 1.0,
-            spacebar: false,
+            jump: false,
+            ..Default::default()
         };
 
         game.update(&input);
@@ -117,4 +137,1794 @@ mod tests {
         // (0 + 1) * 2 = 2
         assert_eq!(game.state.score, 2.0);
     }
+
+    #[test]
+    fn commands_apply_after_update_systems() {
+        fn spawn_and_despawn(world: &mut World, state: &mut GameState, _: &Input) {
+            // The world is unchanged until every system has run.
+            state.score = world.entities().len() as f32;
+            let (entities, commands) = world.entities_and_commands();
+            for e in entities.iter() {
+                if e.tag == Some(Tag::Enemy) {
+                    commands.despawn(e.id());
+                }
+            }
+            commands.spawn(test_entity(Some(Tag::Player)));
+        }
+
+        let world = World::new()
+            .with_entity(test_entity(Some(Tag::Enemy)))
+            .with_entity(test_entity(Some(Tag::Player)))
+            .with_entity(test_entity(Some(Tag::Enemy)));
+        let mut game =
+            Game::new(world).with_update_systems(vec![spawn_and_despawn, spawn_and_despawn]);
+
+        let input = Input {
+            dt: 1.0,
+            jump: false,
+            ..Default::default()
+        };
+
+        game.update(&input);
+
+        assert_eq!(game.state.score, 3.0);
+        assert_eq!(game.world.entities().len(), 3);
+        assert_eq!(game.world.with_tag(Tag::Player).count(), 3);
+        assert!(game.world.commands.is_empty());
+    }
+
+    #[test]
+    fn despawns_follow_the_entity_when_the_list_is_reordered() {
+        let mut world = World::new()
+            .with_entity(test_entity(Some(Tag::Enemy)))
+            .with_entity(test_entity(Some(Tag::Player)));
+        let enemy = world.entities()[0].id();
+        world.commands.spawn(test_entity(Some(Tag::Enemy)));
+        world.commands.despawn(enemy);
+
+        world.entities_mut().swap(0, 1);
+        world.flush();
+
+        assert_eq!(world.entities().len(), 2);
+        assert_eq!(world.entities()[0].tag, Some(Tag::Player));
+        assert!(world.entities().iter().all(|e| e.id() != enemy));
+        assert_ne!(world.entities()[0].id(), world.entities()[1].id());
+    }
+
+    #[test]
+    fn overlapping_pairs_reports_touching_entities() {
+        let far = Entity::new(Rect {
+            x: 500.0,
+            y: 500.0,
+            w: 10.0,
+            h: 10.0,
+        })
+        .with_collider(CollisionLayers::DEFAULT);
+        let world = World::new()
+            .with_entity(test_entity(Some(Tag::Player)).with_collider(CollisionLayers::DEFAULT))
+            .with_entity(far)
+            .with_entity(test_entity(None))
+            .with_entity(test_entity(Some(Tag::Enemy)).with_collider(CollisionLayers::DEFAULT));
+
+        // Index 2 overlaps both, but has no collider.
+        assert_eq!(world.overlapping_pairs(), vec![(0, 3)]);
+    }
+
+    #[test]
+    fn overlapping_pairs_honours_layers() {
+        const PLAYER: u32 = 1 << 0;
+        const ENEMY: u32 = 1 << 1;
+        let enemy =
+            || test_entity(Some(Tag::Enemy)).with_collider(CollisionLayers::new(ENEMY, PLAYER));
+
+        let world = World::new()
+            .with_entity(enemy())
+            .with_entity(
+                test_entity(Some(Tag::Player)).with_collider(CollisionLayers::new(PLAYER, ENEMY)),
+            )
+            .with_entity(enemy());
+
+        // Enemies overlap each other but never collide.
+        assert_eq!(world.overlapping_pairs(), vec![(0, 1), (1, 2)]);
+    }
+
+    #[test]
+    fn fixed_update_records_alpha_for_interpolation() {
+        fn move_right(world: &mut World, _: &mut GameState, input: &Input) {
+            for e in world.entities_mut() {
+                e.transform.x += 100.0 * input.dt;
+            }
+        }
+
+        let world = World::new().with_entity(test_entity(None));
+        let mut game = Game::new(world)
+            .with_fixed_step(0.1)
+            .with_update_system(move_right);
+        let input = Input {
+            dt: 0.0,
+            jump: false,
+            ..Default::default()
+        };
+
+        game.fixed_update(0.15, &input);
+
+        let e = &game.world.entities()[0];
+        assert!((game.state.alpha - 0.5).abs() < 0.001);
+        assert!((e.transform.x - 10.0).abs() < 0.001);
+        assert!((e.interpolated(game.state.alpha).x - 5.0).abs() < 0.001);
+    }
+
+    #[test]
+    fn teleport_skips_interpolation() {
+        let mut e = test_entity(None);
+
+        e.teleport(50.0, 20.0);
+
+        assert_eq!(e.interpolated(0.5).x, 50.0);
+        assert_eq!(e.interpolated(0.5).y, 20.0);
+    }
+
+    fn wander(world: &mut World, state: &mut GameState, input: &Input) {
+        for e in world.entities_mut() {
+            e.transform.x += state.rng.gen_range(-10.0, 10.0) * input.dt;
+            if input.jump {
+                e.transform.y += 5.0;
+            }
+        }
+    }
+
+    fn wander_game(seed: u64) -> Game {
+        Game::new(World::new().with_entity(test_entity(None)))
+            .with_seed(seed)
+            .with_update_system(wander)
+    }
+
+    #[test]
+    fn recording_replays_session_exactly() {
+        let mut game = wander_game(99).with_recording();
+        for tick in 0..120 {
+            let input = Input {
+                dt: DEFAULT_STEP,
+                jump: tick % 30 == 0,
+                ..Default::default()
+            };
+            game.update(&input);
+        }
+        let recording = game.recording.take().unwrap();
+        assert_eq!(recording.inputs.len(), 120);
+
+        let bytes = recording.to_bytes();
+        // 120 ticks collapse into 8 runs of 17 bytes after the 17 byte header.
+        assert_eq!(bytes.len(), 17 + 8 * 17);
+
+        let loaded = Recording::from_bytes(&bytes).unwrap();
+        assert_eq!(loaded, recording);
+
+        let mut replayed = wander_game(0);
+        loaded.replay(&mut replayed);
+
+        assert_eq!(
+            replayed.world.entities()[0].transform,
+            game.world.entities()[0].transform
+        );
+    }
+
+    #[test]
+    fn playback_feeds_one_recorded_tick_per_step() {
+        let mut recording = Recording::new(5);
+        for _ in 0..3 {
+            recording.push(&Input {
+                dt: 0.1,
+                jump: true,
+                ..Default::default()
+            });
+        }
+
+        let mut game = wander_game(0);
+        let mut playback = Playback::new(&recording, &mut game);
+        playback.advance(&mut game, 0.25);
+
+        assert!(!playback.is_finished());
+        assert_eq!(game.world.entities()[0].transform.y, 10.0);
+
+        playback.advance(&mut game, 1.0);
+
+        assert!(playback.is_finished());
+        assert_eq!(game.world.entities()[0].transform.y, 15.0);
+    }
+
+    #[test]
+    fn recording_rejects_bad_data() {
+        let bytes = Recording::new(1).to_bytes();
+
+        assert!(matches!(
+            Recording::from_bytes(b"nope"),
+            Err(ReplayError::BadMagic)
+        ));
+        assert!(matches!(
+            Recording::from_bytes(&bytes[..bytes.len() - 1]),
+            Err(ReplayError::Truncated)
+        ));
+
+        let mut newer = bytes.clone();
+        newer[4] = 99;
+        assert!(matches!(
+            Recording::from_bytes(&newer),
+            Err(ReplayError::UnsupportedVersion(99))
+        ));
+    }
+
+    #[test]
+    fn recording_rejects_trailing_bytes() {
+        let mut bytes = Recording::new(1).to_bytes();
+        bytes.extend_from_slice(&[0xAB, 0xCD]);
+
+        assert!(matches!(
+            Recording::from_bytes(&bytes),
+            Err(ReplayError::TrailingBytes(2))
+        ));
+    }
+
+    #[test]
+    fn recording_rejects_oversized_runs() {
+        // A recording of idle runs `lens` long, written by hand.
+        let runs = |lens: &[u32]| {
+            let mut bytes = Recording::new(1).to_bytes();
+            bytes.truncate(bytes.len() - 4);
+            bytes.extend_from_slice(&(lens.len() as u32).to_le_bytes());
+            for len in lens {
+                bytes.extend_from_slice(&len.to_le_bytes());
+                bytes.extend_from_slice(&DEFAULT_STEP.to_le_bytes());
+                bytes.push(0);
+                bytes.extend_from_slice(&[0; 8]);
+            }
+            bytes
+        };
+
+        assert!(matches!(
+            Recording::from_bytes(&runs(&[u32::MAX])),
+            Err(ReplayError::TooLong)
+        ));
+        // Runs that fit alone still can't add up past the limit.
+        let half = (MAX_INPUTS / 2 + 1) as u32;
+        assert!(matches!(
+            Recording::from_bytes(&runs(&[half, half])),
+            Err(ReplayError::TooLong)
+        ));
+        assert_eq!(
+            Recording::from_bytes(&runs(&[MAX_INPUTS as u32]))
+                .unwrap()
+                .inputs
+                .len(),
+            MAX_INPUTS
+        );
+    }
+
+    #[test]
+    fn render_systems_draw_through_renderer_in_order() {
+        fn outlines(world: &World, _: &GameState, renderer: &mut dyn Renderer) {
+            debug(world.entities(), renderer);
+        }
+
+        fn score(_: &World, state: &GameState, renderer: &mut dyn Renderer) {
+            renderer.draw_text(&format!("{}", state.score), 0.0, 0.0, 20.0, WHITE);
+        }
+
+        let world = World::new().with_entity(test_entity(None));
+        let game = Game::new(world).with_render_systems(vec![outlines, score]);
+        let mut renderer = RecordingRenderer::new();
+
+        game.render(&mut renderer);
+
+        assert!(matches!(renderer.calls[0], DrawCall::RectangleLines { .. }));
+        assert_eq!(
+            renderer.calls[1],
+            DrawCall::Text {
+                text: "0".to_string(),
+                x: 0.0,
+                y: 0.0,
+                custom_font: false,
+                font_size: 20.0,
+                color: WHITE,
+            }
+        );
+    }
+
+    fn parallax_world(layer: impl FnOnce(Handle<Texture>) -> ParallaxLayer) -> (World, GameState) {
+        let mut state = GameState::new();
+        let texture = state.assets.add("layer.png", placeholder_texture(1));
+        let world = World::new().with_entity(
+            Entity::new(Rect::new(0.0, 0.0, 100.0, 50.0))
+                .with_parallax(Parallax::new().with_layer(layer(texture))),
+        );
+        (world, state)
+    }
+
+    #[test]
+    fn parallax_layers_scroll_and_wrap_to_tile_size() {
+        let (mut world, mut state) =
+            parallax_world(|texture| ParallaxLayer::new(texture).with_speed(30.0, 20.0));
+        let input = Input {
+            dt: 1.0,
+            jump: false,
+            ..Default::default()
+        };
+
+        for _ in 0..4 {
+            scroll_parallax(&mut world, &mut state, &input);
+        }
+
+        let layer = &world.entities()[0].parallax.as_ref().unwrap().layers[0];
+        assert_eq!(layer.scroll, vec2(20.0, 30.0));
+    }
+
+    #[test]
+    fn parallax_draws_a_second_tile_only_along_scrolling_axes() {
+        let (mut world, state) = parallax_world(|texture| {
+            ParallaxLayer::new(texture)
+                .with_speed(10.0, 0.0)
+                .with_offset(5.0)
+                .with_tint(RED)
+        });
+        world.entities_mut()[0].parallax.as_mut().unwrap().layers[0]
+            .scroll
+            .x = 30.0;
+        let mut renderer = RecordingRenderer::new();
+
+        render_parallax(&world, &state, &mut renderer);
+
+        let tiles: Vec<_> = renderer
+            .textures()
+            .map(|call| {
+                let DrawCall::Texture { x, y, color, .. } = call else {
+                    unreachable!();
+                };
+                (*x, *y, *color)
+            })
+            .collect();
+        assert_eq!(tiles, vec![(-30.0, 5.0, RED), (70.0, 5.0, RED)]);
+    }
+
+    #[test]
+    fn parallax_scrolls_and_renders_without_a_world() {
+        let mut assets = AssetServer::new();
+        let texture = assets.add("layer.png", placeholder_texture(1));
+        let mut parallax =
+            Parallax::new().with_layer(ParallaxLayer::new(texture).with_speed(10.0, 0.0));
+        parallax.scroll(13.0);
+        let mut renderer = RecordingRenderer::new();
+
+        parallax.render(&assets, Rect::new(0.0, 0.0, 100.0, 50.0), &mut renderer);
+
+        let xs: Vec<_> = renderer
+            .textures()
+            .map(|call| {
+                let DrawCall::Texture { x, .. } = call else {
+                    unreachable!();
+                };
+                *x
+            })
+            .collect();
+        assert_eq!(xs, vec![-30.0, 70.0]);
+    }
+
+    #[test]
+    fn parallax_overlap_pulls_the_next_tile_back() {
+        let (world, state) = parallax_world(|texture| {
+            ParallaxLayer::new(texture)
+                .with_speed(10.0, 0.0)
+                .with_overlap(4.0)
+        });
+        let mut renderer = RecordingRenderer::new();
+
+        render_parallax(&world, &state, &mut renderer);
+
+        let xs: Vec<_> = renderer
+            .textures()
+            .map(|call| {
+                let DrawCall::Texture { x, .. } = call else {
+                    unreachable!();
+                };
+                *x
+            })
+            .collect();
+        assert_eq!(xs, vec![0.0, 96.0]);
+    }
+
+    #[test]
+    fn manifest_records_each_asset_kind() {
+        let manifest = AssetManifest::new()
+            .with_texture("ship.png", FilterMode::Nearest)
+            .with_sound("laser.wav")
+            .with_font("font.ttf")
+            .with_shader("stars.glsl");
+
+        let kinds: Vec<_> = manifest.entries.iter().map(|e| e.kind).collect();
+        assert_eq!(
+            kinds,
+            vec![
+                AssetKind::Texture(FilterMode::Nearest),
+                AssetKind::Sound,
+                AssetKind::Font,
+                AssetKind::Shader,
+            ]
+        );
+        assert_eq!(manifest.entries[1].path, "laser.wav");
+    }
+
+    #[test]
+    fn shaders_declare_the_uniforms_in_their_source() {
+        let source = "precision highp float;
+            uniform vec2 iResolution;
+            uniform lowp float direction_modifier;
+            uniform mat4 Projection;
+            uniform sampler2D Texture;
+            uniform sampler2D noise;
+            void main() {}";
+
+        let (uniforms, textures) = assets::shader_uniforms(source);
+
+        let names: Vec<_> = uniforms.iter().map(|u| u.name.as_str()).collect();
+        assert_eq!(names, vec!["iResolution", "direction_modifier"]);
+        assert!(matches!(uniforms[0].uniform_type, UniformType::Float2));
+        assert!(matches!(uniforms[1].uniform_type, UniformType::Float1));
+        assert_eq!(textures, vec!["noise"]);
+    }
+
+    #[test]
+    fn loading_screen_fills_bar_by_progress() {
+        let progress = LoadProgress {
+            total: 4,
+            finished: 1,
+            failed: vec![],
+        };
+        let mut renderer = RecordingRenderer::new();
+
+        draw_loading_screen(&progress, vec2(1000.0, 500.0), &mut renderer);
+
+        let bars: Vec<_> = renderer
+            .calls
+            .iter()
+            .filter_map(|call| match call {
+                DrawCall::Rectangle { rect, .. } => Some(rect.w),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(bars, vec![600.0, 150.0]);
+        assert_eq!(LoadProgress::default().fraction(), 1.0);
+    }
+
+    #[test]
+    fn load_errors_list_every_missing_path() {
+        let progress = LoadProgress {
+            total: 3,
+            finished: 3,
+            failed: vec![
+                ("a.png".to_string(), "not found".to_string()),
+                ("b.wav".to_string(), "not found".to_string()),
+            ],
+        };
+        let mut renderer = RecordingRenderer::new();
+
+        draw_load_errors(&progress, &mut renderer);
+
+        let lines: Vec<_> = renderer
+            .calls
+            .iter()
+            .filter_map(|call| match call {
+                DrawCall::Text { text, .. } => Some(text.as_str()),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(
+            lines,
+            vec!["Couldn't load:", "a.png: not found", "b.wav: not found"]
+        );
+    }
+
+    #[test]
+    fn asset_server_deduplicates_by_name() {
+        let mut assets = AssetServer::new();
+
+        let first = assets.add("ship.png", placeholder_texture(1));
+        let again = assets.add("ship.png", placeholder_texture(2));
+        let other = assets.add("enemy.png", placeholder_texture(3));
+
+        assert_eq!(first, again);
+        assert_ne!(first, other);
+        assert_eq!(assets.get(first), &placeholder_texture(1));
+        assert_eq!(assets.handle::<Texture>("enemy.png"), Some(other));
+        assert_eq!(assets.get_by_name::<Texture>("missing.png"), None);
+    }
+
+    #[test]
+    fn asset_names_are_per_type() {
+        let mut assets = AssetServer::new();
+        assets.add(
+            "laser",
+            Sound {
+                bytes: vec![1, 2, 3],
+            },
+        );
+
+        assert_eq!(assets.handle::<Texture>("laser"), None);
+        assert_eq!(
+            assets.get_by_name::<Sound>("laser").map(|s| s.bytes.len()),
+            Some(3)
+        );
+    }
+
+    // Writes `contents` to `path` and pushes its modification time forward,
+    // so the change is seen even within the filesystem's timestamp precision.
+    fn touch(path: &std::path::Path, contents: &[u8], seconds: u64) {
+        std::fs::write(path, contents).unwrap();
+        let file = std::fs::File::options().write(true).open(path).unwrap();
+        file.set_modified(std::time::SystemTime::now() + std::time::Duration::from_secs(seconds))
+            .unwrap();
+    }
+
+    #[test]
+    fn watcher_reloads_changed_files_in_place() {
+        let root = std::env::temp_dir().join(format!("hot-reload-{}", std::process::id()));
+        std::fs::create_dir_all(&root).unwrap();
+        let config = root.join("tuning.txt");
+        let shader = root.join("stars.glsl");
+        std::fs::write(&config, "speed = 1").unwrap();
+        std::fs::write(&shader, "void main() {}").unwrap();
+
+        let manifest = AssetManifest::new()
+            .with_data("tuning.txt")
+            .with_shader("stars.glsl");
+        let mut assets = AssetServer::new();
+        let tuning = assets.add(
+            "tuning.txt",
+            Data {
+                bytes: b"speed = 1".to_vec(),
+            },
+        );
+        let mut watcher = AssetWatcher::new(root.to_str().unwrap(), &manifest);
+        assert_eq!(watcher.check(&mut assets), vec![]);
+
+        touch(&config, b"speed = 2", 10);
+        touch(&shader, &[0xff, 0xfe], 10);
+        let events = watcher.check(&mut assets);
+
+        assert_eq!(events[0], ReloadEvent::Reloaded("tuning.txt".to_string()));
+        assert!(matches!(&events[1], ReloadEvent::Failed { path, .. } if path == "stars.glsl"));
+        assert_eq!(assets.get(tuning).as_str(), Some("speed = 2"));
+        assert_eq!(assets.get_by_name::<Shader>("stars.glsl"), None);
+        assert_eq!(watcher.check(&mut assets), vec![]);
+
+        std::fs::remove_dir_all(root).unwrap();
+    }
+
+    fn sound_handles() -> (Handle<Sound>, Handle<Sound>) {
+        let mut assets = AssetServer::new();
+        let a = assets.add("a.ogg", Sound { bytes: vec![] });
+        let b = assets.add("b.wav", Sound { bytes: vec![] });
+        (a, b)
+    }
+
+    #[test]
+    fn volume_buses_scale_by_master_and_mute() {
+        let mut audio = Audio::new(Box::new(NullAudio));
+        audio.set_master_volume(0.5);
+        audio.set_music_volume(0.5);
+        audio.set_sfx_volume(2.0);
+
+        let volumes = audio.volumes();
+        assert_eq!(volumes.effective(Bus::Music), 0.25);
+        assert_eq!(volumes.effective(Bus::Sfx), 0.5);
+
+        audio.toggle_mute();
+        assert_eq!(audio.volumes().effective(Bus::Music), 0.0);
+        assert_eq!(audio.volumes().effective(Bus::Sfx), 0.0);
+    }
+
+    #[test]
+    fn music_loops_and_replaces_the_previous_track() {
+        let (a, b) = sound_handles();
+        let backend = RecordingAudio::new();
+        let mut audio = Audio::new(Box::new(backend.clone()));
+
+        audio.play_music(a);
+        audio.play_music(a);
+        audio.play_music(b);
+        audio.stop_music();
+
+        assert_eq!(
+            backend.calls(),
+            vec![
+                AudioCall::Play {
+                    sound: a,
+                    looped: true,
+                    volume: 1.0
+                },
+                AudioCall::Stop(a),
+                AudioCall::Play {
+                    sound: b,
+                    looped: true,
+                    volume: 1.0
+                },
+                AudioCall::Stop(b),
+            ]
+        );
+        assert_eq!(audio.music(), None);
+    }
+
+    #[test]
+    fn muting_silences_music_and_skips_effects() {
+        let (music, effect) = sound_handles();
+        let backend = RecordingAudio::new();
+        let mut audio = Audio::new(Box::new(backend.clone()));
+
+        audio.play_music(music);
+        audio.set_muted(true);
+        audio.play_sfx(effect);
+        audio.set_muted(false);
+        audio.play_sfx(effect);
+
+        assert_eq!(
+            backend.calls()[1..],
+            [
+                AudioCall::SetVolume(music, 0.0),
+                AudioCall::SetVolume(music, 1.0),
+                AudioCall::Play {
+                    sound: effect,
+                    looped: false,
+                    volume: 1.0
+                },
+            ]
+        );
+    }
+
+    /// Lets a test keep hold of storage it has handed to an [`Audio`].
+    #[derive(Default, Clone)]
+    struct SharedStorage(std::rc::Rc<std::cell::RefCell<MemoryStorage>>);
+
+    impl Storage for SharedStorage {
+        fn get(&self, key: &str) -> Option<String> {
+            self.0.borrow().get(key)
+        }
+
+        fn set(&mut self, key: &str, value: &str) {
+            self.0.borrow_mut().set(key, value);
+        }
+    }
+
+    #[test]
+    fn volume_and_mute_persist_across_sessions() {
+        let storage = SharedStorage::default();
+        let mut audio = Audio::default().with_storage(Box::new(storage.clone()));
+        audio.set_sfx_volume(0.25);
+        audio.toggle_mute();
+
+        let restored = Audio::default().with_storage(Box::new(storage));
+        assert_eq!(
+            restored.volumes(),
+            Volumes {
+                sfx: 0.25,
+                muted: true,
+                ..Default::default()
+            }
+        );
+    }
+
+    #[test]
+    fn asset_server_iterates_one_type_in_order() {
+        let mut assets = AssetServer::new();
+        let a = assets.add("a.ogg", Sound { bytes: vec![1] });
+        assets.add("a.txt", Data { bytes: vec![] });
+        let b = assets.add("b.ogg", Sound { bytes: vec![2] });
+
+        let sounds: Vec<(Handle<Sound>, u8)> = assets
+            .iter::<Sound>()
+            .map(|(h, s)| (h, s.bytes[0]))
+            .collect();
+        assert_eq!(sounds, vec![(a, 1), (b, 2)]);
+    }
+
+    fn line_texts(layout: &TextLayout) -> Vec<&str> {
+        layout.lines.iter().map(|l| l.text.as_str()).collect()
+    }
+
+    #[test]
+    fn text_centres_on_position_when_middle_aligned() {
+        let renderer = RecordingRenderer::new();
+        let style = TextStyle::new(20.0).aligned(HAlign::Center, VAlign::Middle);
+
+        let layout = layout_text(
+            "ABCD",
+            vec2(100.0, 100.0),
+            &style,
+            &AssetServer::new(),
+            &renderer,
+        );
+
+        let size = fixed_text_size("ABCD", 20.0);
+        assert_eq!(
+            layout.bounds,
+            Rect::new(80.0, 90.0, size.width, size.height)
+        );
+        assert_eq!(layout.lines[0].position, vec2(80.0, 90.0 + size.offset_y));
+    }
+
+    #[test]
+    fn right_aligned_text_ends_at_position_on_the_baseline() {
+        let renderer = RecordingRenderer::new();
+        let style = TextStyle::new(10.0).aligned(HAlign::Right, VAlign::Baseline);
+
+        let layout = layout_text(
+            "ab\nabcd",
+            vec2(50.0, 30.0),
+            &style,
+            &AssetServer::new(),
+            &renderer,
+        );
+
+        let positions: Vec<Vec2> = layout.lines.iter().map(|l| l.position).collect();
+        assert_eq!(positions, vec![vec2(40.0, 30.0), vec2(30.0, 42.5)]);
+    }
+
+    #[test]
+    fn text_wraps_between_words_to_fit_max_width() {
+        let renderer = RecordingRenderer::new();
+        let style = TextStyle::new(10.0).wrapped(50.0);
+
+        let layout = layout_text(
+            "the quick brown fox",
+            Vec2::ZERO,
+            &style,
+            &AssetServer::new(),
+            &renderer,
+        );
+
+        assert_eq!(line_texts(&layout), vec!["the quick", "brown fox"]);
+        assert!(layout.bounds.w <= 50.0);
+    }
+
+    #[test]
+    fn words_wider_than_the_wrap_width_get_their_own_line() {
+        let renderer = RecordingRenderer::new();
+        let style = TextStyle::new(10.0).wrapped(20.0);
+
+        let layout = layout_text(
+            "a\nsupercalifragilistic b",
+            Vec2::ZERO,
+            &style,
+            &AssetServer::new(),
+            &renderer,
+        );
+
+        assert_eq!(line_texts(&layout), vec!["a", "supercalifragilistic", "b"]);
+    }
+
+    #[test]
+    fn render_text_draws_each_laid_out_line() {
+        let mut renderer = RecordingRenderer::new();
+        let style = TextStyle::new(10.0).with_color(RED);
+
+        let layout = render_text(
+            "one\ntwo",
+            vec2(5.0, 5.0),
+            &style,
+            &AssetServer::new(),
+            &mut renderer,
+        );
+
+        let drawn: Vec<DrawCall> = layout
+            .lines
+            .iter()
+            .map(|line| DrawCall::Text {
+                text: line.text.clone(),
+                x: line.position.x,
+                y: line.position.y,
+                custom_font: false,
+                font_size: 10.0,
+                color: RED,
+            })
+            .collect();
+        assert_eq!(renderer.calls, drawn);
+    }
+
+    #[derive(Debug, Default, PartialEq)]
+    struct Settings {
+        clicks: u32,
+        volume: f32,
+        muted: bool,
+    }
+
+    fn settings_ui() -> Ui<Settings> {
+        Ui::<Settings>::new(UiSkin::default())
+            .with_panel(Rect::new(0.0, 0.0, 200.0, 200.0))
+            .with_label(Rect::new(10.0, 10.0, 180.0, 20.0), "Options")
+            .with_button(Rect::new(10.0, 40.0, 180.0, 40.0), "Play", |s| {
+                s.clicks += 1
+            })
+            .with_slider(Rect::new(10.0, 90.0, 180.0, 40.0), "Volume", 0.5, |s, v| {
+                s.volume = v
+            })
+            .with_toggle(
+                Rect::new(10.0, 140.0, 180.0, 40.0),
+                "Mute",
+                false,
+                |s, on| s.muted = on,
+            )
+    }
+
+    fn pointer(x: f32, y: f32, pressed: bool, down: bool, released: bool) -> UiInput {
+        UiInput {
+            pointer: Some(vec2(x, y)),
+            pointer_pressed: pressed,
+            pointer_down: down,
+            pointer_released: released,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn keyboard_focus_skips_panels_and_labels_and_wraps() {
+        let mut ui = settings_ui();
+        let mut settings = Settings::default();
+        let down = UiInput {
+            down: true,
+            ..Default::default()
+        };
+
+        let mut focused = vec![];
+        for _ in 0..4 {
+            ui.update(&down, &mut settings);
+            focused.push(ui.focus.unwrap());
+        }
+        assert_eq!(focused, vec![2, 3, 4, 2]);
+
+        ui.update(
+            &UiInput {
+                up: true,
+                ..Default::default()
+            },
+            &mut settings,
+        );
+        assert_eq!(ui.focus, Some(4));
+    }
+
+    #[test]
+    fn keys_activate_buttons_toggles_and_step_sliders() {
+        let mut ui = settings_ui();
+        let mut settings = Settings::default();
+        let key = |f: fn(&mut UiInput)| {
+            let mut input = UiInput::default();
+            f(&mut input);
+            input
+        };
+
+        ui.update(&key(|i| i.down = true), &mut settings);
+        ui.update(&key(|i| i.activate = true), &mut settings);
+        ui.update(&key(|i| i.down = true), &mut settings);
+        ui.update(&key(|i| i.right = true), &mut settings);
+        ui.update(&key(|i| i.down = true), &mut settings);
+        ui.update(&key(|i| i.activate = true), &mut settings);
+
+        assert_eq!(settings.clicks, 1);
+        assert!((settings.volume - 0.6).abs() < 1e-6);
+        assert!(settings.muted);
+    }
+
+    #[test]
+    fn buttons_click_when_released_over_them() {
+        let mut ui = settings_ui();
+        let mut settings = Settings::default();
+
+        ui.update(&pointer(50.0, 50.0, true, true, false), &mut settings);
+        assert!(ui.is_pressed(2));
+        assert_eq!(settings.clicks, 0);
+        ui.update(&pointer(50.0, 50.0, false, false, true), &mut settings);
+        assert_eq!(settings.clicks, 1);
+        assert!(!ui.is_pressed(2));
+
+        // Dragging off before letting go cancels the click.
+        ui.update(&pointer(50.0, 50.0, true, true, false), &mut settings);
+        ui.update(&pointer(50.0, 300.0, false, false, true), &mut settings);
+        assert_eq!(settings.clicks, 1);
+    }
+
+    #[test]
+    fn sliders_follow_a_held_pointer() {
+        let mut ui = settings_ui();
+        let mut settings = Settings::default();
+
+        // The label half focuses the slider without moving it.
+        ui.update(&pointer(55.0, 100.0, true, true, false), &mut settings);
+        assert_eq!(settings.volume, 0.0);
+        assert_eq!(ui.focus, Some(3));
+        ui.update(&pointer(55.0, 100.0, false, false, true), &mut settings);
+
+        // The track runs across the right half, from 100 to 190.
+        ui.update(&pointer(122.5, 100.0, true, true, false), &mut settings);
+        assert_eq!(settings.volume, 0.25);
+        ui.update(&pointer(500.0, 300.0, false, true, false), &mut settings);
+        assert_eq!(settings.volume, 1.0);
+        ui.update(&pointer(10.0, 100.0, false, false, true), &mut settings);
+        assert_eq!(settings.volume, 0.0);
+        ui.update(&pointer(100.0, 100.0, false, false, false), &mut settings);
+        assert_eq!(settings.volume, 0.0);
+    }
+
+    #[test]
+    fn nine_slice_keeps_border_size_and_stretches_the_middle() {
+        let mut assets = AssetServer::new();
+        let texture = assets.add("button.png", placeholder_texture(1));
+        let slice = NineSlice::new(
+            texture,
+            vec2(36.0, 36.0),
+            RectOffset::new(8.0, 8.0, 8.0, 8.0),
+        );
+        let mut renderer = RecordingRenderer::new();
+
+        slice.draw(
+            Rect::new(0.0, 0.0, 100.0, 50.0),
+            WHITE,
+            &assets,
+            &mut renderer,
+        );
+
+        let pieces: Vec<(Rect, Rect)> = renderer
+            .textures()
+            .map(|call| {
+                let DrawCall::Texture {
+                    x,
+                    y,
+                    dest_size: Some(size),
+                    source: Some(source),
+                    ..
+                } = call
+                else {
+                    panic!("not a sized texture draw: {call:?}");
+                };
+                (Rect::new(*x, *y, size.x, size.y), *source)
+            })
+            .collect();
+        assert_eq!(pieces.len(), 9);
+        assert_eq!(
+            pieces[0],
+            (Rect::new(0.0, 0.0, 8.0, 8.0), Rect::new(0.0, 0.0, 8.0, 8.0))
+        );
+        assert_eq!(
+            pieces[4],
+            (
+                Rect::new(8.0, 8.0, 84.0, 34.0),
+                Rect::new(8.0, 8.0, 20.0, 20.0)
+            )
+        );
+        assert_eq!(
+            pieces[8],
+            (
+                Rect::new(92.0, 42.0, 8.0, 8.0),
+                Rect::new(28.0, 28.0, 8.0, 8.0)
+            )
+        );
+    }
+
+    #[test]
+    fn ui_draws_in_screen_space_and_outlines_the_focus() {
+        let mut ui = settings_ui();
+        ui.focus = Some(2);
+        let mut renderer = RecordingRenderer::new();
+
+        ui.render(&AssetServer::new(), &mut renderer);
+
+        assert_eq!(renderer.calls[0], DrawCall::SetDefaultCamera);
+        let outlines: Vec<Rect> = renderer
+            .calls
+            .iter()
+            .filter_map(|call| match call {
+                DrawCall::RectangleLines { rect, .. } => Some(*rect),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(outlines, vec![Rect::new(10.0, 40.0, 180.0, 40.0)]);
+    }
+
+    // Space, which is bound to both jump and confirm by default.
+    fn press(space: bool, pause: bool) -> Input {
+        Input {
+            dt: DEFAULT_STEP,
+            jump: space,
+            confirm: space,
+            pause,
+            ..Default::default()
+        }
+    }
+
+    // Hooks append a digit to the score so tests can read back their order:
+    // 1 and 2 for entering and exiting "a", 3 and 4 for "b".
+    fn hook_game() -> Game {
+        fn log(state: &mut GameState, digit: f32) {
+            state.score = state.score * 10.0 + digit;
+        }
+        Game::new(World::new())
+            .with_scene(
+                "a",
+                Scene::new()
+                    .with_on_enter(|_, s| log(s, 1.0))
+                    .with_on_exit(|_, s| log(s, 2.0)),
+            )
+            .with_scene(
+                "b",
+                Scene::new()
+                    .with_on_enter(|_, s| log(s, 3.0))
+                    .with_on_exit(|_, s| log(s, 4.0)),
+            )
+    }
+
+    #[test]
+    fn scene_transitions_run_enter_and_exit_hooks() {
+        let mut game = hook_game();
+
+        game.push_scene("a");
+        game.push_scene("b");
+        assert_eq!(game.scenes().stack(), ["a", "b"]);
+        game.pop_scene();
+        game.switch_scene("b");
+        game.reset_scene("a");
+
+        assert_eq!(game.scenes().stack(), ["a"]);
+        assert_eq!(game.state.score, 1_342_341.0);
+    }
+
+    #[test]
+    fn only_the_top_scene_updates_and_transitions_wait_for_the_update() {
+        fn count(_: &mut World, state: &mut GameState, _: &Input) {
+            state.score += 1.0;
+        }
+        fn leave(_: &mut World, state: &mut GameState, _: &Input) {
+            state.pop_scene();
+        }
+        let mut game = Game::new(World::new())
+            .with_scene("under", Scene::new().with_update_system(count))
+            .with_scene("over", Scene::new().with_update_system(leave));
+        game.push_scene("under");
+        game.push_scene("over");
+
+        game.update(&press(false, false));
+        assert_eq!(game.state.score, 0.0);
+        assert_eq!(game.scene(), Some("under"));
+
+        game.update(&press(false, false));
+        assert_eq!(game.state.score, 1.0);
+    }
+
+    #[test]
+    fn overlays_draw_over_the_scene_beneath() {
+        fn draw_under(_: &World, _: &GameState, renderer: &mut dyn Renderer) {
+            renderer.draw_rectangle(0.0, 0.0, 1.0, 1.0, RED);
+        }
+        fn draw_over(_: &World, _: &GameState, renderer: &mut dyn Renderer) {
+            renderer.draw_rectangle(0.0, 0.0, 2.0, 2.0, WHITE);
+        }
+        let mut game = Game::new(World::new())
+            .with_scene("bottom", Scene::new().with_render_system(draw_under))
+            .with_scene("under", Scene::new().with_render_system(draw_under))
+            .with_scene("over", Scene::new().overlay().with_render_system(draw_over));
+        game.push_scene("bottom");
+        game.push_scene("under");
+        game.push_scene("over");
+
+        assert_eq!(game.scenes().visible(), ["under", "over"]);
+        let mut renderer = RecordingRenderer::new();
+        game.render(&mut renderer);
+        let colors: Vec<Color> = renderer
+            .calls
+            .iter()
+            .filter_map(|call| match call {
+                DrawCall::Rectangle { color, .. } => Some(*color),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(colors, vec![RED, WHITE]);
+    }
+
+    fn standard_game() -> Game {
+        fn start(world: &mut World, _: &mut GameState) {
+            *world = World::new().with_entity(test_entity(None));
+        }
+        fn end_on_space(_: &mut World, state: &mut GameState, input: &Input) {
+            if input.jump {
+                state.push_scene(GAME_OVER);
+            }
+        }
+        let playing = Scene::new()
+            .with_on_enter(start)
+            .with_update_systems(vec![wander, end_on_space]);
+        Game::new(World::new()).with_standard_flow(playing)
+    }
+
+    #[test]
+    fn standard_flow_goes_from_title_to_play_to_game_over_and_back() {
+        let mut game = standard_game();
+        assert_eq!(game.scene(), Some(TITLE));
+
+        game.update(&press(true, false));
+        assert_eq!(game.scene(), Some(PLAYING));
+        assert_eq!(game.world.entities().len(), 1);
+
+        game.update(&press(false, true));
+        assert_eq!(game.scenes().stack(), [PLAYING, PAUSED]);
+        game.update(&press(false, true));
+        assert_eq!(game.scene(), Some(PLAYING));
+        game.update(&press(false, true));
+        game.update(&press(true, false));
+        assert_eq!(game.scene(), Some(PLAYING));
+
+        game.update(&press(true, false));
+        assert_eq!(game.scenes().stack(), [PLAYING, GAME_OVER]);
+        game.world.clear();
+        game.update(&press(true, false));
+        assert_eq!(game.scenes().stack(), [PLAYING]);
+        assert_eq!(game.world.entities().len(), 1);
+    }
+
+    fn banner_text(flow: &SceneFlow) -> Vec<String> {
+        let mut renderer = RecordingRenderer::new();
+        flow.render(
+            &VirtualCanvas::new(800.0, 600.0),
+            &AssetServer::new(),
+            &mut renderer,
+        );
+        renderer
+            .calls
+            .into_iter()
+            .filter_map(|call| match call {
+                DrawCall::Text { text, .. } => Some(text),
+                _ => None,
+            })
+            .collect()
+    }
+
+    #[test]
+    fn scene_flow_runs_title_play_and_game_over_without_a_game() {
+        let mut flow = SceneFlow::new();
+        assert_eq!(flow.scene(), TITLE);
+        assert_eq!(banner_text(&flow), ["READY?", "Press space to start"]);
+        assert!(!flow.update(false));
+
+        assert!(flow.update(true));
+        assert!(flow.is_playing());
+        assert!(banner_text(&flow).is_empty());
+        // Confirm means nothing mid-run.
+        assert!(!flow.update(true));
+
+        flow.game_over();
+        assert_eq!(flow.scene(), GAME_OVER);
+        assert_eq!(banner_text(&flow), ["GAME OVER!", "Press space to retry"]);
+        assert!(flow.update(true));
+        assert!(flow.is_playing());
+    }
+
+    #[test]
+    fn each_run_gets_its_own_recording() {
+        let mut game = standard_game().with_seed(3).with_recording();
+        game.update(&press(true, false));
+        for _ in 0..10 {
+            game.update(&press(false, false));
+        }
+        game.update(&press(true, false));
+        let first = game.recording.clone().unwrap();
+        let end_of_first = game.world.entities()[0].transform;
+        assert_eq!(first.inputs.len(), 11);
+
+        game.update(&press(true, false));
+        let second = game.recording.clone().unwrap();
+        assert!(second.inputs.is_empty());
+        assert_ne!(second.seed, first.seed);
+
+        let mut replayed = standard_game();
+        first.replay(&mut replayed);
+        assert_eq!(replayed.scenes().stack(), [PLAYING, GAME_OVER]);
+        assert_eq!(replayed.world.entities()[0].transform, end_of_first);
+    }
+
+    fn focus_lost() -> Input {
+        Input {
+            focus_lost: true,
+            ..press(false, false)
+        }
+    }
+
+    #[test]
+    fn losing_focus_pauses_without_resuming() {
+        let mut game = standard_game();
+        game.update(&press(true, false));
+        game.update(&focus_lost());
+        assert_eq!(game.scenes().stack(), [PLAYING, PAUSED]);
+        game.update(&focus_lost());
+        assert_eq!(game.scenes().stack(), [PLAYING, PAUSED]);
+        game.update(&press(false, true));
+        assert_eq!(game.scene(), Some(PLAYING));
+    }
+
+    #[test]
+    fn pausing_freezes_the_world() {
+        let mut game = standard_game().with_seed(8);
+        game.update(&press(true, false));
+        game.update(&press(false, false));
+        game.update(&press(false, true));
+        let paused_at = game.world.entities()[0].transform;
+
+        game.fixed_update(1.0, &press(false, false));
+        let entity = &game.world.entities()[0];
+        assert_eq!(entity.transform, paused_at);
+        assert_eq!(entity.interpolated(game.state.alpha), paused_at);
+    }
+
+    #[test]
+    fn replays_pause_where_focus_was_lost() {
+        let mut game = standard_game().with_seed(4).with_recording();
+        game.update(&press(true, false));
+        game.update(&press(false, false));
+        game.update(&focus_lost());
+        game.update(&press(false, false));
+        let recording = game.recording.clone().unwrap();
+
+        let loaded = Recording::from_bytes(&recording.to_bytes()).unwrap();
+        assert_eq!(loaded, recording);
+        let mut replayed = standard_game();
+        loaded.replay(&mut replayed);
+        assert_eq!(replayed.scenes().stack(), [PLAYING, PAUSED]);
+        assert_eq!(
+            replayed.world.entities()[0].transform,
+            game.world.entities()[0].transform
+        );
+    }
+
+    #[test]
+    fn focus_watcher_treats_a_stalled_frame_as_lost_focus() {
+        let mut focus = FocusWatcher::new(STALL_SECONDS);
+        assert!(!focus.poll(DEFAULT_STEP));
+        assert!(focus.poll(STALL_SECONDS));
+        assert!(!focus.poll(DEFAULT_STEP));
+
+        let mut patient = FocusWatcher::new(1.0);
+        patient.poll(DEFAULT_STEP);
+        assert!(!patient.poll(STALL_SECONDS));
+    }
+
+    #[test]
+    fn focus_watcher_ignores_a_slow_first_frame() {
+        let mut focus = FocusWatcher::new(STALL_SECONDS);
+        assert!(!focus.poll(STALL_SECONDS * 4.0));
+        assert!(focus.poll(STALL_SECONDS * 4.0));
+    }
+
+    #[test]
+    fn action_map_turns_bindings_into_input() {
+        let actions = ActionMap::new();
+        let mut source = SimulatedInput::new();
+        source.press(Binding::Key(KeyCode::Space));
+        source.press(Binding::Key(KeyCode::Left));
+        source.press(Binding::Key(KeyCode::W));
+
+        let input = actions.input(DEFAULT_STEP, &source);
+        assert!(input.jump && input.fire && input.confirm && !input.pause);
+        assert_eq!((input.move_x, input.move_y), (-1.0, 1.0));
+
+        // Opposite directions cancel out, and presses last a single frame.
+        source.press(Binding::Key(KeyCode::D));
+        source.next_frame();
+        let input = actions.input(DEFAULT_STEP, &source);
+        assert!(!input.jump && !input.fire);
+        assert_eq!(input.move_x, 0.0);
+        assert!(actions.is_down(Action::Jump, &source));
+    }
+
+    #[test]
+    fn rebinding_replaces_only_the_same_device_and_direction() {
+        let mut actions = ActionMap::new();
+        actions.rebind(Action::Jump, Binding::Key(KeyCode::J), 1.0);
+        actions.rebind(Action::MoveX, Binding::Key(KeyCode::H), -1.0);
+
+        assert_eq!(
+            actions.bindings(Action::Jump),
+            [
+                (Binding::Touch, 1.0),
+                (Binding::Pad(PadButton::South), 1.0),
+                (Binding::Key(KeyCode::J), 1.0)
+            ]
+        );
+        let mut source = SimulatedInput::new();
+        source.press(Binding::Key(KeyCode::Left));
+        assert_eq!(actions.value(Action::MoveX, &source), 0.0);
+        source.press(Binding::Key(KeyCode::Right));
+        assert_eq!(actions.value(Action::MoveX, &source), 1.0);
+        source.press(Binding::Key(KeyCode::H));
+        assert_eq!(actions.value(Action::MoveX, &source), 0.0);
+    }
+
+    #[test]
+    fn rebinds_persist_across_sessions() {
+        let storage = SharedStorage::default();
+        let mut actions = ActionMap::new().with_storage(Box::new(storage.clone()));
+        actions.rebind(Action::Fire, Binding::Key(KeyCode::LeftControl), 1.0);
+        actions.rebind(Action::MoveX, Binding::Mouse(MouseButton::Right), -1.0);
+        actions.rebind(Action::MoveY, Binding::Stick(PadAxis::RightY), -1.0);
+
+        let restored = ActionMap::new().with_storage(Box::new(storage));
+        for action in Action::ALL {
+            assert_eq!(restored.bindings(action), actions.bindings(action));
+        }
+        assert_eq!(
+            restored.bindings(Action::Fire),
+            [
+                (Binding::Mouse(MouseButton::Left), 1.0),
+                (Binding::Pad(PadButton::West), 1.0),
+                (Binding::Key(KeyCode::LeftControl), 1.0)
+            ]
+        );
+    }
+
+    #[test]
+    fn old_replays_load_without_the_new_inputs() {
+        let mut bytes = b"RPLY\x01".to_vec();
+        bytes.extend_from_slice(&9u64.to_le_bytes());
+        bytes.extend_from_slice(&1u32.to_le_bytes());
+        bytes.extend_from_slice(&2u32.to_le_bytes());
+        bytes.extend_from_slice(&0.5f32.to_le_bytes());
+        bytes.push(1);
+
+        let recording = Recording::from_bytes(&bytes).unwrap();
+        let input = Input {
+            dt: 0.5,
+            jump: true,
+            ..Default::default()
+        };
+        assert_eq!(recording.inputs, [input, input]);
+    }
+
+    #[test]
+    fn gamepads_report_hot_plugging_by_slot() {
+        let sim = SimulatedPads::default();
+        let mut pads = Gamepads::new(Box::new(sim.clone()));
+        assert!(pads.update().is_empty());
+
+        sim.set(1, PadState::default());
+        assert_eq!(pads.update(), [PadEvent::Connected(1)]);
+        assert_eq!(pads.connected().collect::<Vec<_>>(), [1]);
+
+        sim.set(0, PadState::default());
+        sim.unplug(1);
+        assert_eq!(
+            pads.update(),
+            [PadEvent::Connected(0), PadEvent::Disconnected(1)]
+        );
+        assert_eq!(pads.connected().collect::<Vec<_>>(), [0]);
+    }
+
+    #[test]
+    fn pad_buttons_press_once_and_combine_across_pads() {
+        let sim = SimulatedPads::default();
+        let mut pads = Gamepads::new(Box::new(sim.clone()));
+        sim.set(0, PadState::default().with_button(PadButton::South, 1.0));
+        pads.update();
+        assert!(pads.button_pressed(PadButton::South));
+
+        sim.set(
+            1,
+            PadState::default().with_button(PadButton::RightTrigger, 0.4),
+        );
+        pads.update();
+        assert!(!pads.button_pressed(PadButton::South));
+        assert_eq!(pads.button(PadButton::South), 1.0);
+        assert_eq!(pads.button(PadButton::RightTrigger), 0.4);
+    }
+
+    #[test]
+    fn sticks_ignore_the_dead_zone_and_rescale_the_rest() {
+        let sim = SimulatedPads::default();
+        let mut pads = Gamepads::new(Box::new(sim.clone())).with_dead_zone(0.25);
+        let stick = |x: f32, y: f32| {
+            PadState::default()
+                .with_axis(PadAxis::LeftX, x)
+                .with_axis(PadAxis::LeftY, y)
+        };
+
+        sim.set(0, stick(0.15, -0.15));
+        pads.update();
+        assert_eq!(pads.axis(PadAxis::LeftX), 0.0);
+        assert_eq!(pads.axis(PadAxis::LeftY), 0.0);
+
+        sim.set(0, stick(0.625, 0.0));
+        pads.update();
+        assert_eq!(pads.axis(PadAxis::LeftX), 0.5);
+
+        sim.set(0, stick(0.0, 1.0));
+        pads.update();
+        assert_eq!(pads.axis(PadAxis::LeftY), 1.0);
+        assert_eq!(pads.axis(PadAxis::RightY), 0.0);
+    }
+
+    #[test]
+    fn a_simulated_pad_drives_the_default_actions() {
+        let actions = ActionMap::new();
+        let sim = SimulatedPads::default();
+        let mut pads = Gamepads::new(Box::new(sim.clone()));
+        sim.set(
+            0,
+            PadState::default()
+                .with_button(PadButton::South, 1.0)
+                .with_button(PadButton::DPadUp, 1.0)
+                .with_axis(PadAxis::LeftX, -1.0),
+        );
+        pads.update();
+
+        let input = actions.input(DEFAULT_STEP, &(SimulatedInput::new(), &pads));
+        assert!(input.jump && input.confirm && !input.fire);
+        assert_eq!((input.move_x, input.move_y), (-1.0, 1.0));
+
+        // Holding right on the keyboard cancels the stick out.
+        let mut keys = SimulatedInput::new();
+        keys.press(Binding::Key(KeyCode::Right));
+        assert_eq!(actions.value(Action::MoveX, &(keys, &pads)), 0.0);
+    }
+
+    fn finger(id: u64, phase: TouchPhase, x: f32, y: f32) -> Touch {
+        Touch {
+            id,
+            phase,
+            position: vec2(x, y),
+        }
+    }
+
+    // On a 1000x500 screen the stick has a radius of 60 and sits at
+    // (120, 380), with the fire button at (880, 380).
+    const PHONE: Vec2 = Vec2::new(1000.0, 500.0);
+
+    #[test]
+    fn touch_controls_stay_hidden_until_touched() {
+        let mut controls = TouchControls::new().with_button(PadButton::West, "FIRE");
+        let mut renderer = RecordingRenderer::new();
+        controls.update(&[], PHONE);
+        controls.render(&mut renderer);
+        assert!(renderer.calls.is_empty());
+
+        controls.update(&[finger(1, TouchPhase::Started, 500.0, 100.0)], PHONE);
+        controls.render(&mut renderer);
+        let circles = renderer
+            .calls
+            .iter()
+            .filter(|call| matches!(call, DrawCall::Circle { .. }))
+            .count();
+        assert!(controls.is_enabled());
+        assert_eq!(circles, 3);
+    }
+
+    #[test]
+    fn the_stick_follows_the_touch_that_took_it() {
+        let mut controls = TouchControls::new();
+        controls.update(&[finger(1, TouchPhase::Started, 120.0, 380.0)], PHONE);
+        assert_eq!(controls.stick(), Vec2::ZERO);
+
+        controls.update(&[finger(1, TouchPhase::Moved, 150.0, 380.0)], PHONE);
+        assert_eq!(controls.stick(), vec2(0.5, 0.0));
+
+        // Dragging past the rim pins it there, even far from the stick.
+        controls.update(&[finger(1, TouchPhase::Moved, 120.0, 0.0)], PHONE);
+        assert_eq!(controls.stick(), vec2(0.0, -1.0));
+
+        controls.update(&[finger(1, TouchPhase::Ended, 120.0, 0.0)], PHONE);
+        assert_eq!(controls.stick(), Vec2::ZERO);
+    }
+
+    #[test]
+    fn touch_controls_drive_actions_like_a_pad() {
+        let actions = ActionMap::new();
+        let mut controls = TouchControls::new().with_button(PadButton::West, "FIRE");
+        controls.update(
+            &[
+                finger(1, TouchPhase::Started, 90.0, 380.0),
+                finger(2, TouchPhase::Started, 880.0, 380.0),
+            ],
+            PHONE,
+        );
+        let input = actions.input(DEFAULT_STEP, &controls);
+        assert!(input.fire);
+        assert_eq!(input.move_x, -0.5);
+
+        controls.update(
+            &[
+                finger(1, TouchPhase::Stationary, 120.0, 320.0),
+                finger(2, TouchPhase::Stationary, 880.0, 380.0),
+            ],
+            PHONE,
+        );
+        let input = actions.input(DEFAULT_STEP, &controls);
+        assert!(!input.fire && actions.is_down(Action::Fire, &controls));
+        assert_eq!(input.move_y, 1.0);
+    }
+
+    fn snapping(dead_zone: Vec2) -> Camera {
+        Camera::new(Vec2::ZERO).following(Tag::Player, dead_zone, f32::INFINITY)
+    }
+
+    #[test]
+    fn camera_ignores_focus_inside_the_dead_zone() {
+        let canvas = VirtualCanvas::default();
+        let mut camera = snapping(vec2(50.0, 50.0));
+
+        camera.update(DEFAULT_STEP, Some(vec2(30.0, -40.0)), &canvas);
+        assert_eq!(camera.position, Vec2::ZERO);
+
+        camera.update(DEFAULT_STEP, Some(vec2(80.0, -70.0)), &canvas);
+        assert_eq!(camera.position, vec2(30.0, -20.0));
+    }
+
+    #[test]
+    fn camera_eases_towards_focus() {
+        let canvas = VirtualCanvas::default();
+        let mut camera = Camera::new(Vec2::ZERO).following(Tag::Player, Vec2::ZERO, 5.0);
+
+        camera.update(0.1, Some(vec2(100.0, 0.0)), &canvas);
+        let first = camera.position.x;
+        camera.update(0.1, Some(vec2(100.0, 0.0)), &canvas);
+
+        assert!(0.0 < first && first < camera.position.x && camera.position.x < 100.0);
+    }
+
+    #[test]
+    fn camera_keeps_the_view_inside_bounds() {
+        let canvas = VirtualCanvas::default();
+        let bounds = Rect::new(0.0, 0.0, 2000.0, 1000.0);
+        let mut camera = snapping(Vec2::ZERO).with_bounds(bounds);
+
+        camera.update(DEFAULT_STEP, Some(vec2(-500.0, 5000.0)), &canvas);
+        assert_eq!(camera.position, vec2(400.0, 700.0));
+
+        // Zoomed in, the view is half the size and can get closer to edges.
+        camera.zoom = 2.0;
+        camera.update(DEFAULT_STEP, Some(vec2(-500.0, 5000.0)), &canvas);
+        assert_eq!(camera.position, vec2(200.0, 850.0));
+    }
+
+    #[test]
+    fn camera_centres_on_bounds_smaller_than_the_view() {
+        let canvas = VirtualCanvas::default();
+        let mut camera = snapping(Vec2::ZERO).with_bounds(Rect::new(0.0, 0.0, 500.0, 2000.0));
+
+        camera.update(DEFAULT_STEP, Some(vec2(900.0, 100.0)), &canvas);
+
+        assert_eq!(camera.position, vec2(250.0, 300.0));
+    }
+
+    #[test]
+    fn trauma_shakes_within_limits_then_settles() {
+        let canvas = VirtualCanvas::default();
+        let mut camera = Camera::new(Vec2::ZERO);
+        camera.add_trauma(0.7);
+        camera.add_trauma(0.7);
+        assert_eq!(camera.trauma(), 1.0);
+
+        camera.update(DEFAULT_STEP, None, &canvas);
+        let shake = camera.eye() - camera.position;
+        assert!(shake != Vec2::ZERO);
+        assert!(shake.x.abs() <= MAX_SHAKE && shake.y.abs() <= MAX_SHAKE);
+
+        camera.update(1.0, None, &canvas);
+        assert_eq!(camera.trauma(), 0.0);
+        assert_eq!(camera.eye(), camera.position);
+    }
+
+    #[test]
+    fn camera_zoom_scales_around_the_view_centre() {
+        let mut canvas = VirtualCanvas::default();
+        canvas.resize(vec2(1600.0, 1200.0));
+        let camera = Camera::new(vec2(100.0, 100.0)).with_zoom(2.0);
+
+        assert_eq!(
+            camera.world_to_screen(&canvas, vec2(100.0, 100.0)),
+            vec2(800.0, 600.0)
+        );
+        // Two canvas pixels per unit, and two screen pixels per canvas pixel.
+        assert_eq!(
+            camera.world_to_screen(&canvas, vec2(110.0, 90.0)),
+            vec2(840.0, 640.0)
+        );
+        assert_eq!(
+            camera.screen_to_world(&canvas, vec2(840.0, 640.0)),
+            vec2(110.0, 90.0)
+        );
+    }
+
+    #[test]
+    fn camera_survives_a_negative_dead_zone_and_no_zoom() {
+        let canvas = VirtualCanvas::default();
+        let mut camera = snapping(vec2(-50.0, -50.0));
+
+        camera.update(DEFAULT_STEP, Some(vec2(80.0, -70.0)), &canvas);
+        assert_eq!(camera.position, vec2(30.0, -20.0));
+
+        let camera = Camera::new(Vec2::ZERO).with_zoom(0.0);
+        assert_eq!(camera.zoom, MIN_ZOOM);
+        let mut camera = Camera::new(Vec2::ZERO).with_bounds(Rect::new(0.0, 0.0, 10.0, 10.0));
+        camera.zoom = 0.0;
+        camera.add_trauma(1.0);
+        camera.update(DEFAULT_STEP, None, &canvas);
+        let point = camera.screen_to_world(&canvas, vec2(10.0, 10.0));
+        assert!(camera.eye().is_finite() && point.is_finite());
+    }
+
+    #[test]
+    fn camera_view_matches_its_screen_transform() {
+        use macroquad::camera::Camera as _;
+
+        let mut canvas = VirtualCanvas::default().with_scaling(Scaling::Integer);
+        canvas.resize(vec2(1000.0, 700.0));
+        let camera = Camera::new(vec2(-20.0, 45.0)).with_zoom(1.5);
+
+        let point = vec2(130.0, -60.0);
+        let clip = camera
+            .view(&canvas)
+            .matrix()
+            .project_point3(point.extend(0.0));
+        let projected = vec2((clip.x + 1.0) * 500.0, (1.0 - clip.y) * 350.0);
+
+        assert!(projected.distance(camera.world_to_screen(&canvas, point)) < 1e-3);
+    }
+
+    #[test]
+    fn camera_system_follows_the_tagged_entity() {
+        let world = World::new()
+            .with_entity(test_entity(Some(Tag::Enemy)))
+            .with_entity(Entity::new(Rect::new(600.0, 100.0, 10.0, 10.0)).with_tag(Tag::Player));
+        let mut game = Game::new(world)
+            .with_camera(snapping(vec2(20.0, 20.0)))
+            .with_update_system(camera_system);
+
+        game.update(&Input {
+            dt: DEFAULT_STEP,
+            ..Default::default()
+        });
+
+        assert_eq!(game.state.camera.position, vec2(585.0, 85.0));
+    }
+
+    fn clip(name: AnimationState, frames: u32) -> macroquad::experimental::animation::Animation {
+        macroquad::experimental::animation::Animation {
+            name: name.to_string(),
+            row: 0,
+            frames,
+            fps: 10,
+        }
+    }
+
+    // Run and jump loop on one sheet; hurt and death play once from others.
+    fn test_animator(assets: &mut AssetServer) -> Animator {
+        let sheet = assets.add("sheet", placeholder_texture(1));
+        let hurt = assets.add("hurt", placeholder_texture(2));
+        let death = assets.add("death", placeholder_texture(3));
+        Animator::new(RUN)
+            .with_clip(sheet, clip(RUN, 6), ClipEnd::Loop)
+            .with_clip(sheet, clip(JUMP, 2), ClipEnd::Loop)
+            .with_clip(hurt, clip(HURT, 2), ClipEnd::Return)
+            .with_clip(death, clip(DEATH, 4), ClipEnd::Hold)
+    }
+
+    #[test]
+    fn animator_loops_and_falls_back_to_its_default() {
+        let mut animator = test_animator(&mut AssetServer::new());
+
+        animator.update(0.75);
+        assert_eq!((animator.state(), animator.frame()), (RUN, 1));
+
+        animator.play(JUMP);
+        assert_eq!((animator.state(), animator.frame()), (JUMP, 0));
+
+        // There's no idle clip.
+        animator.play(IDLE);
+        assert_eq!(animator.state(), RUN);
+    }
+
+    #[test]
+    fn one_shots_play_through_then_return() {
+        let mut assets = AssetServer::new();
+        let mut animator = test_animator(&mut assets);
+
+        animator.play(HURT);
+        assert_eq!(animator.texture(), assets.handle::<Texture>("hurt"));
+        animator.update(0.1);
+        animator.play(JUMP);
+        assert_eq!((animator.state(), animator.frame()), (HURT, 1));
+
+        animator.update(0.1);
+        assert_eq!((animator.state(), animator.frame()), (RUN, 0));
+    }
+
+    #[test]
+    fn held_one_shots_stay_on_their_last_frame() {
+        let mut animator = test_animator(&mut AssetServer::new());
+
+        animator.play(HURT);
+        animator.play(DEATH);
+        animator.update(5.0);
+        animator.play(RUN);
+
+        assert_eq!((animator.state(), animator.frame()), (DEATH, 3));
+        assert!(animator.is_finished());
+    }
+
+    #[test]
+    fn animators_without_clips_do_nothing() {
+        let mut assets = AssetServer::new();
+        let texture = assets.add("still.png", placeholder_texture(1));
+        let mut animator = Animator::new(IDLE);
+        animator.play(RUN);
+        animator.update(1.0);
+
+        assert_eq!((animator.state(), animator.frame()), (IDLE, 0));
+        assert!(!animator.is_finished());
+        assert_eq!(animator.texture(), None);
+
+        let mut world = World::new().with_entity(
+            test_entity(None).with_sprite(texture, Animator::new(IDLE).sprite(16, 16)),
+        );
+        world.entities_mut()[0].sprite.as_mut().unwrap().animator = Some(animator);
+        animate_sprites(&mut world, 0.1);
+        assert_eq!(
+            world.entities()[0].sprite.as_ref().unwrap().texture,
+            texture
+        );
+
+        assert!(
+            test_entity(None)
+                .with_animator(Animator::new(IDLE), 16, 16)
+                .sprite
+                .is_none()
+        );
+    }
+
+    #[test]
+    fn looping_clip_without_frames_keeps_a_finite_time() {
+        let mut assets = AssetServer::new();
+        let sheet = assets.add("sheet", placeholder_texture(1));
+        let mut animator = Animator::new(IDLE).with_clip(sheet, clip(IDLE, 0), ClipEnd::Loop);
+
+        animator.update(0.25);
+        animator.update(0.25);
+
+        assert!(animator.elapsed().is_finite());
+        assert_eq!((animator.state(), animator.frame()), (IDLE, 0));
+        assert!(!animator.is_finished());
+    }
+
+    #[test]
+    fn physics_picks_the_looping_state() {
+        let mut physics = Physics::new();
+        assert_eq!(physics_state(&physics), IDLE);
+        physics.velocity.x = 10.0;
+        assert_eq!(physics_state(&physics), RUN);
+        physics.is_grounded = false;
+        assert_eq!(physics_state(&physics), JUMP);
+    }
+
+    #[test]
+    fn animate_sprites_shows_the_state_on_the_sprite() {
+        let mut assets = AssetServer::new();
+        let animator = test_animator(&mut assets);
+        let mut physics = Physics::new();
+        physics.is_grounded = false;
+        let mut world = World::new().with_entity(
+            test_entity(None)
+                .with_animator(animator, 16, 16)
+                .with_physics(physics),
+        );
+
+        animate_sprites(&mut world, 0.15);
+        let sprite = world.entities()[0].sprite.as_ref().unwrap();
+        assert_eq!(sprite.sprite.current_animation(), 1);
+        assert_eq!(sprite.sprite.frame().source_rect.x, 16.0);
+
+        world.entities_mut()[0].sprite.as_mut().unwrap().play(DEATH);
+        animate_sprites(&mut world, 0.0);
+        let sprite = world.entities()[0].sprite.as_ref().unwrap();
+        assert_eq!(sprite.texture, assets.handle::<Texture>("death").unwrap());
+        assert_eq!(sprite.sprite.current_animation(), 3);
+    }
 }
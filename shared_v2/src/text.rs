@@ -0,0 +1,187 @@
+use macroquad::color::{Color, WHITE};
+use macroquad::math::{Rect, Vec2, vec2};
+use macroquad::text::Font;
+
+use crate::{AssetServer, Handle, Renderer};
+
+/// Where a line sits horizontally relative to the position it's drawn at.
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub enum HAlign {
+    #[default]
+    Left,
+    Center,
+    Right,
+}
+
+/// Where text sits vertically relative to the position it's drawn at.
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub enum VAlign {
+    /// The first line's baseline is at the position, like macroquad's own
+    /// `draw_text`.
+    #[default]
+    Baseline,
+    /// The whole block is centred on the position.
+    Middle,
+}
+
+/// How [`render_text`] draws a string.
+#[derive(Debug, Clone, PartialEq)]
+pub struct TextStyle {
+    /// The default font when `None`.
+    pub font: Option<Handle<Font>>,
+    pub size: f32,
+    pub color: Color,
+    pub align: HAlign,
+    pub valign: VAlign,
+    /// Wraps at word boundaries to keep lines narrower than this.
+    pub max_width: Option<f32>,
+    /// Distance between baselines, as a multiple of `size`.
+    pub line_height: f32,
+}
+
+impl TextStyle {
+    pub fn new(size: f32) -> Self {
+        Self {
+            font: None,
+            size,
+            color: WHITE,
+            align: HAlign::Left,
+            valign: VAlign::Baseline,
+            max_width: None,
+            line_height: 1.25,
+        }
+    }
+
+    pub fn with_font(mut self, font: Handle<Font>) -> Self {
+        self.font = Some(font);
+        self
+    }
+
+    pub fn with_color(mut self, color: Color) -> Self {
+        self.color = color;
+        self
+    }
+
+    pub fn aligned(mut self, align: HAlign, valign: VAlign) -> Self {
+        self.align = align;
+        self.valign = valign;
+        self
+    }
+
+    pub fn wrapped(mut self, max_width: f32) -> Self {
+        self.max_width = Some(max_width);
+        self
+    }
+}
+
+/// One line of laid out text, positioned at its left end on the baseline.
+#[derive(Debug, Clone, PartialEq)]
+pub struct TextLine {
+    pub text: String,
+    pub position: Vec2,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct TextLayout {
+    pub lines: Vec<TextLine>,
+    /// What the text covers, from the top of the first line to the bottom of
+    /// the last.
+    pub bounds: Rect,
+}
+
+/// Splits `text` into lines at newlines and, if `max_width` is set, between
+/// words. A word too long to fit gets a line to itself rather than being
+/// broken.
+fn wrap(text: &str, max_width: Option<f32>, width: impl Fn(&str) -> f32) -> Vec<String> {
+    let mut lines = vec![];
+    for paragraph in text.split('\n') {
+        let Some(max_width) = max_width else {
+            lines.push(paragraph.to_string());
+            continue;
+        };
+        let mut line = String::new();
+        for word in paragraph.split_whitespace() {
+            let candidate = if line.is_empty() {
+                word.to_string()
+            } else {
+                format!("{line} {word}")
+            };
+            if !line.is_empty() && width(&candidate) > max_width {
+                lines.push(std::mem::replace(&mut line, word.to_string()));
+            } else {
+                line = candidate;
+            }
+        }
+        lines.push(line);
+    }
+    lines
+}
+
+/// Works out where each line of `text` goes when drawn at `position`,
+/// measuring with the same renderer and font that will draw it.
+pub fn layout_text(
+    text: &str,
+    position: Vec2,
+    style: &TextStyle,
+    assets: &AssetServer,
+    renderer: &dyn Renderer,
+) -> TextLayout {
+    let font = style.font.map(|font| assets.get(font));
+    let measure = |line: &str| renderer.measure_text(line, font, style.size);
+    let lines = wrap(text, style.max_width, |line| measure(line).width);
+
+    // Measuring every line the same way keeps the line spacing even whatever
+    // letters each one holds.
+    let metrics = measure(&lines.concat());
+    let spacing = style.size * style.line_height;
+    let height = spacing * (lines.len() - 1) as f32 + metrics.height;
+    let top = match style.valign {
+        VAlign::Baseline => position.y - metrics.offset_y,
+        VAlign::Middle => position.y - height / 2.0,
+    };
+
+    let mut bounds = Rect::new(position.x, top, 0.0, height);
+    let lines: Vec<TextLine> = lines
+        .into_iter()
+        .enumerate()
+        .map(|(i, line)| {
+            let width = measure(&line).width;
+            let x = match style.align {
+                HAlign::Left => position.x,
+                HAlign::Center => position.x - width / 2.0,
+                HAlign::Right => position.x - width,
+            };
+            bounds = bounds.combine_with(Rect::new(x, top, width, height));
+            TextLine {
+                text: line,
+                position: vec2(x, top + metrics.offset_y + spacing * i as f32),
+            }
+        })
+        .collect();
+
+    TextLayout { lines, bounds }
+}
+
+/// Draws `text` at `position` in the current camera, laid out by
+/// [`layout_text`]. Returns the layout so callers can place things around it.
+pub fn render_text(
+    text: &str,
+    position: Vec2,
+    style: &TextStyle,
+    assets: &AssetServer,
+    renderer: &mut dyn Renderer,
+) -> TextLayout {
+    let layout = layout_text(text, position, style, assets, renderer);
+    let font = style.font.map(|font| assets.get(font));
+    for line in &layout.lines {
+        renderer.draw_text_ex(
+            &line.text,
+            line.position.x,
+            line.position.y,
+            font,
+            style.size,
+            style.color,
+        );
+    }
+    layout
+}
@@ -0,0 +1,188 @@
+use macroquad::color::Color;
+use macroquad::input::{Touch, TouchPhase};
+use macroquad::math::{Vec2, vec2};
+
+use crate::{Binding, InputSource, PadAxis, PadButton, Renderer};
+
+/// The stick's radius, as a share of the screen's shorter side.
+const STICK_SCALE: f32 = 0.12;
+/// Buttons are this much smaller than the stick.
+const BUTTON_SCALE: f32 = 0.6;
+
+/// An on-screen button that presses a pad button while it's touched.
+#[derive(Debug, Clone)]
+pub struct TouchButton {
+    pub button: PadButton,
+    pub label: String,
+    pub centre: Vec2,
+    pub radius: f32,
+    touch: Option<u64>,
+    pressed: bool,
+}
+
+impl TouchButton {
+    pub fn is_held(&self) -> bool {
+        self.touch.is_some()
+    }
+}
+
+/// A virtual stick in the bottom left corner and buttons in the bottom
+/// right, for phones.
+///
+/// They act as a pad: the stick reads as [`PadAxis::LeftX`] and
+/// [`PadAxis::LeftY`], and each button as its [`PadButton`], so the default
+/// [`crate::ActionMap`] bindings already cover them. They stay hidden until
+/// the first touch, so desktop players never see them.
+#[derive(Debug, Clone, Default)]
+pub struct TouchControls {
+    enabled: bool,
+    pub stick_centre: Vec2,
+    pub stick_radius: f32,
+    stick_touch: Option<u64>,
+    stick: Vec2,
+    pub buttons: Vec<TouchButton>,
+}
+
+impl TouchControls {
+    /// Just the stick. Add buttons with [`TouchControls::with_button`].
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds a button, to the left of the ones already there.
+    pub fn with_button(mut self, button: PadButton, label: &str) -> Self {
+        self.buttons.push(TouchButton {
+            button,
+            label: label.to_string(),
+            centre: Vec2::ZERO,
+            radius: 0.0,
+            touch: None,
+            pressed: false,
+        });
+        self
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        self.enabled
+    }
+
+    /// Where the stick is pushed, each axis from -1 to 1 with down positive.
+    pub fn stick(&self) -> Vec2 {
+        self.stick
+    }
+
+    /// Lays the controls out for a `screen` sized in pixels, then reads this
+    /// frame's `touches` (from [`macroquad::input::touches`]).
+    ///
+    /// A touch starting near the stick takes it until it lifts, wherever it
+    /// moves. One starting on a button holds the button.
+    pub fn update(&mut self, touches: &[Touch], screen: Vec2) {
+        self.layout(screen);
+        self.enabled |= !touches.is_empty();
+
+        let lifted = |id: Option<u64>| {
+            id.is_some_and(|id| {
+                touches
+                    .iter()
+                    .find(|t| t.id == id)
+                    .is_none_or(|t| matches!(t.phase, TouchPhase::Ended | TouchPhase::Cancelled))
+            })
+        };
+        if lifted(self.stick_touch) {
+            self.stick_touch = None;
+        }
+        for button in &mut self.buttons {
+            button.pressed = false;
+            if lifted(button.touch) {
+                button.touch = None;
+            }
+        }
+
+        for touch in touches.iter().filter(|t| t.phase == TouchPhase::Started) {
+            let reach = self.stick_radius * 2.0;
+            if self.stick_touch.is_none() && touch.position.distance(self.stick_centre) <= reach {
+                self.stick_touch = Some(touch.id);
+                continue;
+            }
+            let hit = self
+                .buttons
+                .iter_mut()
+                .find(|b| b.touch.is_none() && touch.position.distance(b.centre) <= b.radius);
+            if let Some(button) = hit {
+                button.touch = Some(touch.id);
+                button.pressed = true;
+            }
+        }
+
+        self.stick = self
+            .stick_touch
+            .and_then(|id| touches.iter().find(|t| t.id == id))
+            .map_or(Vec2::ZERO, |touch| {
+                ((touch.position - self.stick_centre) / self.stick_radius).clamp_length_max(1.0)
+            });
+    }
+
+    fn layout(&mut self, screen: Vec2) {
+        let radius = screen.x.min(screen.y) * STICK_SCALE;
+        self.stick_radius = radius;
+        self.stick_centre = vec2(radius * 2.0, screen.y - radius * 2.0);
+
+        let button_radius = radius * BUTTON_SCALE;
+        let mut x = screen.x - radius * 2.0;
+        for button in &mut self.buttons {
+            button.radius = button_radius;
+            button.centre = vec2(x, screen.y - radius * 2.0);
+            x -= button_radius * 3.0;
+        }
+    }
+
+    /// Draws the controls in screen space once they're enabled.
+    pub fn render(&self, renderer: &mut dyn Renderer) {
+        if !self.enabled {
+            return;
+        }
+        renderer.set_default_camera();
+        let faint = Color::new(1.0, 1.0, 1.0, 0.2);
+        let bright = Color::new(1.0, 1.0, 1.0, 0.5);
+
+        let (centre, radius) = (self.stick_centre, self.stick_radius);
+        renderer.draw_circle(centre.x, centre.y, radius, faint);
+        let knob = centre + self.stick * radius;
+        renderer.draw_circle(knob.x, knob.y, radius * 0.4, bright);
+
+        for button in &self.buttons {
+            let color = if button.is_held() { bright } else { faint };
+            renderer.draw_circle(button.centre.x, button.centre.y, button.radius, color);
+            let font_size = button.radius * 0.6;
+            let size = renderer.measure_text(&button.label, None, font_size);
+            renderer.draw_text(
+                &button.label,
+                button.centre.x - size.width / 2.0,
+                button.centre.y - size.height / 2.0 + size.offset_y,
+                font_size,
+                bright,
+            );
+        }
+    }
+}
+
+impl InputSource for TouchControls {
+    fn value(&self, binding: Binding) -> f32 {
+        match binding {
+            Binding::Stick(PadAxis::LeftX) => self.stick.x,
+            Binding::Stick(PadAxis::LeftY) => self.stick.y,
+            Binding::Pad(pad) => {
+                let held = self.buttons.iter().any(|b| b.button == pad && b.is_held());
+                if held { 1.0 } else { 0.0 }
+            }
+            _ => 0.0,
+        }
+    }
+
+    fn pressed(&self, binding: Binding) -> bool {
+        match binding {
+            Binding::Pad(pad) => self.buttons.iter().any(|b| b.button == pad && b.pressed),
+            _ => false,
+        }
+    }
+}
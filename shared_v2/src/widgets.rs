@@ -0,0 +1,531 @@
+use macroquad::color::{Color, GRAY, WHITE, YELLOW};
+use macroquad::input::{
+    KeyCode, MouseButton, TouchPhase, is_key_pressed, is_mouse_button_down,
+    is_mouse_button_pressed, is_mouse_button_released, mouse_position, touches,
+};
+use macroquad::math::{Rect, RectOffset, Vec2, vec2};
+use macroquad::text::Font;
+use macroquad::texture::DrawTextureParams;
+
+use crate::{
+    AssetServer, Gamepads, HAlign, Handle, PadButton, Renderer, TextStyle, Texture, VAlign,
+    render_text,
+};
+
+/// How far the arrow keys move a slider, out of its 0 to 1 range.
+pub const SLIDER_STEP: f32 = 0.1;
+
+/// An image stretched to fit any rect while its borders keep their size.
+#[derive(Debug, Clone, PartialEq)]
+pub struct NineSlice {
+    pub texture: Handle<Texture>,
+    /// The image's size in pixels, which textures can't report without a
+    /// window.
+    pub size: Vec2,
+    /// How much of each edge, in pixels, is border rather than middle.
+    pub border: RectOffset,
+}
+
+impl NineSlice {
+    pub fn new(texture: Handle<Texture>, size: Vec2, border: RectOffset) -> Self {
+        Self {
+            texture,
+            size,
+            border,
+        }
+    }
+
+    pub fn draw(
+        &self,
+        rect: Rect,
+        color: Color,
+        assets: &AssetServer,
+        renderer: &mut dyn Renderer,
+    ) {
+        let b = self.border;
+        // Borders shrink in proportion when the rect is too small for them.
+        let sx = (rect.w / (b.left + b.right)).min(1.0);
+        let sy = (rect.h / (b.top + b.bottom)).min(1.0);
+        let source_x = [0.0, b.left, self.size.x - b.right, self.size.x];
+        let source_y = [0.0, b.top, self.size.y - b.bottom, self.size.y];
+        let dest_x = [
+            rect.x,
+            rect.x + b.left * sx,
+            rect.right() - b.right * sx,
+            rect.right(),
+        ];
+        let dest_y = [
+            rect.y,
+            rect.y + b.top * sy,
+            rect.bottom() - b.bottom * sy,
+            rect.bottom(),
+        ];
+
+        let texture = assets.get(self.texture);
+        for row in 0..3 {
+            for column in 0..3 {
+                let w = dest_x[column + 1] - dest_x[column];
+                let h = dest_y[row + 1] - dest_y[row];
+                if w <= 0.0 || h <= 0.0 {
+                    continue;
+                }
+                renderer.draw_texture_ex(
+                    texture,
+                    dest_x[column],
+                    dest_y[row],
+                    color,
+                    DrawTextureParams {
+                        dest_size: Some(vec2(w, h)),
+                        source: Some(Rect::new(
+                            source_x[column],
+                            source_y[row],
+                            source_x[column + 1] - source_x[column],
+                            source_y[row + 1] - source_y[row],
+                        )),
+                        ..Default::default()
+                    },
+                );
+            }
+        }
+    }
+}
+
+/// How a [`Ui`] looks. Anything without art is drawn as plain rectangles.
+#[derive(Debug, Clone, PartialEq)]
+pub struct UiSkin {
+    pub panel: Option<NineSlice>,
+    pub button: Option<NineSlice>,
+    pub button_pressed: Option<NineSlice>,
+    pub font: Option<Handle<Font>>,
+    pub text_size: f32,
+    pub text_color: Color,
+    /// Fills panels and buttons that have no art, and the empty part of
+    /// sliders.
+    pub fill_color: Color,
+    /// The outline around the focused widget, and the filled part of
+    /// sliders and toggles.
+    pub accent_color: Color,
+}
+
+impl Default for UiSkin {
+    fn default() -> Self {
+        Self {
+            panel: None,
+            button: None,
+            button_pressed: None,
+            font: None,
+            text_size: 24.0,
+            text_color: WHITE,
+            fill_color: GRAY,
+            accent_color: YELLOW,
+        }
+    }
+}
+
+/// One frame of pointer and navigation input for a [`Ui`].
+///
+/// Mouse and touch both come through as the pointer, and the keys as
+/// navigation, so the widgets don't care which the player used.
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct UiInput {
+    pub pointer: Option<Vec2>,
+    /// Went down this frame.
+    pub pointer_pressed: bool,
+    pub pointer_down: bool,
+    /// Came up this frame.
+    pub pointer_released: bool,
+    pub up: bool,
+    pub down: bool,
+    pub left: bool,
+    pub right: bool,
+    /// Clicks the focused button or flips the focused toggle.
+    pub activate: bool,
+}
+
+impl UiInput {
+    /// Reads the keyboard, then the first touch, or the mouse if there
+    /// isn't one. Arrows or WASD navigate, and Enter or Space activates.
+    pub fn from_macroquad() -> Self {
+        let pressed = |keys: &[KeyCode]| keys.iter().any(|&key| is_key_pressed(key));
+        let mut input = UiInput {
+            up: pressed(&[KeyCode::Up, KeyCode::W]),
+            down: pressed(&[KeyCode::Down, KeyCode::S]),
+            left: pressed(&[KeyCode::Left, KeyCode::A]),
+            right: pressed(&[KeyCode::Right, KeyCode::D]),
+            activate: pressed(&[KeyCode::Enter, KeyCode::KpEnter, KeyCode::Space]),
+            ..Default::default()
+        };
+
+        // macroquad also reports touches as mouse clicks, so reading both
+        // would count each tap twice.
+        if let Some(touch) = touches().first() {
+            input.pointer = Some(touch.position);
+            input.pointer_pressed = touch.phase == TouchPhase::Started;
+            input.pointer_released =
+                matches!(touch.phase, TouchPhase::Ended | TouchPhase::Cancelled);
+            input.pointer_down = !input.pointer_released;
+        } else {
+            input.pointer = Some(mouse_position().into());
+            input.pointer_pressed = is_mouse_button_pressed(MouseButton::Left);
+            input.pointer_down = is_mouse_button_down(MouseButton::Left);
+            input.pointer_released = is_mouse_button_released(MouseButton::Left);
+        }
+        input
+    }
+
+    /// Adds the d-pad to navigation, and South to activating.
+    pub fn with_gamepads(mut self, pads: &Gamepads) -> Self {
+        self.up |= pads.button_pressed(PadButton::DPadUp);
+        self.down |= pads.button_pressed(PadButton::DPadDown);
+        self.left |= pads.button_pressed(PadButton::DPadLeft);
+        self.right |= pads.button_pressed(PadButton::DPadRight);
+        self.activate |= pads.button_pressed(PadButton::South);
+        self
+    }
+}
+
+/// What a widget is, and what it calls when used. Callbacks get the same
+/// context [`Ui::update`] was given.
+pub enum WidgetKind<C> {
+    Panel,
+    Label(String),
+    Button {
+        text: String,
+        on_click: fn(&mut C),
+    },
+    /// Holds a value from 0 to 1.
+    Slider {
+        text: String,
+        value: f32,
+        on_change: fn(&mut C, f32),
+    },
+    Toggle {
+        text: String,
+        on: bool,
+        on_change: fn(&mut C, bool),
+    },
+}
+
+pub struct Widget<C> {
+    /// Where the widget sits on screen, in pixels.
+    pub rect: Rect,
+    pub kind: WidgetKind<C>,
+}
+
+impl<C> Widget<C> {
+    /// Panels and labels are only looked at.
+    pub fn is_focusable(&self) -> bool {
+        !matches!(self.kind, WidgetKind::Panel | WidgetKind::Label(_))
+    }
+}
+
+/// The right half of a slider, where its value is shown and set. The label
+/// takes the left half.
+fn slider_track(rect: Rect) -> Rect {
+    Rect::new(rect.center().x, rect.y, rect.w / 2.0, rect.h)
+}
+
+/// A retained set of widgets, laid out in screen pixels and drawn over
+/// whatever the game's camera is.
+///
+/// Widgets are kept in the order they were added, which is both the draw
+/// order and the order keyboard focus moves through them. `C` is whatever
+/// the callbacks need to change, such as the game's settings.
+pub struct Ui<C> {
+    pub widgets: Vec<Widget<C>>,
+    pub skin: UiSkin,
+    /// Index of the widget keyboard input goes to.
+    pub focus: Option<usize>,
+    /// The widget the pointer went down on, until it comes back up.
+    pressed: Option<usize>,
+    last_pointer: Option<Vec2>,
+}
+
+impl<C> Ui<C> {
+    pub fn new(skin: UiSkin) -> Self {
+        Self {
+            widgets: vec![],
+            skin,
+            focus: None,
+            pressed: None,
+            last_pointer: None,
+        }
+    }
+
+    fn with(mut self, rect: Rect, kind: WidgetKind<C>) -> Self {
+        self.widgets.push(Widget { rect, kind });
+        self
+    }
+
+    pub fn with_panel(self, rect: Rect) -> Self {
+        self.with(rect, WidgetKind::Panel)
+    }
+
+    pub fn with_label(self, rect: Rect, text: &str) -> Self {
+        self.with(rect, WidgetKind::Label(text.to_string()))
+    }
+
+    pub fn with_button(self, rect: Rect, text: &str, on_click: fn(&mut C)) -> Self {
+        self.with(
+            rect,
+            WidgetKind::Button {
+                text: text.to_string(),
+                on_click,
+            },
+        )
+    }
+
+    pub fn with_slider(
+        self,
+        rect: Rect,
+        text: &str,
+        value: f32,
+        on_change: fn(&mut C, f32),
+    ) -> Self {
+        self.with(
+            rect,
+            WidgetKind::Slider {
+                text: text.to_string(),
+                value: value.clamp(0.0, 1.0),
+                on_change,
+            },
+        )
+    }
+
+    pub fn with_toggle(
+        self,
+        rect: Rect,
+        text: &str,
+        on: bool,
+        on_change: fn(&mut C, bool),
+    ) -> Self {
+        self.with(
+            rect,
+            WidgetKind::Toggle {
+                text: text.to_string(),
+                on,
+                on_change,
+            },
+        )
+    }
+
+    /// The topmost focusable widget under `point`.
+    fn widget_at(&self, point: Vec2) -> Option<usize> {
+        (0..self.widgets.len())
+            .rev()
+            .find(|&i| self.widgets[i].is_focusable() && self.widgets[i].rect.contains(point))
+    }
+
+    /// Moves focus to the next or previous focusable widget, wrapping at the
+    /// ends. With nothing focused, it goes to the first or last.
+    fn move_focus(&mut self, forwards: bool) {
+        let focusable: Vec<usize> = (0..self.widgets.len())
+            .filter(|&i| self.widgets[i].is_focusable())
+            .collect();
+        if focusable.is_empty() {
+            return;
+        }
+        let current = self
+            .focus
+            .and_then(|focus| focusable.iter().position(|&i| i == focus));
+        let next = match (current, forwards) {
+            (None, true) => 0,
+            (None, false) => focusable.len() - 1,
+            (Some(i), true) => (i + 1) % focusable.len(),
+            (Some(i), false) => (i + focusable.len() - 1) % focusable.len(),
+        };
+        self.focus = Some(focusable[next]);
+    }
+
+    fn activate(&mut self, index: usize, context: &mut C) {
+        match &mut self.widgets[index].kind {
+            WidgetKind::Button { on_click, .. } => on_click(context),
+            WidgetKind::Toggle { on, on_change, .. } => {
+                *on = !*on;
+                on_change(context, *on);
+            }
+            _ => {}
+        }
+    }
+
+    fn set_slider(&mut self, index: usize, new_value: f32, context: &mut C) {
+        if let WidgetKind::Slider {
+            value, on_change, ..
+        } = &mut self.widgets[index].kind
+        {
+            let new_value = new_value.clamp(0.0, 1.0);
+            if new_value != *value {
+                *value = new_value;
+                on_change(context, new_value);
+            }
+        }
+    }
+
+    fn slider_value(&self, index: usize) -> Option<f32> {
+        match self.widgets[index].kind {
+            WidgetKind::Slider { value, .. } => Some(value),
+            _ => None,
+        }
+    }
+
+    /// Whether the pointer is down on the widget at `index`.
+    pub fn is_pressed(&self, index: usize) -> bool {
+        self.pressed == Some(index)
+    }
+
+    /// Applies one frame of input, calling back into `context` for every
+    /// widget that was used.
+    ///
+    /// Buttons click when the pointer comes up over the one it went down on,
+    /// so dragging off a button cancels it. Sliders follow the pointer while
+    /// it's held on them.
+    pub fn update(&mut self, input: &UiInput, context: &mut C) {
+        if let Some(pointer) = input.pointer {
+            let hovered = self.widget_at(pointer);
+            // A mouse resting over a widget shouldn't take focus back from
+            // the keyboard every frame.
+            let moved = self.last_pointer != Some(pointer);
+            self.last_pointer = Some(pointer);
+            if hovered.is_some() && (moved || input.pointer_pressed) {
+                self.focus = hovered;
+            }
+            if input.pointer_pressed {
+                // A slider's label only focuses it; its track takes the drag.
+                self.pressed = hovered.filter(|&i| {
+                    self.slider_value(i).is_none()
+                        || slider_track(self.widgets[i].rect).contains(pointer)
+                });
+            }
+            if let Some(pressed) = self.pressed
+                && self.slider_value(pressed).is_some()
+            {
+                let track = slider_track(self.widgets[pressed].rect);
+                self.set_slider(pressed, (pointer.x - track.x) / track.w, context);
+            }
+            if input.pointer_released
+                && let Some(pressed) = self.pressed.take()
+                && hovered == Some(pressed)
+            {
+                self.activate(pressed, context);
+            }
+        }
+        if input.pointer_released || !input.pointer_down && !input.pointer_pressed {
+            self.pressed = None;
+        }
+
+        if input.down {
+            self.move_focus(true);
+        }
+        if input.up {
+            self.move_focus(false);
+        }
+        let Some(focus) = self.focus else {
+            return;
+        };
+        if let Some(value) = self.slider_value(focus) {
+            if input.left {
+                self.set_slider(focus, value - SLIDER_STEP, context);
+            }
+            if input.right {
+                self.set_slider(focus, value + SLIDER_STEP, context);
+            }
+        }
+        if input.activate {
+            self.activate(focus, context);
+        }
+    }
+
+    fn draw_frame(
+        &self,
+        art: Option<&NineSlice>,
+        rect: Rect,
+        assets: &AssetServer,
+        renderer: &mut dyn Renderer,
+    ) {
+        match art {
+            Some(art) => art.draw(rect, WHITE, assets, renderer),
+            None => renderer.draw_rectangle(rect.x, rect.y, rect.w, rect.h, self.skin.fill_color),
+        }
+    }
+
+    fn draw_label(
+        &self,
+        text: &str,
+        rect: Rect,
+        align: HAlign,
+        assets: &AssetServer,
+        renderer: &mut dyn Renderer,
+    ) {
+        let mut style = TextStyle::new(self.skin.text_size)
+            .with_color(self.skin.text_color)
+            .aligned(align, VAlign::Middle);
+        style.font = self.skin.font;
+        let padding = self.skin.text_size / 2.0;
+        let x = match align {
+            HAlign::Left => rect.x + padding,
+            HAlign::Center => rect.center().x,
+            HAlign::Right => rect.right() - padding,
+        };
+        render_text(text, vec2(x, rect.center().y), &style, assets, renderer);
+    }
+
+    /// Draws every widget in screen space. Leaves the default camera set,
+    /// so draw the UI after the game.
+    pub fn render(&self, assets: &AssetServer, renderer: &mut dyn Renderer) {
+        renderer.set_default_camera();
+        let skin = &self.skin;
+        for (i, widget) in self.widgets.iter().enumerate() {
+            let rect = widget.rect;
+            match &widget.kind {
+                WidgetKind::Panel => self.draw_frame(skin.panel.as_ref(), rect, assets, renderer),
+                WidgetKind::Label(text) => {
+                    self.draw_label(text, rect, HAlign::Left, assets, renderer);
+                }
+                WidgetKind::Button { text, .. } => {
+                    let art = if self.is_pressed(i) {
+                        skin.button_pressed.as_ref().or(skin.button.as_ref())
+                    } else {
+                        skin.button.as_ref()
+                    };
+                    self.draw_frame(art, rect, assets, renderer);
+                    self.draw_label(text, rect, HAlign::Center, assets, renderer);
+                }
+                WidgetKind::Slider { text, value, .. } => {
+                    let track = slider_track(rect);
+                    renderer.draw_rectangle(track.x, track.y, track.w, track.h, skin.fill_color);
+                    renderer.draw_rectangle(
+                        track.x,
+                        track.y,
+                        track.w * value,
+                        track.h,
+                        skin.accent_color,
+                    );
+                    self.draw_label(text, rect, HAlign::Left, assets, renderer);
+                }
+                WidgetKind::Toggle { text, on, .. } => {
+                    self.draw_frame(skin.button.as_ref(), rect, assets, renderer);
+                    let size = rect.h / 2.0;
+                    let check =
+                        Rect::new(rect.right() - size * 1.5, rect.y + size / 2.0, size, size);
+                    let color = if *on {
+                        skin.accent_color
+                    } else {
+                        skin.fill_color
+                    };
+                    renderer.draw_rectangle(check.x, check.y, check.w, check.h, color);
+                    self.draw_label(text, rect, HAlign::Left, assets, renderer);
+                }
+            }
+            if self.focus == Some(i) {
+                renderer.draw_rectangle_lines(
+                    rect.x,
+                    rect.y,
+                    rect.w,
+                    rect.h,
+                    2.0,
+                    skin.accent_color,
+                );
+            }
+        }
+    }
+}